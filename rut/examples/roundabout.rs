@@ -8,14 +8,14 @@ use winit::{
 };
 use ascetic_vis::{
     Scene, Theme, Style, Stroke, Fill, Variation, Group, CrumbItem,
-    Color, UnitPoint,
-    kurbo::{Line, Rect, Circle, TranslateScale},
+    Color, UnitPoint, VisError,
+    kurbo::{Line, Rect, Circle, Affine},
 };
 use ascetic_rut::{Gui, Logger};
 
 const SCENE_NAME: &str = "scene";
 
-fn roundabout_theme() -> Theme {
+fn roundabout_theme() -> Result<Theme, VisError> {
     let frame_stops = vec![Color::WHITE, Color::BLACK];
     let node_gradient_stops = vec![Color::WHITE, Color::rgb8(0, 0x60, 0)];
     let node_dark_gradient_stops = vec![Color::BLACK, Color::rgb8(0, 0x80, 0xff)];
@@ -70,6 +70,7 @@ fn roundabout_theme() -> Theme {
         .with_fills(fills)
         .with_variations(variations)
         .with_styles(styles)
+        .build()
 }
 
 fn roundabout_scene(theme: &Theme) -> Scene {
@@ -111,13 +112,13 @@ fn roundabout_scene(theme: &Theme) -> Scene {
     let nodes = scene.add_grouped_crumb_items(
         node_positions
             .into_iter()
-            .map(|(x, y)| CrumbItem(node, TranslateScale::translate((x, y).into()), node_style)),
+            .map(|(x, y)| CrumbItem(node, Affine::translate((x, y)), node_style)),
     );
 
     let tokens = scene.add_grouped_crumb_items(
         token_positions
             .into_iter()
-            .map(|(x, y)| CrumbItem(token, TranslateScale::translate((x, y).into()), token_style)),
+            .map(|(x, y)| CrumbItem(token, Affine::translate((x, y)), token_style)),
     );
 
     scene.add_root(
@@ -171,7 +172,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .with_decorations(true)
         .with_transparent(true);
     let mut gui = Gui::new(&event_loop, &window_builder)?;
-    let mut theme = roundabout_theme();
+    let mut theme = roundabout_theme()?;
     let mut scene = roundabout_scene(&theme);
 
     event_loop.run(move |event, _, control_flow| match gui.update(event, &mut scene, &mut theme) {
@@ -8,7 +8,7 @@ use winit::{
     monitor::MonitorHandle,
 };
 use pixels::wgpu;
-use ascetic_vis::{Scene, Theme};
+use ascetic_vis::{Scene, Theme, VariationStack};
 use crate::{Action, Scheduler, Renderer, Raster, Frame, Pan, Zoom, Keyboard, Mouse};
 
 #[derive(Debug)]
@@ -22,7 +22,7 @@ pub struct Gui {
     pan:                Pan,
     zoom:               Zoom,
     fullscreen_monitor: MonitorHandle,
-    is_dark:            bool,
+    variations:         VariationStack,
     renderer:           Renderer,
     raster:             Raster,
     frame:              Frame,
@@ -61,7 +61,7 @@ impl Gui {
             .with_ins(vec![1.5, 2., 2.75, 3.75, 4.5, 6.])
             .with_outs(vec![0.75, 0.5, 0.3, 0.1]);
 
-        let is_dark = false;
+        let variations = VariationStack::new();
 
         let PhysicalSize { width: win_width, height: win_height } = window.inner_size();
         let renderer = Renderer::new(Self::DEFAULT_RENDER_SIZE, Self::DEFAULT_RENDER_MARGIN);
@@ -80,7 +80,7 @@ impl Gui {
             pan,
             zoom,
             fullscreen_monitor,
-            is_dark,
+            variations,
             renderer,
             raster,
             frame,
@@ -135,14 +135,12 @@ impl Gui {
     }
 
     fn change_theme_variation(&mut self, theme: &mut Theme) -> Result<(), crate::Error> {
-        if self.is_dark {
-            theme.use_original_variation();
-            self.is_dark = false;
-        } else {
-            theme.use_variation(Some("dark"));
-            self.is_dark = true;
+        if !self.variations.remove(Some("dark")) {
+            self.variations.push(Some("dark"), 0);
         }
 
+        theme.use_variation_stack(&self.variations);
+
         self.scheduler.enroll(Action::RenderScene);
 
         Ok(())
@@ -17,7 +17,7 @@ use ascetic_vis::{
 
 const SCENE_NAME: &str = "scene";
 
-fn roundabout_theme() -> Theme {
+fn roundabout_theme() -> Result<Theme, VisError> {
     let frame_stops = vec![Color::WHITE, Color::rgb8(0xd0, 0xd0, 0xd0)];
     let node_gradient_stops = vec![Color::WHITE, Color::rgb8(0, 0x60, 0)];
     let node_dark_gradient_stops = vec![Color::BLACK, Color::rgb8(0, 0x80, 0xff)];
@@ -112,6 +112,7 @@ fn roundabout_theme() -> Theme {
         .with_fills(fills)
         .with_variations(variations)
         .with_styles(styles)
+        .build()
 }
 
 fn roundabout_scene(theme: &Theme) -> Result<Scene, VisError> {
@@ -316,7 +317,7 @@ fn roundabout_scene(theme: &Theme) -> Result<Scene, VisError> {
     scene.set_z_index(tokens, 1)?;
 
     let all_crumbs: Vec<_> = scene
-        .all_crumbs(kurbo::TranslateScale::scale(1.0))?
+        .all_crumbs(kurbo::Affine::IDENTITY)?
         .map(|(level, CrumbItem(crumb_id, ..))| {
             if let Some(crumb) = scene.get_crumb(crumb_id) {
                 let variant = match crumb {
@@ -508,7 +509,7 @@ impl App {
 
 fn main() -> Result<(), Box<dyn Error>> {
     let app = App::new()?;
-    let mut theme = roundabout_theme();
+    let mut theme = roundabout_theme()?;
     let mut scene = roundabout_scene(&theme)?;
 
     if app.verbosity > 1 {
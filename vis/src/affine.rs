@@ -0,0 +1,31 @@
+use kurbo::Affine;
+#[cfg(any(feature = "svg", feature = "tiny", feature = "fvg"))]
+use kurbo::{TranslateScale, Vec2};
+
+/// Returns the factor by which an affine transformation scales
+/// lengths on average, i.e. the square root of its area scaling.
+///
+/// This is applied to stroke widths, font sizes, etc., which are
+/// scalars, hence can't follow non-uniform scaling or skew.
+#[inline]
+pub(crate) fn get_scale_factor(ts: Affine) -> f64 {
+    ts.determinant().abs().sqrt()
+}
+
+/// Converts an affine transformation into a [`TranslateScale`],
+/// unless the former rotates, skews, mirrors or scales non-uniformly.
+///
+/// Shapes which may be transformed without being converted to paths,
+/// like rectangles and circles, are transformed directly only if
+/// this succeeds.
+#[cfg(any(feature = "svg", feature = "tiny", feature = "fvg"))]
+pub(crate) fn as_translate_scale(ts: Affine) -> Option<TranslateScale> {
+    let [a, b, c, d, e, f] = ts.as_coeffs();
+
+    #[allow(clippy::float_cmp)]
+    if a > 0.0 && a == d && b == 0.0 && c == 0.0 {
+        Some(TranslateScale::new(Vec2::new(e, f), a))
+    } else {
+        None
+    }
+}
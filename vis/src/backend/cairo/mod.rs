@@ -2,6 +2,7 @@ use std::{mem, fmt, error::Error};
 use piet::ImageFormat;
 use piet_cairo::CairoRenderContext;
 use cairo::{Context, Format, ImageSurface};
+use crate::{Stroke, LineCap, LineJoin};
 
 #[derive(Debug)]
 pub struct CairoError(cairo::Error);
@@ -67,3 +68,30 @@ impl BitmapDevice {
         Ok(raw_data)
     }
 }
+
+pub trait AsPietStrokeStyle {
+    fn as_piet_stroke_style(&self) -> piet::StrokeStyle;
+}
+
+impl AsPietStrokeStyle for Stroke {
+    fn as_piet_stroke_style(&self) -> piet::StrokeStyle {
+        let mut style = piet::StrokeStyle::new();
+
+        style.set_line_cap(match self.get_cap() {
+            LineCap::Butt => piet::LineCap::Butt,
+            LineCap::Round => piet::LineCap::Round,
+            LineCap::Square => piet::LineCap::Square,
+        });
+        style.set_line_join(match self.get_join() {
+            LineJoin::Miter => piet::LineJoin::Miter { limit: self.get_miter_limit() },
+            LineJoin::Round => piet::LineJoin::Round,
+            LineJoin::Bevel => piet::LineJoin::Bevel,
+        });
+
+        if self.is_dashed() {
+            style.set_dash(self.get_dash_array().to_vec(), self.get_dash_offset());
+        }
+
+        style
+    }
+}
@@ -1,9 +1,11 @@
+use std::collections::HashMap;
 use rgb::ComponentBytes;
-use kurbo::{Point, Line, Rect, RoundedRect, Circle, Arc, BezPath, Shape, TranslateScale, Size};
+use kurbo::{Point, Line, Rect, RoundedRect, Circle, Arc, BezPath, PathEl, Shape, Affine, Size};
 use femtovg as fvg;
 use crate::{
-    Scene, Theme, Style, StyleId, Stroke, Fill, Color, GradientStop, Gradient, Crumb, CrumbItem,
-    Crumbling, TextLabel, VisError,
+    Scene, PaintStep, Theme, Style, StyleId, Stroke, LineCap, LineJoin, Fill, Color, GradientStop,
+    Gradient, Hatch, Marker, Crumb, Crumbling, TextLabel, FontLibrary, BlendMode, VisError,
+    affine::{get_scale_factor, as_translate_scale},
 };
 
 pub trait Renderable<T: fvg::Renderer> {
@@ -17,6 +19,21 @@ pub trait Renderable<T: fvg::Renderer> {
     where
         S: Into<Size>,
         M: Into<Size>;
+
+    /// Same as [`Renderable::render_as_fvg()`], but fonts needed for
+    /// rendering text are added to the `canvas` only once, and
+    /// remembered in `fonts` for subsequent calls.
+    fn render_as_fvg_with_fonts<S, M>(
+        &self,
+        canvas: &mut fvg::Canvas<T>,
+        fonts: &mut FvgFonts,
+        theme: &Theme,
+        out_size: S,
+        out_margin: M,
+    ) -> Result<(), VisError>
+    where
+        S: Into<Size>,
+        M: Into<Size>;
 }
 
 impl<T: fvg::Renderer> Renderable<T> for Scene {
@@ -31,22 +48,80 @@ impl<T: fvg::Renderer> Renderable<T> for Scene {
         S: Into<Size>,
         M: Into<Size>,
     {
+        self.render_as_fvg_with_fonts(canvas, &mut FvgFonts::new(), theme, out_size, out_margin)
+    }
+
+    fn render_as_fvg_with_fonts<S, M>(
+        &self,
+        canvas: &mut fvg::Canvas<T>,
+        fonts: &mut FvgFonts,
+        theme: &Theme,
+        out_size: S,
+        out_margin: M,
+    ) -> Result<(), VisError>
+    where
+        S: Into<Size>,
+        M: Into<Size>,
+    {
+        // Fonts are loaded first, so that labels are measured with
+        // them when fitting the content.
+        theme.load_fonts()?;
+
         let out_size = out_size.into();
-        let out_margin = out_margin.into();
-        let out_scale = ((out_size.width - 2. * out_margin.width) / self.get_size().width)
-            .min((out_size.height - 2. * out_margin.height) / self.get_size().height);
-        let root_ts =
-            TranslateScale::translate(out_margin.to_vec2()) * TranslateScale::scale(out_scale);
+        let root_ts = self.get_root_ts(theme, out_size, out_margin.into())?;
 
         canvas.set_size(out_size.width as u32, out_size.height as u32, 1.0);
 
         theme.render_background_as_fvg(canvas);
 
-        for (_level, CrumbItem(crumb_id, ts, style_id)) in self.all_visible_crumbs(root_ts)? {
-            if let Some(crumb) = self.get_crumb(crumb_id) {
-                crumb.render_as_fvg_with_style(canvas, ts, style_id, theme)?;
-            } else {
-                return Err(VisError::crumb_missing_for_id(crumb_id))
+        // Note: groups are painted directly onto the canvas, so
+        // only effects not needing offscreen compositing are
+        // supported, i.e. clipping to axis-aligned rectangles.
+        for step in self.visible_paint_steps_in(out_size.to_rect(), root_ts, theme)? {
+            match step {
+                PaintStep::BeginGroup(group, ts) => {
+                    if matches!(group.get_opacity(), Some(opacity) if opacity < 1.0) {
+                        return Err(VisError::unsupported_group_effect("opacity"))
+                    }
+
+                    if let Some(mode) = group.get_blend_mode() {
+                        if mode != BlendMode::Normal {
+                            return Err(VisError::unsupported_group_effect(format!(
+                                "blend mode '{}'",
+                                mode.as_css_name()
+                            )))
+                        }
+                    }
+
+                    canvas.save();
+
+                    if let Some(clip) = group.get_clip() {
+                        let rect = match (clip, as_translate_scale(ts)) {
+                            (Crumb::Rect(rect), Some(ts)) => ts * *rect,
+                            _ => {
+                                return Err(VisError::unsupported_group_effect(
+                                    "clip by a shape other than an axis-aligned rectangle",
+                                ))
+                            }
+                        };
+
+                        canvas.intersect_scissor(
+                            rect.x0 as f32,
+                            rect.y0 as f32,
+                            rect.width() as f32,
+                            rect.height() as f32,
+                        );
+                    }
+                }
+                PaintStep::Crumb(Crumb::Label(label), ts, style_id) => {
+                    label.render_text_as_fvg(canvas, fonts, ts, style_id, theme)?;
+                }
+                PaintStep::Crumb(crumb, ts, style_id) => {
+                    crumb.render_as_fvg_with_style(canvas, ts, style_id, theme)?;
+                }
+                PaintStep::EndGroup => {
+                    canvas.restore();
+                }
             }
         }
 
@@ -59,16 +134,37 @@ impl<T: fvg::Renderer> Renderable<T> for Scene {
 }
 
 pub trait AsPath {
-    fn as_fvg_path(&self, ts: TranslateScale) -> fvg::Path;
+    fn as_fvg_path(&self, ts: Affine) -> fvg::Path;
 
-    fn as_fvg_path_and_points(&self, ts: TranslateScale) -> (fvg::Path, Vec<Point>) {
+    fn as_fvg_path_and_points(&self, ts: Affine) -> (fvg::Path, Vec<Point>) {
         (self.as_fvg_path(ts), Vec::new())
     }
+
+    /// Returns the lines of a `hatch` clipped to the interior of
+    /// this shape transformed by `ts`.  Default implementation
+    /// returns an empty path, as for shapes enclosing no area.
+    #[allow(unused_variables)]
+    fn as_fvg_hatch_path(&self, ts: Affine, hatch: &Hatch) -> fvg::Path {
+        fvg::Path::new()
+    }
+}
+
+/// Returns the lines of a `hatch` clipped to the interior of a
+/// `shape` transformed by `ts`, as a path.
+fn shape_hatch_path<S: Shape>(shape: &S, ts: Affine, hatch: &Hatch) -> fvg::Path {
+    let mut path = fvg::Path::new();
+
+    for Line { p0, p1 } in hatch_lines(shape, ts, hatch) {
+        path.move_to(p0.x as f32, p0.y as f32);
+        path.line_to(p1.x as f32, p1.y as f32);
+    }
+
+    path
 }
 
 impl AsPath for Crumb {
     #[inline]
-    fn as_fvg_path(&self, ts: TranslateScale) -> fvg::Path {
+    fn as_fvg_path(&self, ts: Affine) -> fvg::Path {
         match self {
             Crumb::Line(line) => line.as_fvg_path(ts),
             Crumb::Rect(rect) => rect.as_fvg_path(ts),
@@ -82,7 +178,7 @@ impl AsPath for Crumb {
     }
 
     #[inline]
-    fn as_fvg_path_and_points(&self, ts: TranslateScale) -> (fvg::Path, Vec<Point>) {
+    fn as_fvg_path_and_points(&self, ts: Affine) -> (fvg::Path, Vec<Point>) {
         match self {
             Crumb::Line(line) => line.as_fvg_path_and_points(ts),
             Crumb::Rect(rect) => rect.as_fvg_path_and_points(ts),
@@ -94,17 +190,28 @@ impl AsPath for Crumb {
             Crumb::Label(label) => label.as_fvg_path_and_points(ts),
         }
     }
+
+    #[inline]
+    fn as_fvg_hatch_path(&self, ts: Affine, hatch: &Hatch) -> fvg::Path {
+        match self {
+            Crumb::Rect(rect) => rect.as_fvg_hatch_path(ts, hatch),
+            Crumb::RoundedRect(rr) => rr.as_fvg_hatch_path(ts, hatch),
+            Crumb::Circle(circ) => circ.as_fvg_hatch_path(ts, hatch),
+            Crumb::Arc(arc) => arc.as_fvg_hatch_path(ts, hatch),
+            Crumb::Path(path) => path.as_fvg_hatch_path(ts, hatch),
+            Crumb::Line(_) | Crumb::Pin(_) | Crumb::Label(_) => fvg::Path::new(),
+        }
+    }
 }
 
 impl AsPath for Line {
     #[inline]
-    fn as_fvg_path(&self, ts: TranslateScale) -> fvg::Path {
+    fn as_fvg_path(&self, ts: Affine) -> fvg::Path {
         self.as_fvg_path_and_points(ts).0
     }
 
-    fn as_fvg_path_and_points(&self, ts: TranslateScale) -> (fvg::Path, Vec<Point>) {
-        let p0 = ts * self.p0;
-        let p1 = ts * self.p1;
+    fn as_fvg_path_and_points(&self, ts: Affine) -> (fvg::Path, Vec<Point>) {
+        let Line { p0, p1 } = ts * *self;
         let mut path = fvg::Path::new();
 
         path.move_to(p0.x as f32, p0.y as f32);
@@ -115,7 +222,12 @@ impl AsPath for Line {
 }
 
 impl AsPath for Rect {
-    fn as_fvg_path(&self, ts: TranslateScale) -> fvg::Path {
+    fn as_fvg_path(&self, ts: Affine) -> fvg::Path {
+        let ts = if let Some(ts) = as_translate_scale(ts) {
+            ts
+        } else {
+            return self.to_path(0.1).as_fvg_path(ts)
+        };
         let rect = ts * *self;
         let mut path = fvg::Path::new();
 
@@ -123,10 +235,20 @@ impl AsPath for Rect {
 
         path
     }
+
+    #[inline]
+    fn as_fvg_hatch_path(&self, ts: Affine, hatch: &Hatch) -> fvg::Path {
+        shape_hatch_path(self, ts, hatch)
+    }
 }
 
 impl AsPath for RoundedRect {
-    fn as_fvg_path(&self, ts: TranslateScale) -> fvg::Path {
+    fn as_fvg_path(&self, ts: Affine) -> fvg::Path {
+        let ts = if let Some(ts) = as_translate_scale(ts) {
+            ts
+        } else {
+            return self.to_path(0.1).as_fvg_path(ts)
+        };
         let rr = ts * *self;
 
         if let Some(radius) = rr.radii().as_single_radius() {
@@ -143,13 +265,23 @@ impl AsPath for RoundedRect {
 
             path
         } else {
-            self.rect().as_fvg_path(ts)
+            self.rect().as_fvg_path(ts.into())
         }
     }
+
+    #[inline]
+    fn as_fvg_hatch_path(&self, ts: Affine, hatch: &Hatch) -> fvg::Path {
+        shape_hatch_path(self, ts, hatch)
+    }
 }
 
 impl AsPath for Circle {
-    fn as_fvg_path(&self, ts: TranslateScale) -> fvg::Path {
+    fn as_fvg_path(&self, ts: Affine) -> fvg::Path {
+        let ts = if let Some(ts) = as_translate_scale(ts) {
+            ts
+        } else {
+            return self.to_path(0.1).as_fvg_path(ts)
+        };
         let center = ts * self.center;
         let (cx, cy) = (center.x as f32, center.y as f32);
         let radius = (ts.as_tuple().1 * self.radius) as f32;
@@ -159,31 +291,41 @@ impl AsPath for Circle {
 
         path
     }
+
+    #[inline]
+    fn as_fvg_hatch_path(&self, ts: Affine, hatch: &Hatch) -> fvg::Path {
+        shape_hatch_path(self, ts, hatch)
+    }
 }
 
 impl AsPath for Arc {
     #[inline]
-    fn as_fvg_path(&self, ts: TranslateScale) -> fvg::Path {
+    fn as_fvg_path(&self, ts: Affine) -> fvg::Path {
         let bez_path = BezPath::from_vec(self.path_elements(0.1).collect());
 
         bez_path.as_fvg_path(ts)
     }
 
     #[inline]
-    fn as_fvg_path_and_points(&self, ts: TranslateScale) -> (fvg::Path, Vec<Point>) {
+    fn as_fvg_path_and_points(&self, ts: Affine) -> (fvg::Path, Vec<Point>) {
         let bez_path = BezPath::from_vec(self.path_elements(0.1).collect());
 
         bez_path.as_fvg_path_and_points(ts)
     }
+
+    #[inline]
+    fn as_fvg_hatch_path(&self, ts: Affine, hatch: &Hatch) -> fvg::Path {
+        shape_hatch_path(self, ts, hatch)
+    }
 }
 
 impl AsPath for BezPath {
     #[inline]
-    fn as_fvg_path(&self, ts: TranslateScale) -> fvg::Path {
+    fn as_fvg_path(&self, ts: Affine) -> fvg::Path {
         self.as_fvg_path_and_points(ts).0
     }
 
-    fn as_fvg_path_and_points(&self, ts: TranslateScale) -> (fvg::Path, Vec<Point>) {
+    fn as_fvg_path_and_points(&self, ts: Affine) -> (fvg::Path, Vec<Point>) {
         let mut out_path = fvg::Path::new();
         let mut out_points = Vec::new();
 
@@ -234,45 +376,45 @@ impl AsPath for BezPath {
 
         (out_path, out_points)
     }
+
+    #[inline]
+    fn as_fvg_hatch_path(&self, ts: Affine, hatch: &Hatch) -> fvg::Path {
+        shape_hatch_path(self, ts, hatch)
+    }
 }
 
 impl AsPath for TextLabel {
     #[inline]
-    fn as_fvg_path(&self, ts: TranslateScale) -> fvg::Path {
+    fn as_fvg_path(&self, ts: Affine) -> fvg::Path {
         self.as_fvg_path_and_points(ts).0
     }
 
-    fn as_fvg_path_and_points(&self, ts: TranslateScale) -> (fvg::Path, Vec<Point>) {
-        // FIXME
-        (fvg::Path::new(), Vec::new())
+    /// Returns glyph outlines of this label, set in its own font, or
+    /// in [`TextLabel::DEFAULT_FONT`].
+    fn as_fvg_path_and_points(&self, ts: Affine) -> (fvg::Path, Vec<Point>) {
+        let font = self.get_font().unwrap_or(TextLabel::default_font());
+        let (lines, _) = self.break_lines(font);
+        let path = FontLibrary::with_system(|library| library.outline_label(&lines, font));
+
+        (path.as_fvg_path(ts), Vec::new())
     }
 }
 
 pub trait Markable: Crumbling {
-    fn render_named_marker<T: fvg::Renderer>(
+    fn render_marker<T: fvg::Renderer>(
         &self,
         canvas: &mut fvg::Canvas<T>,
-        marker_name: &str,
+        marker: &Marker,
         marker_scale: f32,
         points: &[Point],
-        anchor: Option<bool>,
+        at_end: bool,
         theme: &Theme,
     ) -> Result<(), VisError> {
-        if let Some(marker) = theme.get_marker_by_name(marker_name) {
-            if let Some(style) =
-                marker.get_style_name().and_then(|name| theme.get_style_by_name(name))
-            {
-                let (angle, origin) = match anchor {
-                    Some(false) => (
-                        marker.get_orient().unwrap_or_else(|| self.end_angle(points)),
-                        points.first().unwrap(),
-                    ),
-                    Some(true) => (
-                        marker.get_orient().unwrap_or_else(|| self.end_angle(points)),
-                        points.last().unwrap(),
-                    ),
-                    None => unimplemented!(),
-                };
+        if let Some(style) = theme.get_style(marker.get_style_id()) {
+            let origin = if at_end { points.last() } else { points.first() };
+
+            if let Some(origin) = origin {
+                let angle = marker.get_orient().unwrap_or_else(|| self.end_angle(points));
                 let refx = -marker.get_refx();
                 let refy = -marker.get_refy();
 
@@ -282,14 +424,21 @@ pub trait Markable: Crumbling {
                 canvas.translate(refx as f32, refy as f32);
 
                 // FIXME precompute marker's path, clone it here.
-                let mut marker_path = marker.get_crumb().as_fvg_path(TranslateScale::default());
+                let mut marker_path = marker.get_crumb().as_fvg_path(Affine::IDENTITY);
 
                 if let Some(fill) = style.get_fill() {
-                    canvas.fill_path(&mut marker_path, fill.as_fvg_paint_with_theme(theme)?);
+                    render_fill_as_fvg(
+                        canvas,
+                        &mut marker_path,
+                        marker.get_crumb(),
+                        Affine::IDENTITY,
+                        fill,
+                        theme,
+                    )?;
                 }
 
                 if let Some(stroke) = style.get_stroke() {
-                    canvas.stroke_path(&mut marker_path, stroke.as_fvg_paint());
+                    canvas.stroke_path(&mut marker_path, stroke.as_fvg_paint_with_theme(theme)?);
                 }
 
                 canvas.reset_transform();
@@ -307,7 +456,7 @@ pub trait RenderableWithStyle: Crumbling + AsPath {
     fn render_as_fvg_with_style<T: fvg::Renderer>(
         &self,
         canvas: &mut fvg::Canvas<T>,
-        ts: TranslateScale,
+        ts: Affine,
         style_id: Option<StyleId>,
         theme: &Theme,
     ) -> Result<(), VisError> {
@@ -315,21 +464,15 @@ pub trait RenderableWithStyle: Crumbling + AsPath {
 
         if !path.is_empty() {
             let style = theme.get_style(style_id).unwrap_or_else(|| theme.get_default_style());
-            let fill_paint = style
-                .get_fill()
-                .or_else(|| theme.get_default_style().get_fill())
-                .map(|f| f.as_fvg_paint_with_theme(theme));
-            let stroke_paint = style
-                .get_stroke()
-                .or_else(|| theme.get_default_style().get_stroke())
-                .map(|s| s.as_fvg_paint());
-
-            if let Some(paint) = fill_paint {
-                canvas.fill_path(&mut path, paint?);
+            let fill = style.get_fill().or_else(|| theme.get_default_style().get_fill());
+            let stroke = style.get_stroke().or_else(|| theme.get_default_style().get_stroke());
+
+            if let Some(fill) = fill {
+                render_fill_as_fvg(canvas, &mut path, self, ts, fill, theme)?;
             }
 
-            if let Some(paint) = stroke_paint {
-                canvas.stroke_path(&mut path, paint);
+            if let Some(stroke) = stroke {
+                canvas.stroke_path(&mut path, scaled_stroke_paint(stroke, ts, theme)?);
             }
         }
 
@@ -342,7 +485,7 @@ impl RenderableWithStyle for Crumb {
     fn render_as_fvg_with_style<T: fvg::Renderer>(
         &self,
         canvas: &mut fvg::Canvas<T>,
-        ts: TranslateScale,
+        ts: Affine,
         style_id: Option<StyleId>,
         theme: &Theme,
     ) -> Result<(), VisError> {
@@ -363,45 +506,27 @@ impl RenderableWithStyle for Line {
     fn render_as_fvg_with_style<T: fvg::Renderer>(
         &self,
         canvas: &mut fvg::Canvas<T>,
-        ts: TranslateScale,
+        ts: Affine,
         style_id: Option<StyleId>,
         theme: &Theme,
     ) -> Result<(), VisError> {
         let style = theme.get_style(style_id).unwrap_or_else(|| theme.get_default_style());
         let stroke = style.get_stroke().or_else(|| theme.get_default_style().get_stroke());
-        let stroke_paint = if let Some(stroke) = stroke {
-            stroke.as_fvg_paint()
-        } else {
-            let stroke = Stroke::default();
-            stroke.as_fvg_paint()
-        };
+        let stroke_paint = scaled_stroke_paint(stroke.unwrap_or(&Stroke::default()), ts, theme)?;
         let (mut path, points) = self.as_fvg_path_and_points(ts);
 
         canvas.stroke_path(&mut path, stroke_paint);
 
-        let marker_scale = stroke.as_ref().map(|s| 0.5 * (s.get_width() as f32)).unwrap_or(0.5);
+        let marker_scale =
+            (stroke.map_or(0.5, |s| 0.5 * s.get_width()) * get_scale_factor(ts)) as f32;
         let markers = style.get_markers();
 
-        if let Some(marker_name) = markers.get_start_name() {
-            self.render_named_marker(
-                canvas,
-                marker_name,
-                marker_scale,
-                &points,
-                Some(false),
-                theme,
-            )?;
+        if let Some(marker) = theme.get_marker(markers.get_start_id()) {
+            self.render_marker(canvas, marker, marker_scale, &points, false, theme)?;
         }
 
-        if let Some(marker_name) = markers.get_end_name() {
-            self.render_named_marker(
-                canvas,
-                marker_name,
-                marker_scale,
-                &points,
-                Some(true),
-                theme,
-            )?;
+        if let Some(marker) = theme.get_marker(markers.get_end_id()) {
+            self.render_marker(canvas, marker, marker_scale, &points, true, theme)?;
         }
 
         Ok(())
@@ -416,7 +541,7 @@ impl RenderableWithStyle for Arc {
     fn render_as_fvg_with_style<T: fvg::Renderer>(
         &self,
         canvas: &mut fvg::Canvas<T>,
-        ts: TranslateScale,
+        ts: Affine,
         style_id: Option<StyleId>,
         theme: &Theme,
     ) -> Result<(), VisError> {
@@ -430,7 +555,7 @@ impl RenderableWithStyle for BezPath {
     fn render_as_fvg_with_style<T: fvg::Renderer>(
         &self,
         canvas: &mut fvg::Canvas<T>,
-        ts: TranslateScale,
+        ts: Affine,
         style_id: Option<StyleId>,
         theme: &Theme,
     ) -> Result<(), VisError> {
@@ -439,36 +564,23 @@ impl RenderableWithStyle for BezPath {
         let stroke = style.get_stroke().or_else(|| theme.get_default_style().get_stroke());
 
         if let Some(fill) = style.get_fill().or_else(|| theme.get_default_style().get_fill()) {
-            canvas.fill_path(&mut path, fill.as_fvg_paint_with_theme(theme)?);
+            render_fill_as_fvg(canvas, &mut path, self, ts, fill, theme)?;
         }
 
         if let Some(stroke) = stroke {
-            canvas.stroke_path(&mut path, stroke.as_fvg_paint());
+            canvas.stroke_path(&mut path, scaled_stroke_paint(stroke, ts, theme)?);
         }
 
-        let marker_scale = stroke.as_ref().map(|s| 0.5 * (s.get_width() as f32)).unwrap_or(0.5);
+        let marker_scale =
+            (stroke.map_or(0.5, |s| 0.5 * s.get_width()) * get_scale_factor(ts)) as f32;
         let markers = style.get_markers();
 
-        if let Some(marker_name) = markers.get_start_name() {
-            self.render_named_marker(
-                canvas,
-                marker_name,
-                marker_scale,
-                &points,
-                Some(false),
-                theme,
-            )?;
+        if let Some(marker) = theme.get_marker(markers.get_start_id()) {
+            self.render_marker(canvas, marker, marker_scale, &points, false, theme)?;
         }
 
-        if let Some(marker_name) = markers.get_end_name() {
-            self.render_named_marker(
-                canvas,
-                marker_name,
-                marker_scale,
-                &points,
-                Some(true),
-                theme,
-            )?;
+        if let Some(marker) = theme.get_marker(markers.get_end_id()) {
+            self.render_marker(canvas, marker, marker_scale, &points, true, theme)?;
         }
 
         Ok(())
@@ -476,14 +588,131 @@ impl RenderableWithStyle for BezPath {
 }
 
 impl RenderableWithStyle for TextLabel {
+    #[inline]
     fn render_as_fvg_with_style<T: fvg::Renderer>(
         &self,
         canvas: &mut fvg::Canvas<T>,
-        ts: TranslateScale,
+        ts: Affine,
         style_id: Option<StyleId>,
         theme: &Theme,
     ) -> Result<(), VisError> {
-        Ok(())
+        self.render_text_as_fvg(canvas, &mut FvgFonts::new(), ts, style_id, theme)
+    }
+}
+
+/// Fonts added to a canvas, mapped from faces of the shared
+/// [`FontLibrary`], which include fonts embedded in a theme, see
+/// [`Theme::load_fonts()`].
+///
+/// Note: font ids are valid only for the canvas, to which the fonts
+/// were added.
+#[derive(Default)]
+pub struct FvgFonts {
+    font_ids: HashMap<fontdb::ID, fvg::FontId>,
+}
+
+impl FvgFonts {
+    pub fn new() -> Self {
+        FvgFonts::default()
+    }
+
+    /// Returns the id of a font containing a face of a `library`,
+    /// adding the font to a `canvas` first, unless already added.
+    fn get_font_id<T: fvg::Renderer>(
+        &mut self,
+        canvas: &mut fvg::Canvas<T>,
+        library: &FontLibrary,
+        face_id: fontdb::ID,
+    ) -> Result<fvg::FontId, VisError> {
+        if let Some(font_id) = self.font_ids.get(&face_id) {
+            Ok(*font_id)
+        } else {
+            let data = library
+                .get_face_data(face_id)
+                .ok_or_else(|| VisError::text_rendering_failure("font data is missing"))?;
+            let font_id = canvas
+                .add_font_mem(&data)
+                .map_err(|err| VisError::text_rendering_failure(format!("{:?}", err)))?;
+
+            self.font_ids.insert(face_id, font_id);
+
+            Ok(font_id)
+        }
+    }
+}
+
+pub trait RenderableText {
+    fn render_text_as_fvg<T: fvg::Renderer>(
+        &self,
+        canvas: &mut fvg::Canvas<T>,
+        fonts: &mut FvgFonts,
+        ts: Affine,
+        style_id: Option<StyleId>,
+        theme: &Theme,
+    ) -> Result<(), VisError>;
+}
+
+/// Text is laid out by [`FontLibrary::layout()`] and rendered with
+/// the text API of femtovg, run by run, in the fill of a style, or in
+/// black, if there is no style, or it has no fill.  Runs of spans with
+/// styles of their own are painted with the fills of their styles, if
+/// any.  The box of a label, if any, is rendered first, as a rounded
+/// rectangle with the style of the box.
+impl RenderableText for TextLabel {
+    fn render_text_as_fvg<T: fvg::Renderer>(
+        &self,
+        canvas: &mut fvg::Canvas<T>,
+        fonts: &mut FvgFonts,
+        ts: Affine,
+        style_id: Option<StyleId>,
+        theme: &Theme,
+    ) -> Result<(), VisError> {
+        let style = theme.get_style(style_id);
+        let font = self.get_font().unwrap_or_else(|| TextLabel::get_default_font(style, theme));
+        let fill = style.and_then(|s| s.get_fill());
+        let (lines, frame) = self.break_lines(font);
+
+        if let (Some(frame), Some(box_style_id)) =
+            (frame, self.get_box().and_then(|b| b.get_style_id()))
+        {
+            frame.render_as_fvg_with_style(canvas, ts, Some(box_style_id), theme)?;
+        }
+
+        let [a, b, c, d, e, f] = ts.as_coeffs();
+
+        FontLibrary::with_system(|library| {
+            let runs = library.layout(&lines, font);
+
+            canvas.save();
+            canvas.set_transform(a as f32, b as f32, c as f32, d as f32, e as f32, f as f32);
+
+            let result = runs.iter().try_for_each(|run| {
+                let font_id = fonts.get_font_id(canvas, library, run.get_face_id())?;
+                let origin = run.get_origin();
+                let run_fill =
+                    theme.get_style(run.get_style_id()).and_then(|s| s.get_fill()).or(fill);
+                let mut paint = if let Some(fill) = run_fill {
+                    fill.as_fvg_paint_with_theme(theme)?
+                } else {
+                    fvg::Paint::color(Color::BLACK.as_fvg_color())
+                };
+
+                paint.set_font(&[font_id]);
+                paint.set_font_size(run.get_font_size() as f32);
+                paint.set_letter_spacing(run.get_letter_spacing() as f32);
+                paint.set_text_align(fvg::Align::Left);
+                paint.set_text_baseline(fvg::Baseline::Alphabetic);
+
+                canvas
+                    .fill_text(origin.x as f32, origin.y as f32, run.get_text(), paint)
+                    .map(|_| ())
+                    .map_err(|err| VisError::text_rendering_failure(format!("{:?}", err)))
+            });
+
+            canvas.restore();
+
+            result
+        })
     }
 }
 
@@ -491,61 +720,218 @@ pub trait AsPaint {
     fn as_fvg_paint(&self) -> fvg::Paint;
 }
 
+/// Note: femtovg doesn't support dashed lines, hence dash patterns
+/// are ignored, and strokes are painted solid.
 impl AsPaint for Stroke {
     fn as_fvg_paint(&self) -> fvg::Paint {
         let mut paint = fvg::Paint::color(self.get_brush().as_fvg_color());
 
-        paint.set_line_width(0.5 * (self.get_width() as f32));
+        set_line_style(&mut paint, self);
 
         paint
     }
 }
 
+impl AsPaint for Gradient {
+    fn as_fvg_paint(&self) -> fvg::Paint {
+        match self {
+            Gradient::Linear(start, end, stops) => {
+                let start = start.resolve(Rect::new(0., 0., 1., 1.));
+                let end = end.resolve(Rect::new(0., 0., 1., 1.));
+                let stops: Vec<_> =
+                    stops.iter().map(|stop| (stop.pos, stop.color.as_fvg_color())).collect();
+
+                fvg::Paint::linear_gradient_stops(
+                    start.x as f32,
+                    start.y as f32,
+                    end.x as f32,
+                    end.y as f32,
+                    stops.as_slice(),
+                )
+            }
+            Gradient::Radial(radius, stops) => {
+                let radius = *radius as f32;
+                let stops: Vec<_> =
+                    stops.iter().map(|stop| (stop.pos, stop.color.as_fvg_color())).collect();
+
+                fvg::Paint::radial_gradient_stops(0.0, 0.0, radius, radius, stops.as_slice())
+            }
+        }
+    }
+}
+
+fn set_line_style(paint: &mut fvg::Paint, stroke: &Stroke) {
+    paint.set_line_width(0.5 * (stroke.get_width() as f32));
+    paint.set_line_cap(match stroke.get_cap() {
+        LineCap::Butt => fvg::LineCap::Butt,
+        LineCap::Round => fvg::LineCap::Round,
+        LineCap::Square => fvg::LineCap::Square,
+    });
+    paint.set_line_join(match stroke.get_join() {
+        LineJoin::Miter => fvg::LineJoin::Miter,
+        LineJoin::Round => fvg::LineJoin::Round,
+        LineJoin::Bevel => fvg::LineJoin::Bevel,
+    });
+    paint.set_miter_limit(stroke.get_miter_limit() as f32);
+}
+
+/// Returns the paint of a `stroke` of a crumb, with the width of the
+/// stroke scaled by the scale factor of the crumb's transformation
+/// `ts`, as in the other backends.
+fn scaled_stroke_paint(stroke: &Stroke, ts: Affine, theme: &Theme) -> Result<fvg::Paint, VisError> {
+    let mut paint = stroke.as_fvg_paint_with_theme(theme)?;
+
+    paint.set_line_width(0.5 * (stroke.get_width() * get_scale_factor(ts)) as f32);
+
+    Ok(paint)
+}
+
 pub trait AsPaintWithTheme {
     fn as_fvg_paint_with_theme(&self, theme: &Theme) -> Result<fvg::Paint, VisError>;
 }
 
+impl AsPaintWithTheme for Stroke {
+    fn as_fvg_paint_with_theme(&self, theme: &Theme) -> Result<fvg::Paint, VisError> {
+        if let Some(name) = self.get_gradient_name() {
+            let mut paint = theme
+                .get_gradspec(name)
+                .map(|spec| spec.as_fvg_paint())
+                .ok_or_else(|| VisError::gradient_missing_for_name(name))?;
+
+            set_line_style(&mut paint, self);
+
+            Ok(paint)
+        } else {
+            Ok(self.as_fvg_paint())
+        }
+    }
+}
+
+/// Note: a hatch fill is painted as its background (if any), and its
+/// lines have to be painted separately, see [`render_fill_as_fvg()`].
 impl AsPaintWithTheme for Fill {
     fn as_fvg_paint_with_theme(&self, theme: &Theme) -> Result<fvg::Paint, VisError> {
         match self {
             Fill::Color(color) => Ok(fvg::Paint::color(color.as_fvg_color())),
             Fill::Linear(name) => match theme.get_gradspec(name) {
-                Some(Gradient::Linear(start, end, stops)) => {
-                    let start = start.resolve(Rect::new(0., 0., 1., 1.));
-                    let end = end.resolve(Rect::new(0., 0., 1., 1.));
-                    let stops: Vec<_> =
-                        stops.iter().map(|stop| (stop.pos, stop.color.as_fvg_color())).collect();
-
-                    Ok(fvg::Paint::linear_gradient_stops(
-                        start.x as f32,
-                        start.y as f32,
-                        end.x as f32,
-                        end.y as f32,
-                        stops.as_slice(),
-                    ))
-                }
+                Some(spec @ Gradient::Linear(..)) => Ok(spec.as_fvg_paint()),
                 Some(_) => Err(VisError::gradient_mismatch_for_name(name)),
                 None => Err(VisError::gradient_missing_for_name(name)),
             },
             Fill::Radial(name) => match theme.get_gradspec(name) {
-                Some(Gradient::Radial(radius, stops)) => {
-                    let radius = *radius as f32;
-                    let stops: Vec<_> =
-                        stops.iter().map(|stop| (stop.pos, stop.color.as_fvg_color())).collect();
-
-                    Ok(fvg::Paint::radial_gradient_stops(
-                        0.0,
-                        0.0,
-                        radius,
-                        radius,
-                        stops.as_slice(),
-                    ))
-                }
+                Some(spec @ Gradient::Radial(..)) => Ok(spec.as_fvg_paint()),
                 Some(_) => Err(VisError::gradient_mismatch_for_name(name)),
                 None => Err(VisError::gradient_missing_for_name(name)),
             },
+            Fill::Hatch(name) => match theme.get_hatch(name) {
+                Some(hatch) => Ok(fvg::Paint::color(
+                    hatch.get_background().copied().unwrap_or(Color::TRANSPARENT).as_fvg_color(),
+                )),
+                None => Err(VisError::hatch_missing_for_name(name)),
+            },
+        }
+    }
+}
+
+/// Fills a `path` made of a `shape` transformed by `ts`.  Lines of
+/// hatch fills are clipped to the shape and painted over the fill's
+/// background.
+pub fn render_fill_as_fvg<T, P>(
+    canvas: &mut fvg::Canvas<T>,
+    path: &mut fvg::Path,
+    shape: &P,
+    ts: Affine,
+    fill: &Fill,
+    theme: &Theme,
+) -> Result<(), VisError>
+where
+    T: fvg::Renderer,
+    P: AsPath + ?Sized,
+{
+    canvas.fill_path(path, fill.as_fvg_paint_with_theme(theme)?);
+
+    if let Fill::Hatch(name) = fill {
+        if let Some(hatch) = theme.get_hatch(name) {
+            let mut hatch_path = shape.as_fvg_hatch_path(ts, hatch);
+            let mut paint = fvg::Paint::color(hatch.get_brush().as_fvg_color());
+
+            paint.set_line_width((hatch.get_width() * get_scale_factor(ts)) as f32);
+            canvas.stroke_path(&mut hatch_path, paint);
         }
     }
+
+    Ok(())
+}
+
+/// Returns the lines of a `hatch`, clipped to the interior of a
+/// `shape` transformed by `ts`.
+///
+/// Hatch lines are aligned to the origin of output coordinates, and
+/// interiors are determined with the even-odd rule.
+fn hatch_lines<S: Shape>(shape: &S, ts: Affine, hatch: &Hatch) -> Vec<Line> {
+    let spacing = hatch.get_spacing() * get_scale_factor(ts);
+    let rotation = Affine::rotate(hatch.get_angle().to_radians());
+    let ts = rotation.inverse() * ts;
+    let mut result = Vec::new();
+
+    if spacing <= 0.0 {
+        return result
+    }
+
+    // Edges of the flattened outline, in a frame, where hatch lines
+    // are horizontal.
+    let mut edges = Vec::new();
+    let mut start_point = None;
+    let mut last_point = None;
+
+    kurbo::flatten(shape.path_elements(0.1).map(|el| ts * el), 0.1, |path_el| match path_el {
+        PathEl::MoveTo(p) => {
+            if let (Some(p0), Some(p1)) = (last_point, start_point) {
+                edges.push(Line::new(p0, p1));
+            }
+            start_point = Some(p);
+            last_point = Some(p);
+        }
+        PathEl::LineTo(p) => {
+            if let Some(p0) = last_point {
+                edges.push(Line::new(p0, p));
+            }
+            last_point = Some(p);
+        }
+        PathEl::ClosePath => {
+            if let (Some(p0), Some(p1)) = (last_point, start_point) {
+                edges.push(Line::new(p0, p1));
+            }
+            last_point = start_point;
+        }
+        _ => {}
+    });
+
+    if let (Some(p0), Some(p1)) = (last_point, start_point) {
+        edges.push(Line::new(p0, p1));
+    }
+
+    if let Some(bbox) = edges.iter().map(|edge| edge.bounding_box()).reduce(|a, b| a.union(b)) {
+        let first = (bbox.y0 / spacing - 0.5).ceil() as i64;
+        let last = (bbox.y1 / spacing - 0.5).floor() as i64;
+
+        for k in first..=last {
+            let y = (k as f64 + 0.5) * spacing;
+            let mut crossings: Vec<_> = edges
+                .iter()
+                .filter(|Line { p0, p1 }| (p0.y <= y) != (p1.y <= y))
+                .map(|Line { p0, p1 }| p0.x + (y - p0.y) / (p1.y - p0.y) * (p1.x - p0.x))
+                .collect();
+
+            crossings.sort_by(f64::total_cmp);
+
+            for pair in crossings.chunks_exact(2) {
+                result.push(rotation * Line::new((pair[0], y), (pair[1], y)));
+            }
+        }
+    }
+
+    result
 }
 
 pub trait AsColor {
@@ -1,8 +1,10 @@
-use std::io::Write;
-use kurbo::{Shape, Line, Rect, RoundedRect, Circle, Arc, BezPath, TranslateScale, Size};
+use std::{io::Write, borrow::Cow};
+use kurbo::{Shape, Line, Rect, RoundedRect, Circle, Arc, BezPath, Affine, TranslateScale, Size};
 use crate::{
-    Scene, Theme, Style, Color, Stroke, Fill, Gradient, Marker, style::MarkerSuit, Crumb, CrumbSet,
-    TextLabel, PreprocessWithStyle,
+    Scene, PaintStep, Theme, Style, Color, Stroke, LineCap, LineJoin, Fill, Gradient, Hatch,
+    Marker, style::MarkerSuit, Crumb, CrumbSet, TextLabel, BaselineShift, PreprocessWithStyle,
+    Font, GenericFontFamily, EmbeddedFont, AsCss, base64,
+    affine::{get_scale_factor, as_translate_scale},
 };
 
 pub trait ToSvg {
@@ -25,7 +27,7 @@ pub trait WriteSvgWithStyle {
     fn write_svg_with_style<W: std::io::Write>(
         &self,
         svg: W,
-        ts: TranslateScale,
+        ts: Affine,
         style: Option<&Style>,
         theme: &Theme,
     ) -> std::io::Result<()>;
@@ -60,12 +62,14 @@ impl ToSvg for Scene {
         S: Into<Size>,
         M: Into<Size>,
     {
+        // Lines are broken and the content is fitted with the fonts
+        // embedded in the theme, as by other backends.
+        #[cfg(feature = "text")]
+        theme.load_fonts()?;
+
         let out_size = out_size.into();
-        let out_margin = out_margin.into();
-        let translation = out_margin.to_vec2();
-        let scale_factor = ((out_size.width - 2. * out_margin.width) / self.get_size().width)
-            .min((out_size.height - 2. * out_margin.height) / self.get_size().height);
-        let root_ts = TranslateScale::translate(translation) * TranslateScale::scale(scale_factor);
+        let root_ts = self.get_root_ts(theme, out_size, out_margin.into())?;
+        let scale_factor = get_scale_factor(root_ts);
 
         let mut svg = Vec::new();
 
@@ -82,10 +86,24 @@ impl ToSvg for Scene {
 
         writeln!(&mut svg, "  <defs>")?;
 
+        if !theme.get_embedded_fonts().is_empty() {
+            writeln!(&mut svg, "    <style><![CDATA[")?;
+
+            for font in theme.get_embedded_fonts() {
+                font.write_svg(&mut svg, scale_factor)?;
+            }
+
+            writeln!(&mut svg, "    ]]></style>")?;
+        }
+
         for (name, spec) in theme.get_named_gradspecs() {
             spec.write_svg_with_name(&mut svg, name)?;
         }
 
+        for (name, hatch) in theme.get_named_hatches() {
+            hatch.write_svg_with_theme(&mut svg, scale_factor, name, theme)?;
+        }
+
         for entry in theme.get_named_markers() {
             let (name, marker) = entry?;
 
@@ -108,11 +126,50 @@ impl ToSvg for Scene {
             label.preprocess_with_style(ts, style, theme)
         })?;
 
-        for item in visible_crumbs.get_crumbs(self) {
-            let (crumb, ts, style_id) = item?;
-            let style = theme.get_style(style_id);
+        let mut clip_count = 0;
+
+        for step in self.visible_paint_steps_in(out_size.to_rect(), root_ts, theme)? {
+            match step {
+                PaintStep::BeginGroup(group, ts) => {
+                    let clip_name = theme.get_clip_id(clip_count);
+
+                    if let Some(clip) = group.get_clip() {
+                        clip_count += 1;
+
+                        writeln!(&mut svg, "  <clipPath id=\"{}\">", clip_name)?;
+                        write!(&mut svg, "    <path d=\"")?;
+
+                        if let Some(path) = clip.get_clip_path() {
+                            (ts * path).write_to(&mut svg)?;
+                        }
+
+                        writeln!(&mut svg, "\" />")?;
+                        writeln!(&mut svg, "  </clipPath>")?;
+                    }
+
+                    write!(&mut svg, "  <g")?;
+
+                    if let Some(opacity) = group.get_opacity() {
+                        write!(&mut svg, " opacity=\"{}\"", opacity)?;
+                    }
 
-            crumb.write_svg_with_style(&mut svg, ts, style, theme)?;
+                    if group.get_clip().is_some() {
+                        write!(&mut svg, " clip-path=\"url(#{})\"", clip_name)?;
+                    }
+
+                    if let Some(mode) = group.get_blend_mode() {
+                        write!(&mut svg, " style=\"mix-blend-mode:{}\"", mode.as_css_name())?;
+                    }
+
+                    writeln!(&mut svg, ">")?;
+                }
+                PaintStep::Crumb(crumb, ts, style_id) => {
+                    let style = theme.get_style(style_id);
+
+                    crumb.write_svg_with_style(&mut svg, ts, style, theme)?;
+                }
+                PaintStep::EndGroup => writeln!(&mut svg, "  </g>")?,
+            }
         }
 
         writeln!(&mut svg, "</svg>")?;
@@ -125,8 +182,35 @@ impl ToSvg for Scene {
 
 impl WriteSvg for Stroke {
     fn write_svg<W: std::io::Write>(&self, mut svg: W, scale_factor: f64) -> std::io::Result<()> {
-        self.get_brush().write_svg_with_name(svg.by_ref(), "stroke")?;
-        write!(svg, " stroke-width=\"{}\"", self.get_width() * scale_factor)
+        if let Some(name) = self.get_gradient_name() {
+            write!(svg, "stroke=\"url(#{})\"", name)?;
+        } else {
+            self.get_brush().write_svg_with_name(svg.by_ref(), "stroke")?;
+        }
+        write!(svg, " stroke-width=\"{}\"", self.get_width() * scale_factor)?;
+
+        if self.is_dashed() {
+            let dash_array: Vec<_> =
+                self.get_dash_array().iter().map(|v| (v * scale_factor).to_string()).collect();
+
+            write!(svg, " stroke-dasharray=\"{}\"", dash_array.join(" "))?;
+
+            if self.get_dash_offset() != 0.0 {
+                write!(svg, " stroke-dashoffset=\"{}\"", self.get_dash_offset() * scale_factor)?;
+            }
+        }
+
+        if self.get_cap() != LineCap::default() {
+            write!(svg, " stroke-linecap=\"{}\"", self.get_cap().as_css_name())?;
+        }
+
+        if self.get_join() != LineJoin::default() {
+            write!(svg, " stroke-linejoin=\"{}\"", self.get_join().as_css_name())?;
+        } else if self.get_miter_limit() != Stroke::DEFAULT_MITER_LIMIT {
+            write!(svg, " stroke-miterlimit=\"{}\"", self.get_miter_limit())?;
+        }
+
+        Ok(())
     }
 }
 
@@ -136,10 +220,38 @@ impl WriteSvg for Fill {
             Fill::Color(ref color) => color.write_svg_with_name(svg, "fill"),
             Fill::Linear(ref name) => write!(svg, "fill=\"url(#{})\"", name),
             Fill::Radial(ref name) => write!(svg, "fill=\"url(#{})\"", name),
+            Fill::Hatch(ref name) => write!(svg, "fill=\"url(#{})\"", name),
         }
     }
 }
 
+/// Writes a `@font-face` rule with the font data inlined as a data
+/// URL, so that rendering doesn't depend on fonts installed on the
+/// host.
+impl WriteSvg for EmbeddedFont {
+    fn write_svg<W: std::io::Write>(&self, mut svg: W, _scale_factor: f64) -> std::io::Result<()> {
+        let data = self.load_data()?;
+        let (mime_type, format) = match data.get(..4) {
+            Some(b"OTTO") => ("font/otf", "opentype"),
+            Some(b"wOFF") => ("font/woff", "woff"),
+            Some(b"wOF2") => ("font/woff2", "woff2"),
+            Some(b"ttcf") => ("font/collection", "collection"),
+            _ => ("font/ttf", "truetype"),
+        };
+
+        write!(
+            svg,
+            "      @font-face {{ font-family: \"{}\"; font-weight: {}; font-style: {}; ",
+            self.get_family_name(),
+            self.get_weight().as_number(),
+            self.get_style().as_css(),
+        )?;
+        write!(svg, "src: url(data:{};base64,", mime_type)?;
+        svg.write_all(base64::encode(&data).as_bytes())?;
+        writeln!(svg, ") format(\"{}\"); }}", format)
+    }
+}
+
 impl WriteSvg for MarkerSuit {
     fn write_svg<W: std::io::Write>(&self, mut svg: W, _scale_factor: f64) -> std::io::Result<()> {
         if let Some(name) = self.get_start_name() {
@@ -180,7 +292,7 @@ impl WriteSvgWithStyle for Crumb {
     fn write_svg_with_style<W: std::io::Write>(
         &self,
         svg: W,
-        ts: TranslateScale,
+        ts: Affine,
         style: Option<&Style>,
         theme: &Theme,
     ) -> std::io::Result<()> {
@@ -201,14 +313,13 @@ impl WriteSvgWithStyle for Line {
     fn write_svg_with_style<W: std::io::Write>(
         &self,
         mut svg: W,
-        ts: TranslateScale,
+        ts: Affine,
         style: Option<&Style>,
         theme: &Theme,
     ) -> std::io::Result<()> {
-        let p0 = ts * self.p0;
-        let p1 = ts * self.p1;
+        let Line { p0, p1 } = ts * *self;
         let style = style.unwrap_or_else(|| theme.get_default_style());
-        let (_, scale_factor) = ts.as_tuple();
+        let scale_factor = get_scale_factor(ts);
 
         write!(svg, "  <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" ", p0.x, p0.y, p1.x, p1.y)?;
 
@@ -226,10 +337,15 @@ impl WriteSvgWithStyle for Rect {
     fn write_svg_with_style<W: std::io::Write>(
         &self,
         mut svg: W,
-        ts: TranslateScale,
+        ts: Affine,
         style: Option<&Style>,
-        _theme: &Theme,
+        theme: &Theme,
     ) -> std::io::Result<()> {
+        let ts = if let Some(ts) = as_translate_scale(ts) {
+            ts
+        } else {
+            return self.to_path(0.1).write_svg_with_style(svg, ts, style, theme)
+        };
         let rect = ts * *self;
         let (_, scale_factor) = ts.as_tuple();
 
@@ -254,10 +370,15 @@ impl WriteSvgWithStyle for RoundedRect {
     fn write_svg_with_style<W: std::io::Write>(
         &self,
         mut svg: W,
-        ts: TranslateScale,
+        ts: Affine,
         style: Option<&Style>,
-        _theme: &Theme,
+        theme: &Theme,
     ) -> std::io::Result<()> {
+        let ts = if let Some(ts) = as_translate_scale(ts) {
+            ts
+        } else {
+            return self.to_path(0.1).write_svg_with_style(svg, ts, style, theme)
+        };
         let rr = ts * *self;
         let rect = &rr.rect();
         let (_, scale_factor) = ts.as_tuple();
@@ -295,10 +416,15 @@ impl WriteSvgWithStyle for Circle {
     fn write_svg_with_style<W: std::io::Write>(
         &self,
         mut svg: W,
-        ts: TranslateScale,
+        ts: Affine,
         style: Option<&Style>,
-        _theme: &Theme,
+        theme: &Theme,
     ) -> std::io::Result<()> {
+        let ts = if let Some(ts) = as_translate_scale(ts) {
+            ts
+        } else {
+            return self.to_path(0.1).write_svg_with_style(svg, ts, style, theme)
+        };
         let center = ts * self.center;
         let (_, scale_factor) = ts.as_tuple();
         let radius = self.radius * scale_factor;
@@ -317,7 +443,7 @@ impl WriteSvgWithStyle for Arc {
     fn write_svg_with_style<W: std::io::Write>(
         &self,
         svg: W,
-        ts: TranslateScale,
+        ts: Affine,
         style: Option<&Style>,
         theme: &Theme,
     ) -> std::io::Result<()> {
@@ -332,14 +458,14 @@ impl WriteSvgWithStyle for BezPath {
     fn write_svg_with_style<W: std::io::Write>(
         &self,
         mut svg: W,
-        ts: TranslateScale,
+        ts: Affine,
         style: Option<&Style>,
         _theme: &Theme,
     ) -> std::io::Result<()> {
-        let (_, scale_factor) = ts.as_tuple();
+        let scale_factor = get_scale_factor(ts);
 
         write!(svg, "  <path d=\"")?;
-        (ts * self.clone()).write_to(svg.by_ref())?;
+        (ts * self).write_to(svg.by_ref())?;
         write!(svg, "\" ")?;
 
         if let Some(style) = style {
@@ -350,11 +476,83 @@ impl WriteSvgWithStyle for BezPath {
     }
 }
 
+impl Font {
+    /// Writes the family names of this font, followed by fallback
+    /// chains of a `theme` and generic families, in the order, in
+    /// which faces are resolved by the raster backends.
+    fn write_svg_family_with_theme<W: std::io::Write>(
+        &self,
+        mut svg: W,
+        theme: &Theme,
+    ) -> std::io::Result<()> {
+        let class = self.get_class();
+
+        write!(svg, " font-family=\"")?;
+
+        for name in self.get_names().iter().chain(theme.get_font_fallbacks(class)) {
+            write!(svg, "'{}', ", name)?;
+        }
+
+        match class {
+            GenericFontFamily::SansSerif | GenericFontFamily::Unspecified => {}
+            _ => write!(svg, "{}, ", class.as_css())?,
+        }
+
+        if class != GenericFontFamily::SansSerif {
+            for name in theme.get_font_fallbacks(GenericFontFamily::SansSerif) {
+                write!(svg, "'{}', ", name)?;
+            }
+        }
+
+        write!(svg, "sans-serif\"")
+    }
+}
+
 impl TextLabel {
+    /// Writes the baseline shift of this span, its letter spacing and
+    /// the fill of its own style, or else of a `style`, if any.
+    fn write_svg_text_attributes<W: std::io::Write>(
+        &self,
+        mut svg: W,
+        scale_factor: f64,
+        style: Option<&Style>,
+        theme: &Theme,
+    ) -> std::io::Result<()> {
+        if !self.is_root() {
+            match self.get_baseline_shift() {
+                Some(BaselineShift::Sub) => write!(svg, " baseline-shift=\"sub\"")?,
+                Some(BaselineShift::Super) => write!(svg, " baseline-shift=\"super\"")?,
+                Some(BaselineShift::Length(length)) => {
+                    write!(svg, " baseline-shift=\"{}\"", length * scale_factor)?
+                }
+                None => {}
+            }
+        }
+
+        if let Some(spacing) = self.get_letter_spacing() {
+            write!(svg, " letter-spacing=\"{}\"", spacing * scale_factor)?;
+        }
+
+        let style = if self.get_style_id().is_some() {
+            theme.get_style(self.get_style_id())
+        } else {
+            style
+        };
+
+        if let Some(fill) = style.and_then(|s| s.get_fill()) {
+            write!(svg, " ")?;
+            fill.write_svg(svg.by_ref(), scale_factor)?;
+        }
+
+        Ok(())
+    }
+
     fn write_svg_with_scale<W: std::io::Write>(
         &self,
         mut svg: W,
         scale_factor: f64,
+        style: Option<&Style>,
+        theme: &Theme,
     ) -> std::io::Result<()> {
         if self.is_root() {
             let origin = self.get_origin().unwrap_or_default();
@@ -405,12 +603,9 @@ impl TextLabel {
                 write!(svg, "\"")?;
             }
         }
-        write!(
-            svg,
-            " font-family=\"{}\" font-size=\"{}\">",
-            Self::DEFAULT_FONT.get_family_name(),
-            Self::DEFAULT_FONT.get_size(),
-        )?;
+        self.write_svg_text_attributes(svg.by_ref(), scale_factor, style, theme)?;
+        Self::default_font().write_svg_family_with_theme(svg.by_ref(), theme)?;
+        write!(svg, " font-size=\"{}\">", Self::default_font().get_size())?;
 
         let mut buffer = Vec::new();
         for item in self.get_body() {
@@ -420,7 +615,7 @@ impl TextLabel {
                 }
                 crate::text::Item::Span(span) => {
                     buffer.clear();
-                    span.write_svg_with_scale(&mut buffer, scale_factor)?;
+                    span.write_svg_with_scale(&mut buffer, scale_factor, None, theme)?;
                     svg.write(buffer.as_slice())?;
                 }
             }
@@ -438,16 +633,45 @@ impl WriteSvgWithStyle for TextLabel {
     fn write_svg_with_style<W: std::io::Write>(
         &self,
         mut svg: W,
-        ts: TranslateScale,
+        ts: Affine,
         style: Option<&Style>,
         theme: &Theme,
     ) -> std::io::Result<()> {
+        if self.is_root() {
+            let (lines, frame) = self.break_lines(self.get_font().unwrap_or(Self::default_font()));
+
+            if let Some(frame) = frame {
+                let box_style = self.get_box().and_then(|b| theme.get_style(b.get_style_id()));
+
+                if box_style.is_some() {
+                    frame.write_svg_with_style(svg.by_ref(), ts, box_style, theme)?;
+                }
+            }
+
+            if let Cow::Owned(lines) = lines {
+                return lines.write_svg_with_style(svg, ts, style, theme)
+            }
+        }
+
+        // Rotated, skewed or non-uniformly scaled text is written in
+        // label coordinates and transformed as a whole.
+        let (ts, matrix) = if let Some(ts) = as_translate_scale(ts) {
+            (ts, None)
+        } else {
+            (TranslateScale::default(), Some(ts))
+        };
         let (_, scale_factor) = ts.as_tuple();
 
         if let Some(font) = self.get_font() {
             if self.is_root() {
                 let origin = ts * self.get_origin().unwrap_or_default();
                 write!(svg, "  <text x=\"{}\" y=\"{}\"", origin.x, origin.y,)?;
+
+                if let Some(matrix) = matrix {
+                    let [a, b, c, d, e, f] = matrix.as_coeffs();
+
+                    write!(svg, " transform=\"matrix({} {} {} {} {} {})\"", a, b, c, d, e, f)?;
+                }
             } else if let Some(origin) = self.get_origin() {
                 let origin = ts * origin;
                 write!(svg, "<tspan x=\"{}\" y=\"{}\"", origin.x, origin.y,)?;
@@ -485,12 +709,14 @@ impl WriteSvgWithStyle for TextLabel {
                     write!(svg, "\"")?;
                 }
             }
-            write!(
-                svg,
-                " font-family=\"{}\" font-size=\"{}\">",
-                font.get_family_name(),
-                font.get_size() * scale_factor,
+            self.write_svg_text_attributes(
+                svg.by_ref(),
+                scale_factor,
+                if self.is_root() { style } else { None },
+                theme,
             )?;
+            font.write_svg_family_with_theme(svg.by_ref(), theme)?;
+            write!(svg, " font-size=\"{}\">", font.get_size() * scale_factor)?;
 
             let mut buffer = Vec::new();
             for item in self.get_body() {
@@ -500,7 +726,7 @@ impl WriteSvgWithStyle for TextLabel {
                     }
                     crate::text::Item::Span(span) => {
                         buffer.clear();
-                        span.write_svg_with_style(&mut buffer, ts, style, theme)?;
+                        span.write_svg_with_style(&mut buffer, ts.into(), style, theme)?;
                         svg.write(buffer.as_slice())?;
                     }
                 }
@@ -513,7 +739,12 @@ impl WriteSvgWithStyle for TextLabel {
             }
         } else {
             // FIXME translation?
-            self.write_svg_with_scale(svg, scale_factor)
+            self.write_svg_with_scale(
+                svg,
+                scale_factor,
+                if self.is_root() { style } else { None },
+                theme,
+            )
         }
     }
 }
@@ -589,6 +820,46 @@ impl WriteSvgWithName for Gradient {
     }
 }
 
+impl WriteSvgWithTheme for Hatch {
+    fn write_svg_with_theme<W: std::io::Write, S: AsRef<str>>(
+        &self,
+        mut svg: W,
+        scale_factor: f64,
+        name: S,
+        _theme: &Theme,
+    ) -> std::io::Result<()> {
+        let spacing = self.get_spacing() * scale_factor;
+
+        writeln!(
+            svg,
+            "    <pattern id=\"{}\" patternUnits=\"userSpaceOnUse\" width=\"{}\" height=\"{}\" \
+             patternTransform=\"rotate({})\">",
+            name.as_ref(),
+            spacing,
+            spacing,
+            self.get_angle()
+        )?;
+
+        if let Some(background) = self.get_background() {
+            write!(svg, "      <rect width=\"{}\" height=\"{}\" ", spacing, spacing)?;
+            background.write_svg_with_name(svg.by_ref(), "fill")?;
+            writeln!(svg, " />")?;
+        }
+
+        write!(
+            svg,
+            "      <line x1=\"0\" y1=\"{}\" x2=\"{}\" y2=\"{}\" ",
+            0.5 * spacing,
+            spacing,
+            0.5 * spacing
+        )?;
+        self.get_brush().write_svg_with_name(svg.by_ref(), "stroke")?;
+        writeln!(svg, " stroke-width=\"{}\" />", self.get_width() * scale_factor)?;
+
+        writeln!(svg, "    </pattern>")
+    }
+}
+
 impl WriteSvgWithTheme for Marker {
     fn write_svg_with_theme<W: std::io::Write, S: AsRef<str>>(
         &self,
@@ -618,11 +889,82 @@ impl WriteSvgWithTheme for Marker {
 
         self.get_crumb().write_svg_with_style(
             svg.by_ref(),
-            TranslateScale::scale(scale_factor),
-            self.get_style_name().and_then(|name| theme.get_style_by_name(name)),
+            Affine::scale(scale_factor),
+            theme.get_style(self.get_style_id()),
             theme,
         )?;
 
         writeln!(svg, "    </marker>")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Group, UnitPoint};
+
+    #[test]
+    fn hatch_and_gradient_paints() {
+        let stops = vec![Color::WHITE, Color::BLACK];
+        let linear_gradients = vec![("fade", UnitPoint::LEFT, UnitPoint::RIGHT, stops.as_slice())];
+        let radial_gradients: Vec<(&str, f64, &[Color])> = Vec::new();
+        let strokes = vec![("faded", Stroke::new().with_named_gradient("fade").with_width(2.0))];
+        let fills = vec![("hatched", Fill::Hatch("lines".into()))];
+        let styles = vec![
+            ("frame", Style::new().with_named_stroke("faded")),
+            ("area", Style::new().with_named_fill("hatched")),
+        ];
+        let theme = Theme::new()
+            .with_gradients(linear_gradients, radial_gradients)
+            .with_hatches(vec![("lines", Hatch::default().with_spacing(4.0).with_angle(45.0))])
+            .with_strokes(strokes)
+            .with_fills(fills)
+            .with_styles(styles)
+            .build()
+            .unwrap();
+        let mut scene = Scene::new((100.0, 100.0));
+        let frame = scene.add_rect(Rect::new(10.0, 10.0, 90.0, 90.0));
+        let area = scene.add_rect(Rect::new(20.0, 20.0, 80.0, 80.0));
+
+        scene.add_layer(Group::from_crumbs(vec![
+            (frame, theme.get("frame")),
+            (area, theme.get("area")),
+        ]));
+
+        let svg = scene.to_svg(&theme, (200.0, 200.0), (0.0, 0.0)).unwrap();
+
+        assert!(svg.contains("<linearGradient id=\"fade\""));
+        assert!(svg.contains(
+            "<pattern id=\"lines\" patternUnits=\"userSpaceOnUse\" width=\"8\" height=\"8\" \
+             patternTransform=\"rotate(45)\">"
+        ));
+        assert!(svg.contains("<line x1=\"0\" y1=\"4\" x2=\"8\" y2=\"4\" stroke=\"#000000\""));
+        assert!(svg.contains("stroke=\"url(#fade)\" stroke-width=\"4\""));
+        assert!(svg.contains("fill=\"url(#lines)\""));
+    }
+
+    #[test]
+    fn spans_scaled() {
+        let theme = Theme::new();
+        let index = TextLabel::new()
+            .with_text("2")
+            .with_baseline_shift(BaselineShift::Length(3.0))
+            .with_letter_spacing(0.5);
+        let label = TextLabel::new()
+            .with_text("x")
+            .with_span(index)
+            .with_span(TextLabel::new().with_text("i").with_subscript())
+            .with_letter_spacing(1.5)
+            .with_xy(10.0, 50.0);
+        let mut scene = Scene::new((100.0, 100.0));
+        let label = scene.add_crumb(Crumb::Label(label));
+
+        scene.add_layer(Group::from_crumbs(vec![(label, None)]));
+
+        let svg = scene.to_svg(&theme, (200.0, 200.0), (0.0, 0.0)).unwrap();
+
+        assert!(svg.contains("<text x=\"20\" y=\"100\" letter-spacing=\"3\""));
+        assert!(svg.contains(" baseline-shift=\"6\" letter-spacing=\"1\" "));
+        assert!(svg.contains(" baseline-shift=\"sub\" font-family="));
+    }
+}
@@ -1,9 +1,10 @@
 use std::f64::consts::PI;
-use kurbo::{Point, Line, Rect, RoundedRect, Circle, Arc, BezPath, Shape, TranslateScale, Size};
+use kurbo::{Point, Line, Rect, RoundedRect, Circle, Arc, BezPath, Shape, Affine, Size};
 use usvg::NodeExt;
 use crate::{
-    Scene, Theme, Style, StyleId, Stroke, Fill, GradientStop, Gradient, Crumb, CrumbItem,
-    Crumbling, TextLabel, VisError,
+    Scene, PaintStep, Theme, Style, StyleId, Stroke, LineCap, LineJoin, Fill, Color, GradientStop,
+    Gradient, Hatch, Crumb, Crumbling, TextLabel, FontLibrary, BlendMode, VisError,
+    affine::{get_scale_factor, as_translate_scale},
 };
 
 pub use usvg::{Tree, FitTo};
@@ -33,12 +34,12 @@ impl AsUsvgTree for Scene {
         S: Into<Size>,
         M: Into<Size>,
     {
+        // Fonts are loaded first, so that labels are measured with
+        // them when fitting the content.
+        theme.load_fonts()?;
+
         let out_size = out_size.into();
-        let out_margin = out_margin.into();
-        let out_scale = ((out_size.width - 2. * out_margin.width) / self.get_size().width)
-            .min((out_size.height - 2. * out_margin.height) / self.get_size().height);
-        let root_ts =
-            TranslateScale::translate(out_margin.to_vec2()) * TranslateScale::scale(out_scale);
+        let root_ts = self.get_root_ts(theme, out_size, out_margin.into())?;
 
         let svg_size = usvg::Size::new(out_size.width.round(), out_size.height.round()).unwrap();
         let mut rtree = usvg::Tree::create(usvg::Svg {
@@ -48,7 +49,8 @@ impl AsUsvgTree for Scene {
                 aspect: usvg::AspectRatio::default(),
             },
         });
-        let mut root_node = rtree.root();
+        let mut parent_nodes = vec![rtree.root()];
+        let mut clip_count = 0;
 
         for (name, spec) in theme.get_named_gradspecs() {
             let node = spec.as_usvg_node_with_name(name);
@@ -56,21 +58,75 @@ impl AsUsvgTree for Scene {
             rtree.append_to_defs(node);
         }
 
-        theme.append_background_to_usvg_tree(&mut rtree);
+        for (name, hatch) in theme.get_named_hatches() {
+            hatch.append_to_usvg_defs(&mut rtree, name, get_scale_factor(root_ts));
+        }
 
-        for (_level, CrumbItem(crumb_id, ts, style_id)) in self.all_visible_crumbs(root_ts)? {
-            if let Some(crumb) = self.get_crumb(crumb_id) {
-                let (node_kind, more_kinds) = crumb.as_usvg_node_with_style(ts, style_id, theme);
+        theme.append_background_to_usvg_tree(&mut rtree);
 
-                if let Some(kind) = node_kind {
-                    root_node.append(usvg::Node::new(kind));
+        for step in self.visible_paint_steps_in(out_size.to_rect(), root_ts, theme)? {
+            match step {
+                PaintStep::BeginGroup(group, ts) => {
+                    if let Some(mode) = group.get_blend_mode() {
+                        if mode != BlendMode::Normal {
+                            return Err(VisError::unsupported_group_effect(format!(
+                                "blend mode '{}'",
+                                mode.as_css_name()
+                            )))
+                        }
+                    }
+
+                    let clip_path = if let Some(clip) = group.get_clip() {
+                        let id = theme.get_clip_id(clip_count);
+                        let mut clip_node =
+                            rtree.append_to_defs(usvg::NodeKind::ClipPath(usvg::ClipPath {
+                                id: id.clone(),
+                                ..Default::default()
+                            }));
+
+                        clip_count += 1;
+
+                        if let Some(path) = clip.get_clip_path() {
+                            clip_node.append_kind(usvg::NodeKind::Path(usvg::Path {
+                                fill: Some(usvg::Fill::default()),
+                                data: std::rc::Rc::new(path.as_path_data(ts)),
+                                ..Default::default()
+                            }));
+                        }
+
+                        Some(id)
+                    } else {
+                        None
+                    };
+                    let opacity = usvg::Opacity::new(group.get_opacity().unwrap_or(1.0));
+                    let group_node = usvg::Node::new(usvg::NodeKind::Group(usvg::Group {
+                        opacity,
+                        clip_path,
+                        ..Default::default()
+                    }));
+
+                    if let Some(parent_node) = parent_nodes.last_mut() {
+                        parent_node.append(group_node.clone());
+                    }
+                    parent_nodes.push(group_node);
                 }
-
-                for kind in more_kinds {
-                    root_node.append(usvg::Node::new(kind));
+                PaintStep::Crumb(crumb, ts, style_id) => {
+                    let (node_kind, more_kinds) =
+                        crumb.as_usvg_node_with_style(ts, style_id, theme);
+
+                    if let Some(parent_node) = parent_nodes.last_mut() {
+                        if let Some(kind) = node_kind {
+                            parent_node.append(usvg::Node::new(kind));
+                        }
+
+                        for kind in more_kinds {
+                            parent_node.append(usvg::Node::new(kind));
+                        }
+                    }
+                }
+                PaintStep::EndGroup => {
+                    parent_nodes.pop();
                 }
-            } else {
-                return Err(VisError::crumb_missing_for_id(crumb_id))
             }
         }
 
@@ -79,16 +135,16 @@ impl AsUsvgTree for Scene {
 }
 
 pub trait AsPathData {
-    fn as_path_data(&self, ts: TranslateScale) -> usvg::PathData;
+    fn as_path_data(&self, ts: Affine) -> usvg::PathData;
 
-    fn as_path_data_and_points(&self, ts: TranslateScale) -> (usvg::PathData, Vec<Point>) {
+    fn as_path_data_and_points(&self, ts: Affine) -> (usvg::PathData, Vec<Point>) {
         (self.as_path_data(ts), Vec::new())
     }
 }
 
 impl AsPathData for Crumb {
     #[inline]
-    fn as_path_data(&self, ts: TranslateScale) -> usvg::PathData {
+    fn as_path_data(&self, ts: Affine) -> usvg::PathData {
         match self {
             Crumb::Line(line) => line.as_path_data(ts),
             Crumb::Rect(rect) => rect.as_path_data(ts),
@@ -102,7 +158,7 @@ impl AsPathData for Crumb {
     }
 
     #[inline]
-    fn as_path_data_and_points(&self, ts: TranslateScale) -> (usvg::PathData, Vec<Point>) {
+    fn as_path_data_and_points(&self, ts: Affine) -> (usvg::PathData, Vec<Point>) {
         match self {
             Crumb::Line(line) => line.as_path_data_and_points(ts),
             Crumb::Rect(rect) => rect.as_path_data_and_points(ts),
@@ -118,13 +174,12 @@ impl AsPathData for Crumb {
 
 impl AsPathData for Line {
     #[inline]
-    fn as_path_data(&self, ts: TranslateScale) -> usvg::PathData {
+    fn as_path_data(&self, ts: Affine) -> usvg::PathData {
         self.as_path_data_and_points(ts).0
     }
 
-    fn as_path_data_and_points(&self, ts: TranslateScale) -> (usvg::PathData, Vec<Point>) {
-        let p0 = ts * self.p0;
-        let p1 = ts * self.p1;
+    fn as_path_data_and_points(&self, ts: Affine) -> (usvg::PathData, Vec<Point>) {
+        let Line { p0, p1 } = ts * *self;
 
         (
             usvg::PathData(vec![
@@ -137,7 +192,12 @@ impl AsPathData for Line {
 }
 
 impl AsPathData for Rect {
-    fn as_path_data(&self, ts: TranslateScale) -> usvg::PathData {
+    fn as_path_data(&self, ts: Affine) -> usvg::PathData {
+        let ts = if let Some(ts) = as_translate_scale(ts) {
+            ts
+        } else {
+            return self.to_path(0.1).as_path_data(ts)
+        };
         let rect = ts * *self;
 
         usvg::PathData(vec![
@@ -151,7 +211,12 @@ impl AsPathData for Rect {
 }
 
 impl AsPathData for RoundedRect {
-    fn as_path_data(&self, ts: TranslateScale) -> usvg::PathData {
+    fn as_path_data(&self, ts: Affine) -> usvg::PathData {
+        let ts = if let Some(ts) = as_translate_scale(ts) {
+            ts
+        } else {
+            return self.to_path(0.1).as_path_data(ts)
+        };
         let rr = ts * *self;
 
         if let Some(radius) = rr.radii().as_single_radius() {
@@ -177,13 +242,18 @@ impl AsPathData for RoundedRect {
 
             path
         } else {
-            self.rect().as_path_data(ts)
+            self.rect().as_path_data(ts.into())
         }
     }
 }
 
 impl AsPathData for Circle {
-    fn as_path_data(&self, ts: TranslateScale) -> usvg::PathData {
+    fn as_path_data(&self, ts: Affine) -> usvg::PathData {
+        let ts = if let Some(ts) = as_translate_scale(ts) {
+            ts
+        } else {
+            return self.to_path(0.1).as_path_data(ts)
+        };
         let center = ts * self.center;
         let (cx, cy) = (center.x, center.y);
         let radius = ts.as_tuple().1 * self.radius;
@@ -203,14 +273,14 @@ impl AsPathData for Circle {
 
 impl AsPathData for Arc {
     #[inline]
-    fn as_path_data(&self, ts: TranslateScale) -> usvg::PathData {
+    fn as_path_data(&self, ts: Affine) -> usvg::PathData {
         let path = BezPath::from_vec(self.path_elements(0.1).collect());
 
         path.as_path_data(ts)
     }
 
     #[inline]
-    fn as_path_data_and_points(&self, ts: TranslateScale) -> (usvg::PathData, Vec<Point>) {
+    fn as_path_data_and_points(&self, ts: Affine) -> (usvg::PathData, Vec<Point>) {
         let path = BezPath::from_vec(self.path_elements(0.1).collect());
 
         path.as_path_data_and_points(ts)
@@ -219,11 +289,11 @@ impl AsPathData for Arc {
 
 impl AsPathData for BezPath {
     #[inline]
-    fn as_path_data(&self, ts: TranslateScale) -> usvg::PathData {
+    fn as_path_data(&self, ts: Affine) -> usvg::PathData {
         self.as_path_data_and_points(ts).0
     }
 
-    fn as_path_data_and_points(&self, ts: TranslateScale) -> (usvg::PathData, Vec<Point>) {
+    fn as_path_data_and_points(&self, ts: Affine) -> (usvg::PathData, Vec<Point>) {
         let mut out_path = usvg::PathData::with_capacity(self.elements().len());
         let mut out_points = Vec::new();
 
@@ -267,20 +337,25 @@ impl AsPathData for BezPath {
 
 impl AsPathData for TextLabel {
     #[inline]
-    fn as_path_data(&self, ts: TranslateScale) -> usvg::PathData {
+    fn as_path_data(&self, ts: Affine) -> usvg::PathData {
         self.as_path_data_and_points(ts).0
     }
 
-    fn as_path_data_and_points(&self, _ts: TranslateScale) -> (usvg::PathData, Vec<Point>) {
-        // FIXME
-        (usvg::PathData::new(), Vec::new())
+    /// Returns glyph outlines of this label, set in its own font, or
+    /// in [`TextLabel::DEFAULT_FONT`].
+    fn as_path_data_and_points(&self, ts: Affine) -> (usvg::PathData, Vec<Point>) {
+        let font = self.get_font().unwrap_or(TextLabel::default_font());
+        let (lines, _) = self.break_lines(font);
+        let path = FontLibrary::with_system(|library| library.outline_label(&lines, font));
+
+        (path.as_path_data(ts), Vec::new())
     }
 }
 
 pub trait AsUsvgNodeWithStyle: Crumbling + AsPathData {
     fn as_usvg_node_with_style(
         &self,
-        ts: TranslateScale,
+        ts: Affine,
         style_id: Option<StyleId>,
         theme: &Theme,
     ) -> (Option<usvg::NodeKind>, Vec<usvg::NodeKind>) {
@@ -290,6 +365,7 @@ pub trait AsUsvgNodeWithStyle: Crumbling + AsPathData {
             (None, Vec::new())
         } else {
             let (fill, stroke) = theme.get_style_as_usvg(style_id);
+            let stroke = stroke.map(|s| scale_stroke(s, get_scale_factor(ts)));
             let data = std::rc::Rc::new(path_data);
 
             (
@@ -304,7 +380,7 @@ impl AsUsvgNodeWithStyle for Crumb {
     #[inline]
     fn as_usvg_node_with_style(
         &self,
-        ts: TranslateScale,
+        ts: Affine,
         style_id: Option<StyleId>,
         theme: &Theme,
     ) -> (Option<usvg::NodeKind>, Vec<usvg::NodeKind>) {
@@ -324,7 +400,7 @@ impl AsUsvgNodeWithStyle for Crumb {
 impl AsUsvgNodeWithStyle for Line {
     fn as_usvg_node_with_style(
         &self,
-        ts: TranslateScale,
+        ts: Affine,
         style_id: Option<StyleId>,
         theme: &Theme,
     ) -> (Option<usvg::NodeKind>, Vec<usvg::NodeKind>) {
@@ -333,18 +409,16 @@ impl AsUsvgNodeWithStyle for Line {
         let stroke = style
             .get_stroke()
             .or_else(|| theme.get_default_style().get_stroke())
-            .map(|s| s.as_usvg());
+            .map(|s| scale_stroke(s.as_usvg(), get_scale_factor(ts)));
 
         let path_data = {
             let markers = style.get_markers();
 
-            if let Some(marker) =
-                markers.get_end_name().and_then(|name| theme.get_marker_by_name(name))
-            {
+            if let Some(marker) = theme.get_marker(markers.get_end_id()) {
                 let (path_data, points) = self.as_path_data_and_points(ts);
 
                 // FIXME precompute marker's path data, clone it here.
-                let mut marker_data = marker.get_crumb().as_path_data(TranslateScale::default());
+                let mut marker_data = marker.get_crumb().as_path_data(Affine::IDENTITY);
 
                 // FIXME precompute unit width.
                 let unit_width = stroke.as_ref().map(|s| s.width.value()).unwrap_or(1.0);
@@ -365,9 +439,7 @@ impl AsUsvgNodeWithStyle for Line {
                     p1.y - b * refx - a * refy,
                 ));
 
-                let (stroke, fill) = if let Some(style) =
-                    marker.get_style_name().and_then(|name| theme.get_style_by_name(name))
-                {
+                let (stroke, fill) = if let Some(style) = theme.get_style(marker.get_style_id()) {
                     (style.get_stroke().map(|s| s.as_usvg()), style.get_fill().map(|s| s.as_usvg()))
                 } else {
                     (None, None)
@@ -400,7 +472,7 @@ impl AsUsvgNodeWithStyle for Circle {}
 impl AsUsvgNodeWithStyle for Arc {
     fn as_usvg_node_with_style(
         &self,
-        ts: TranslateScale,
+        ts: Affine,
         style_id: Option<StyleId>,
         theme: &Theme,
     ) -> (Option<usvg::NodeKind>, Vec<usvg::NodeKind>) {
@@ -413,7 +485,7 @@ impl AsUsvgNodeWithStyle for Arc {
 impl AsUsvgNodeWithStyle for BezPath {
     fn as_usvg_node_with_style(
         &self,
-        ts: TranslateScale,
+        ts: Affine,
         style_id: Option<StyleId>,
         theme: &Theme,
     ) -> (Option<usvg::NodeKind>, Vec<usvg::NodeKind>) {
@@ -422,19 +494,17 @@ impl AsUsvgNodeWithStyle for BezPath {
         let stroke = style
             .get_stroke()
             .or_else(|| theme.get_default_style().get_stroke())
-            .map(|s| s.as_usvg());
+            .map(|s| scale_stroke(s.as_usvg(), get_scale_factor(ts)));
         let fill = style.get_fill().map(|f| f.as_usvg());
 
         let path_data = {
             let markers = style.get_markers();
 
-            if let Some(marker) =
-                markers.get_end_name().and_then(|name| theme.get_marker_by_name(name))
-            {
+            if let Some(marker) = theme.get_marker(markers.get_end_id()) {
                 let (path_data, points) = self.as_path_data_and_points(ts);
 
                 // FIXME precompute marker's path data, clone it here.
-                let mut marker_data = marker.get_crumb().as_path_data(TranslateScale::default());
+                let mut marker_data = marker.get_crumb().as_path_data(Affine::IDENTITY);
 
                 // FIXME precompute unit width.
                 let unit_width = stroke.as_ref().map(|s| s.width.value()).unwrap_or(1.0);
@@ -455,9 +525,7 @@ impl AsUsvgNodeWithStyle for BezPath {
                     p1.y - b * refx - a * refy,
                 ));
 
-                let (stroke, fill) = if let Some(style) =
-                    marker.get_style_name().and_then(|name| theme.get_style_by_name(name))
-                {
+                let (stroke, fill) = if let Some(style) = theme.get_style(marker.get_style_id()) {
                     (style.get_stroke().map(|s| s.as_usvg()), style.get_fill().map(|s| s.as_usvg()))
                 } else {
                     (None, None)
@@ -486,7 +554,59 @@ impl AsUsvgNodeWithStyle for BezPath {
     }
 }
 
-impl AsUsvgNodeWithStyle for TextLabel {}
+/// Text is rendered as glyph outlines filled with the fill of a
+/// style, or black, if there is no style, or it has no fill.  Spans
+/// with styles of their own are filled separately, with the fills of
+/// their styles, if any, or else as the rest of a label.  The box
+/// of a label, if any, is rendered first, as a rounded rectangle with
+/// the style of the box.
+impl AsUsvgNodeWithStyle for TextLabel {
+    fn as_usvg_node_with_style(
+        &self,
+        ts: Affine,
+        style_id: Option<StyleId>,
+        theme: &Theme,
+    ) -> (Option<usvg::NodeKind>, Vec<usvg::NodeKind>) {
+        let style = theme.get_style(style_id);
+        let font = self.get_font().unwrap_or_else(|| TextLabel::get_default_font(style, theme));
+        let (lines, frame) = self.break_lines(font);
+        let paths =
+            FontLibrary::with_system(|library| library.outline_label_by_style(&lines, font));
+        let label_fill = style.and_then(|s| s.get_fill());
+        let mut kinds = Vec::new();
+
+        if let (Some(frame), Some(box_style_id)) =
+            (frame, self.get_box().and_then(|b| b.get_style_id()))
+        {
+            let (node_kind, more_kinds) =
+                frame.as_usvg_node_with_style(ts, Some(box_style_id), theme);
+
+            kinds.extend(node_kind);
+            kinds.extend(more_kinds);
+        }
+
+        for (run_style_id, path) in paths {
+            if !path.elements().is_empty() {
+                let fill = theme
+                    .get_style(run_style_id)
+                    .and_then(|s| s.get_fill())
+                    .or(label_fill)
+                    .map_or_else(|| Fill::Color(Color::BLACK).as_usvg(), |f| f.as_usvg());
+                let data = std::rc::Rc::new(path.as_path_data(ts));
+
+                kinds.push(usvg::NodeKind::Path(usvg::Path {
+                    fill: Some(fill),
+                    data,
+                    ..Default::default()
+                }));
+            }
+        }
+
+        let mut kinds = kinds.into_iter();
+
+        (kinds.next(), kinds.collect())
+    }
+}
 
 pub trait AsUsvgNodeWithName {
     fn as_usvg_node_with_name<S: AsRef<str>>(&self, name: S) -> usvg::NodeKind;
@@ -533,6 +653,57 @@ impl AsUsvgNodeWithName for Gradient {
     }
 }
 
+pub trait AppendToUsvgDefs {
+    fn append_to_usvg_defs<S: AsRef<str>>(
+        &self,
+        rtree: &mut usvg::Tree,
+        name: S,
+        scale_factor: f64,
+    );
+}
+
+impl AppendToUsvgDefs for Hatch {
+    fn append_to_usvg_defs<S: AsRef<str>>(
+        &self,
+        rtree: &mut usvg::Tree,
+        name: S,
+        scale_factor: f64,
+    ) {
+        let spacing = self.get_spacing() * scale_factor;
+        let tile = Rect::new(0.0, 0.0, spacing, spacing);
+        let line = Line::new((0.0, 0.5 * spacing), (spacing, 0.5 * spacing));
+
+        if let Some(rect) = usvg::Rect::new(0.0, 0.0, spacing, spacing) {
+            let mut pattern_node = rtree.append_to_defs(usvg::NodeKind::Pattern(usvg::Pattern {
+                id: name.as_ref().into(),
+                units: usvg::Units::UserSpaceOnUse,
+                content_units: usvg::Units::UserSpaceOnUse,
+                transform: usvg::Transform::new_rotate(self.get_angle()),
+                rect,
+                view_box: None,
+            }));
+
+            if let Some(background) = self.get_background() {
+                pattern_node.append_kind(usvg::NodeKind::Path(usvg::Path {
+                    fill: Some(Fill::Color(*background).as_usvg()),
+                    data: std::rc::Rc::new(tile.as_path_data(Affine::IDENTITY)),
+                    ..Default::default()
+                }));
+            }
+
+            let stroke = Stroke::new()
+                .with_brush(*self.get_brush())
+                .with_width(self.get_width() * scale_factor);
+
+            pattern_node.append_kind(usvg::NodeKind::Path(usvg::Path {
+                stroke: Some(stroke.as_usvg()),
+                data: std::rc::Rc::new(line.as_path_data(Affine::IDENTITY)),
+                ..Default::default()
+            }));
+        }
+    }
+}
+
 pub trait AsUsvgTheme {
     fn get_style_as_usvg(
         &self,
@@ -592,24 +763,52 @@ pub trait AsUsvgStroke {
 impl AsUsvgStroke for Stroke {
     fn as_usvg(&self) -> usvg::Stroke {
         let (red, green, blue, alpha) = self.get_brush().as_rgba8();
-
-        if alpha == 0xff {
-            usvg::Stroke {
-                paint: usvg::Paint::Color(usvg::Color::new_rgb(red, green, blue)),
-                width: self.get_width().into(),
-                ..Default::default()
-            }
+        let (paint, alpha) = if let Some(name) = self.get_gradient_name() {
+            (usvg::Paint::Link(name.into()), 0xff)
         } else {
-            usvg::Stroke {
-                paint: usvg::Paint::Color(usvg::Color::new_rgb(red, green, blue)),
-                opacity: (alpha as f64 / 255.0).into(),
-                width: self.get_width().into(),
-                ..Default::default()
-            }
+            (usvg::Paint::Color(usvg::Color::new_rgb(red, green, blue)), alpha)
+        };
+        let dasharray = if self.is_dashed() { Some(self.get_dash_array().to_vec()) } else { None };
+        let linecap = match self.get_cap() {
+            LineCap::Butt => usvg::LineCap::Butt,
+            LineCap::Round => usvg::LineCap::Round,
+            LineCap::Square => usvg::LineCap::Square,
+        };
+        let linejoin = match self.get_join() {
+            LineJoin::Miter => usvg::LineJoin::Miter,
+            LineJoin::Round => usvg::LineJoin::Round,
+            LineJoin::Bevel => usvg::LineJoin::Bevel,
+        };
+
+        usvg::Stroke {
+            paint,
+            dasharray,
+            dashoffset: self.get_dash_offset() as f32,
+            miterlimit: usvg::StrokeMiterlimit::new(self.get_miter_limit().max(1.0)),
+            opacity: (alpha as f64 / 255.0).into(),
+            width: self.get_width().into(),
+            linecap,
+            linejoin,
         }
     }
 }
 
+/// Scales the width and the dash pattern of a `stroke` of a crumb by
+/// the scale factor of the crumb's transformation, as in the other
+/// backends.
+fn scale_stroke(mut stroke: usvg::Stroke, scale_factor: f64) -> usvg::Stroke {
+    stroke.width = (stroke.width.value() * scale_factor).into();
+    stroke.dashoffset *= scale_factor as f32;
+
+    if let Some(dasharray) = stroke.dasharray.as_mut() {
+        for length in dasharray.iter_mut() {
+            *length *= scale_factor;
+        }
+    }
+
+    stroke
+}
+
 pub trait AsUsvgFill {
     fn as_usvg(&self) -> usvg::Fill;
 }
@@ -622,7 +821,9 @@ impl AsUsvgFill for Fill {
 
                 (usvg::Paint::Color(usvg::Color::new_rgb(red, green, blue)), alpha)
             }
-            Fill::Linear(name) | Fill::Radial(name) => (usvg::Paint::Link(name.into()), 0xff),
+            Fill::Linear(name) | Fill::Radial(name) | Fill::Hatch(name) => {
+                (usvg::Paint::Link(name.into()), 0xff)
+            }
         };
 
         if alpha == 0xff {
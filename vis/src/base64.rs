@@ -0,0 +1,115 @@
+const DIGITS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encodes `data` in the standard base64 alphabet, with padding.
+pub(crate) fn encode(data: &[u8]) -> String {
+    let mut result = String::with_capacity(data.len().div_ceil(3) * 4);
+
+    for chunk in data.chunks(3) {
+        let bits = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |bits, (i, byte)| bits | (*byte as u32) << (16 - 8 * i));
+        let mut quad = ['='; 4];
+
+        for (i, digit) in quad.iter_mut().take(chunk.len() + 1).enumerate() {
+            *digit = DIGITS[(bits >> (18 - 6 * i)) as usize & 0x3f] as char;
+        }
+
+        result.extend(quad);
+    }
+
+    result
+}
+
+/// Decodes base64 `text` in the standard alphabet, with or without
+/// padding.  Returns `None` if the `text` is malformed.
+#[cfg(feature = "serialize")]
+pub(crate) fn decode(text: &str) -> Option<Vec<u8>> {
+    let text = text.as_bytes();
+    let end = text.iter().rposition(|c| *c != b'=').map_or(0, |pos| pos + 1);
+
+    if text.len() - end > 2 {
+        return None
+    }
+
+    let mut result = Vec::with_capacity(end / 4 * 3 + 2);
+
+    for chunk in text[..end].chunks(4) {
+        if chunk.len() == 1 {
+            return None
+        }
+
+        let mut bits = 0u32;
+
+        for (i, digit) in chunk.iter().enumerate() {
+            let value = DIGITS.iter().position(|d| d == digit)?;
+
+            bits |= (value as u32) << (18 - 6 * i);
+        }
+
+        for i in 0..chunk.len() - 1 {
+            result.push((bits >> (16 - 8 * i)) as u8);
+        }
+    }
+
+    Some(result)
+}
+
+/// Serializes bytes as a base64 string, see `#[serde(with)]`.
+#[cfg(feature = "serialize")]
+pub(crate) fn serialize<S: serde::Serializer>(
+    data: &[u8],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&encode(data))
+}
+
+/// Deserializes bytes from a base64 string, see `#[serde(with)]`.
+#[cfg(feature = "serialize")]
+pub(crate) fn deserialize<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<u8>, D::Error> {
+    use serde::{Deserialize, de::Error};
+
+    let text = String::deserialize(deserializer)?;
+
+    decode(&text).ok_or_else(|| D::Error::custom("malformed base64 data"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VECTORS: [(&str, &str); 7] = [
+        ("", ""),
+        ("f", "Zg=="),
+        ("fo", "Zm8="),
+        ("foo", "Zm9v"),
+        ("foob", "Zm9vYg=="),
+        ("fooba", "Zm9vYmE="),
+        ("foobar", "Zm9vYmFy"),
+    ];
+
+    #[test]
+    fn encode_vectors() {
+        for (data, text) in VECTORS {
+            assert_eq!(encode(data.as_bytes()), text);
+        }
+
+        assert_eq!(encode(&[0xfb, 0xff, 0xbf]), "+/+/");
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn decode_vectors() {
+        for (data, text) in VECTORS {
+            assert_eq!(decode(text).as_deref(), Some(data.as_bytes()));
+        }
+
+        assert_eq!(decode("Zm8").as_deref(), Some(&b"fo"[..]));
+        assert_eq!(decode("+/+/").as_deref(), Some(&[0xfb, 0xff, 0xbf][..]));
+        assert_eq!(decode("Zm9vY"), None);
+        assert_eq!(decode("Zm9v!A=="), None);
+        assert_eq!(decode("Zg==="), None);
+    }
+}
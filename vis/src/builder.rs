@@ -176,6 +176,13 @@ impl Iterator for PinIter<'_> {
     }
 }
 
+const NODE_NAME_SIZE: f64 = 28.0;
+const SPAN_SIZE: f64 = 22.0;
+// Baseline shifts of upper and lower spans, as fractions of the
+// ascent of a node name.
+const UPPER_SPAN_RISE: f64 = 0.6;
+const LOWER_SPAN_DROP: f64 = 0.5;
+
 struct NodeLabelEntry {
     node_name: String,
     node_ref:  NodeRef,
@@ -374,15 +381,20 @@ impl Iterator for NodeLabelIter<'_> {
                     .with_text(node_name)
                     .with_end_anchor()
                     .with_origin(origin)
-                    .with_font_size(28.0);
+                    .with_font_size(NODE_NAME_SIZE);
+
+                // Spans are shifted in proportion to the measured
+                // ascent of a node name, so that they stay clear of
+                // the name in any font.
+                let ascent = label.get_metrics().get_ascent();
 
                 if let Some(upper) = upper {
                     label.append_span(
                         TextLabel::new()
                             .with_text(upper)
                             .with_origin(origin)
-                            .with_dy([-16.0])
-                            .with_font_size(22.0),
+                            .with_dy([-UPPER_SPAN_RISE * ascent])
+                            .with_font_size(SPAN_SIZE),
                     );
                 }
 
@@ -391,8 +403,8 @@ impl Iterator for NodeLabelIter<'_> {
                         TextLabel::new()
                             .with_text(lower)
                             .with_origin(origin)
-                            .with_dy([12.0])
-                            .with_font_size(22.0),
+                            .with_dy([LOWER_SPAN_DROP * ascent])
+                            .with_font_size(SPAN_SIZE),
                     );
                 }
 
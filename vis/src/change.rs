@@ -0,0 +1,103 @@
+use std::collections::HashSet;
+use crate::{CrumbId, GroupId};
+
+/// A record of changes made to a [`Scene`](crate::Scene), see
+/// [`Scene::take_changes()`](crate::Scene::take_changes) and
+/// [`Scene::diff()`](crate::Scene::diff).
+///
+/// Crumbs and groups are listed if they were added, modified or
+/// removed.  Layers are listed if they were added or removed, or if
+/// their visibility or stacking order changed.
+#[derive(Clone, Default, Debug)]
+pub struct SceneChanges {
+    crumbs:     HashSet<CrumbId>,
+    groups:     HashSet<GroupId>,
+    layers:     HashSet<GroupId>,
+    is_resized: bool,
+}
+
+impl SceneChanges {
+    #[inline]
+    pub(crate) fn mark_crumb(&mut self, crumb_id: CrumbId) {
+        self.crumbs.insert(crumb_id);
+    }
+
+    #[inline]
+    pub(crate) fn mark_group(&mut self, group_id: GroupId) {
+        self.groups.insert(group_id);
+    }
+
+    #[inline]
+    pub(crate) fn mark_layer(&mut self, group_id: GroupId) {
+        self.layers.insert(group_id);
+    }
+
+    #[inline]
+    pub(crate) fn mark_resized(&mut self) {
+        self.is_resized = true;
+    }
+
+    /// Adds all changes recorded in `other` to this record.
+    pub fn merge(&mut self, other: SceneChanges) {
+        self.crumbs.extend(other.crumbs);
+        self.groups.extend(other.groups);
+        self.layers.extend(other.layers);
+        self.is_resized |= other.is_resized;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.crumbs.is_empty()
+            && self.groups.is_empty()
+            && self.layers.is_empty()
+            && !self.is_resized
+    }
+
+    /// Returns `true` if the size or fit mode of the scene changed,
+    /// in which case everything needs repainting.
+    #[inline]
+    pub fn is_resized(&self) -> bool {
+        self.is_resized
+    }
+
+    #[inline]
+    pub fn has_crumb(&self, crumb_id: CrumbId) -> bool {
+        self.crumbs.contains(&crumb_id)
+    }
+
+    #[inline]
+    pub fn has_group(&self, group_id: GroupId) -> bool {
+        self.groups.contains(&group_id)
+    }
+
+    #[inline]
+    pub fn has_layer(&self, group_id: GroupId) -> bool {
+        self.layers.contains(&group_id)
+    }
+
+    /// Lists changed crumbs in the order of creation.
+    pub fn get_crumbs(&self) -> Vec<CrumbId> {
+        let mut result: Vec<_> = self.crumbs.iter().copied().collect();
+
+        result.sort_by_key(|id| id.0);
+
+        result
+    }
+
+    /// Lists changed groups in the order of creation.
+    pub fn get_groups(&self) -> Vec<GroupId> {
+        let mut result: Vec<_> = self.groups.iter().copied().collect();
+
+        result.sort_by_key(|id| id.0);
+
+        result
+    }
+
+    /// Lists changed layers in the order of creation of their groups.
+    pub fn get_layers(&self) -> Vec<GroupId> {
+        let mut result: Vec<_> = self.layers.iter().copied().collect();
+
+        result.sort_by_key(|id| id.0);
+
+        result
+    }
+}
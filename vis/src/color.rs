@@ -0,0 +1,753 @@
+use std::str::FromStr;
+use cssparser as css;
+use crate::{Color, Rgba, VisError};
+
+/// Converts a gamma-encoded sRGB component to linear light, both in
+/// the range [0..1].
+fn srgb_to_linear(c: f64) -> f64 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Converts a linear light component to gamma-encoded sRGB, both in
+/// the range [0..1].
+fn linear_to_srgb(c: f64) -> f64 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn mul_matrix(m: &[[f64; 3]; 3], v: [f64; 3]) -> [f64; 3] {
+    [
+        m[0][0] * v[0] + m[0][1] * v[1] + m[0][2] * v[2],
+        m[1][0] * v[0] + m[1][1] * v[1] + m[1][2] * v[2],
+        m[2][0] * v[0] + m[2][1] * v[1] + m[2][2] * v[2],
+    ]
+}
+
+// Linear sRGB to CIE XYZ, and back, for the D65 white point.
+const RGB_TO_XYZ: [[f64; 3]; 3] = [
+    [0.4124564, 0.3575761, 0.1804375],
+    [0.2126729, 0.7151522, 0.0721750],
+    [0.0193339, 0.1191920, 0.9503041],
+];
+const XYZ_TO_RGB: [[f64; 3]; 3] = [
+    [3.2404542, -1.5371385, -0.4985314],
+    [-0.9692660, 1.8760108, 0.0415560],
+    [0.0556434, -0.2040259, 1.0572252],
+];
+const WHITE_XYZ: [f64; 3] = [0.95047, 1.0, 1.08883];
+const LAB_EPSILON: f64 = 216.0 / 24389.0;
+const LAB_KAPPA: f64 = 24389.0 / 27.0;
+
+// Linear sRGB to OKLab's cone responses, cone responses to OKLab, and
+// back.
+const RGB_TO_LMS: [[f64; 3]; 3] = [
+    [0.4122214708, 0.5363325363, 0.0514459929],
+    [0.2119034982, 0.6806995451, 0.1073969566],
+    [0.0883024619, 0.2817188376, 0.6299787005],
+];
+const LMS_TO_OKLAB: [[f64; 3]; 3] = [
+    [0.2104542553, 0.7936177850, -0.0040720468],
+    [1.9779984951, -2.4285922050, 0.4505937099],
+    [0.0259040371, 0.7827717662, -0.8086757660],
+];
+const OKLAB_TO_LMS: [[f64; 3]; 3] = [
+    [1.0, 0.3963377774, 0.2158037573],
+    [1.0, -0.1055613458, -0.0638541728],
+    [1.0, -0.0894841775, -1.2914855480],
+];
+const LMS_TO_RGB: [[f64; 3]; 3] = [
+    [4.0767416621, -3.3077115913, 0.2309699292],
+    [-1.2684380046, 2.6097574011, -0.3413193965],
+    [-0.0041960863, -0.7034186147, 1.7076147010],
+];
+
+/// Chroma below which a color has no OKLCH hue.
+const OKLCH_MIN_CHROMA: f64 = 1e-4;
+
+#[inline]
+fn lerp(v0: f64, v1: f64, t: f64) -> f64 {
+    v0 + (v1 - v0) * t
+}
+
+/// Interpolates between two hues in degrees, along the shorter arc.
+/// A missing hue is replaced with the other one, if any, or with
+/// zero.
+fn lerp_hue(h0: Option<f64>, h1: Option<f64>, t: f64) -> f64 {
+    match (h0, h1) {
+        (Some(h0), Some(h1)) => {
+            let mut delta = (h1 - h0).rem_euclid(360.0);
+
+            if delta > 180.0 {
+                delta -= 360.0;
+            }
+
+            (h0 + delta * t).rem_euclid(360.0)
+        }
+        (Some(hue), None) | (None, Some(hue)) => hue,
+        (None, None) => 0.0,
+    }
+}
+
+/// Returns red, green and blue components of a fully saturated color
+/// of a given `hue` (in degrees) and `chroma`, before adding the
+/// gray level common to HSV and HSL.
+fn hue_to_rgb(hue: f64, chroma: f64) -> [f64; 3] {
+    let sextant = hue.rem_euclid(360.0) / 60.0;
+    let middle = chroma * (1.0 - (sextant % 2.0 - 1.0).abs());
+
+    match sextant as u32 {
+        0 => [chroma, middle, 0.0],
+        1 => [middle, chroma, 0.0],
+        2 => [0.0, chroma, middle],
+        3 => [0.0, middle, chroma],
+        4 => [middle, 0.0, chroma],
+        _ => [chroma, 0.0, middle],
+    }
+}
+
+/// Returns hue in degrees (`None` for grays), the maximum component
+/// and chroma of a color given by red, green and blue components in
+/// the range [0..1].
+fn rgb_to_hue(rgb: [f64; 3]) -> (Option<f64>, f64, f64) {
+    let [red, green, blue] = rgb;
+    let max = red.max(green).max(blue);
+    let min = red.min(green).min(blue);
+    let chroma = max - min;
+
+    let hue = if chroma <= 0.0 {
+        None
+    } else if max == red {
+        Some(60.0 * ((green - blue) / chroma).rem_euclid(6.0))
+    } else if max == green {
+        Some(60.0 * ((blue - red) / chroma + 2.0))
+    } else {
+        Some(60.0 * ((red - green) / chroma + 4.0))
+    };
+
+    (hue, max, chroma)
+}
+
+/// A color space in which colors are interpolated, see
+/// [`Color::mix()`] and [`Theme::with_color_space()`](crate::Theme::with_color_space).
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum ColorSpace {
+    /// Gamma-encoded sRGB components, as stored.
+    Srgb,
+    /// sRGB components in linear light.
+    LinearRgb,
+    /// Hue, saturation and value.
+    #[default]
+    Hsv,
+    /// Hue, saturation and lightness.
+    Hsl,
+    /// CIE L\*a\*b\*.
+    Lab,
+    /// OKLab, a perceptually uniform space.
+    Oklab,
+    /// The polar form of OKLab, which keeps midpoints of two
+    /// saturated colors saturated.
+    Oklch,
+}
+
+/// A kind of color vision deficiency, see [`ColorDeficiency::simulate()`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum ColorDeficiency {
+    /// Lack of long-wavelength (red) cones.
+    Protanopia,
+    /// Lack of medium-wavelength (green) cones.
+    Deuteranopia,
+    /// Lack of short-wavelength (blue) cones.
+    Tritanopia,
+}
+
+impl ColorDeficiency {
+    /// Returns the color seen instead of a given `color` by a person
+    /// with this deficiency.
+    ///
+    /// The simulation is that of Machado, Oliveira and Fernandes
+    /// (2009), for full severity, applied in linear sRGB.
+    pub fn simulate(&self, color: Color) -> Color {
+        let matrix = match self {
+            ColorDeficiency::Protanopia => [
+                [0.152286, 1.052583, -0.204868],
+                [0.114503, 0.786281, 0.099216],
+                [-0.003882, -0.048116, 1.051998],
+            ],
+            ColorDeficiency::Deuteranopia => [
+                [0.367322, 0.860646, -0.227968],
+                [0.280085, 0.672501, 0.047413],
+                [-0.011820, 0.042940, 0.968881],
+            ],
+            ColorDeficiency::Tritanopia => [
+                [1.255528, -0.076749, -0.178779],
+                [-0.078411, 0.930809, 0.147602],
+                [0.004733, 0.691367, 0.303900],
+            ],
+        };
+        let (rgb, alpha) = color.as_linear_rgba();
+
+        Color::from_linear_rgba(mul_matrix(&matrix, rgb), alpha)
+    }
+
+    /// Returns the name of this deficiency, as used for naming
+    /// variations, see
+    /// [`Theme::with_color_deficiency_variation()`](crate::Theme::with_color_deficiency_variation).
+    pub fn as_name(&self) -> &'static str {
+        match self {
+            ColorDeficiency::Protanopia => "protanopia",
+            ColorDeficiency::Deuteranopia => "deuteranopia",
+            ColorDeficiency::Tritanopia => "tritanopia",
+        }
+    }
+}
+
+impl Color {
+    /// Returns the red, green, blue and alpha components, all in the
+    /// range [0..1].
+    fn as_unit_rgba(self) -> ([f64; 3], f64) {
+        let Rgba::<f64>(red, green, blue, alpha) = self.into();
+
+        ([red / 255.0, green / 255.0, blue / 255.0], alpha / 255.0)
+    }
+
+    /// Returns the red, green and blue components in linear light,
+    /// and alpha, all in the range [0..1].
+    pub fn as_linear_rgba(self) -> ([f64; 3], f64) {
+        let Rgba::<f64>(red, green, blue, alpha) = self.into();
+
+        (
+            [
+                srgb_to_linear(red / 255.0),
+                srgb_to_linear(green / 255.0),
+                srgb_to_linear(blue / 255.0),
+            ],
+            alpha / 255.0,
+        )
+    }
+
+    /// Creates a color from red, green and blue components in linear
+    /// light, and alpha, all clamped to the range [0..1].
+    pub fn from_linear_rgba(rgb: [f64; 3], alpha: f64) -> Self {
+        let [red, green, blue] = rgb;
+
+        Color::rgba(
+            linear_to_srgb(red.max(0.0).min(1.0)),
+            linear_to_srgb(green.max(0.0).min(1.0)),
+            linear_to_srgb(blue.max(0.0).min(1.0)),
+            alpha,
+        )
+    }
+
+    /// Creates a color from _hue_ in degrees, and _saturation_,
+    /// _value_ and alpha in the range [0..1].  _Hue_ is taken modulo
+    /// 360, other components are clamped.
+    pub fn from_hsva(hue: f64, saturation: f64, value: f64, alpha: f64) -> Self {
+        let saturation = saturation.max(0.0).min(1.0);
+        let value = value.max(0.0).min(1.0);
+        let chroma = value * saturation;
+        let [red, green, blue] = hue_to_rgb(hue, chroma);
+        let bottom = value - chroma;
+
+        Color::rgba(red + bottom, green + bottom, blue + bottom, alpha)
+    }
+
+    /// Returns _hue_ in degrees, in the range [0..360), and
+    /// _saturation_, _value_ and alpha in the range [0..1].
+    ///
+    /// _Hue_ is `None` iff _saturation_ is zero (for any _value_ of
+    /// gray).
+    pub fn as_hsva(self) -> (Option<f64>, f64, f64, f64) {
+        let (rgb, alpha) = self.as_unit_rgba();
+        let (hue, max, chroma) = rgb_to_hue(rgb);
+        let saturation = if max > 0.0 { chroma / max } else { 0.0 };
+
+        (hue, saturation, max, alpha)
+    }
+
+    /// Creates a color from _hue_ in degrees, and _saturation_,
+    /// _lightness_ and alpha in the range [0..1].  _Hue_ is taken
+    /// modulo 360, other components are clamped.
+    pub fn from_hsla(hue: f64, saturation: f64, lightness: f64, alpha: f64) -> Self {
+        let saturation = saturation.max(0.0).min(1.0);
+        let lightness = lightness.max(0.0).min(1.0);
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        let [red, green, blue] = hue_to_rgb(hue, chroma);
+        let bottom = lightness - chroma / 2.0;
+
+        Color::rgba(red + bottom, green + bottom, blue + bottom, alpha)
+    }
+
+    /// Returns _hue_ in degrees, in the range [0..360), and
+    /// _saturation_, _lightness_ and alpha in the range [0..1].
+    ///
+    /// _Hue_ is `None` iff _saturation_ is zero.
+    pub fn as_hsla(self) -> (Option<f64>, f64, f64, f64) {
+        let (rgb, alpha) = self.as_unit_rgba();
+        let (hue, max, chroma) = rgb_to_hue(rgb);
+        let lightness = max - chroma / 2.0;
+        let saturation =
+            if hue.is_some() { chroma / (1.0 - (2.0 * lightness - 1.0).abs()) } else { 0.0 };
+
+        (hue, saturation, lightness, alpha)
+    }
+
+    /// Creates a color from OKLCH coordinates, see
+    /// [`Color::as_oklch()`].  Colors out of the sRGB gamut are
+    /// clipped.
+    pub fn from_oklch(lightness: f64, chroma: f64, hue: f64, alpha: f64) -> Self {
+        let (sin, cos) = hue.to_radians().sin_cos();
+        let chroma = chroma.max(0.0);
+
+        Color::from_oklab(lightness, chroma * cos, chroma * sin, alpha)
+    }
+
+    /// Returns the OKLCH coordinates of this color, i.e. the polar
+    /// form of [`Color::as_oklab()`]: _lightness_ in the range
+    /// [0..1], _chroma_, _hue_ in degrees, in the range [0..360), and
+    /// alpha in the range [0..1].
+    ///
+    /// _Hue_ is `None` for (nearly) achromatic colors.
+    pub fn as_oklch(self) -> (f64, f64, Option<f64>, f64) {
+        let (lightness, a, b, alpha) = self.as_oklab();
+        let chroma = a.hypot(b);
+        let hue = if chroma < OKLCH_MIN_CHROMA {
+            None
+        } else {
+            Some(b.atan2(a).to_degrees().rem_euclid(360.0))
+        };
+
+        (lightness, chroma, hue, alpha)
+    }
+
+    /// Mixes this color with `other` in a given color `space`, taking
+    /// `amount` of the latter, where `amount` is clamped to the range
+    /// [0..1].
+    ///
+    /// In cylindrical spaces hue is interpolated along the shorter
+    /// arc.  The hue of a gray is that of the other color.
+    pub fn mix(self, other: Color, amount: f64, space: ColorSpace) -> Self {
+        let t = amount.max(0.0).min(1.0);
+
+        match space {
+            ColorSpace::Srgb => {
+                let ([r0, g0, b0], alpha0) = self.as_unit_rgba();
+                let ([r1, g1, b1], alpha1) = other.as_unit_rgba();
+
+                Color::rgba(
+                    lerp(r0, r1, t),
+                    lerp(g0, g1, t),
+                    lerp(b0, b1, t),
+                    lerp(alpha0, alpha1, t),
+                )
+            }
+            ColorSpace::LinearRgb => {
+                let ([r0, g0, b0], alpha0) = self.as_linear_rgba();
+                let ([r1, g1, b1], alpha1) = other.as_linear_rgba();
+
+                Color::from_linear_rgba(
+                    [lerp(r0, r1, t), lerp(g0, g1, t), lerp(b0, b1, t)],
+                    lerp(alpha0, alpha1, t),
+                )
+            }
+            ColorSpace::Hsv => {
+                let (h0, s0, v0, alpha0) = self.as_hsva();
+                let (h1, s1, v1, alpha1) = other.as_hsva();
+
+                Color::from_hsva(
+                    lerp_hue(h0, h1, t),
+                    lerp(s0, s1, t),
+                    lerp(v0, v1, t),
+                    lerp(alpha0, alpha1, t),
+                )
+            }
+            ColorSpace::Hsl => {
+                let (h0, s0, l0, alpha0) = self.as_hsla();
+                let (h1, s1, l1, alpha1) = other.as_hsla();
+
+                Color::from_hsla(
+                    lerp_hue(h0, h1, t),
+                    lerp(s0, s1, t),
+                    lerp(l0, l1, t),
+                    lerp(alpha0, alpha1, t),
+                )
+            }
+            ColorSpace::Lab => self.mix_lab(other, t),
+            ColorSpace::Oklab => self.mix_oklab(other, t),
+            ColorSpace::Oklch => {
+                let (l0, c0, h0, alpha0) = self.as_oklch();
+                let (l1, c1, h1, alpha1) = other.as_oklch();
+
+                Color::from_oklch(
+                    lerp(l0, l1, t),
+                    lerp(c0, c1, t),
+                    lerp_hue(h0, h1, t),
+                    lerp(alpha0, alpha1, t),
+                )
+            }
+        }
+    }
+
+    /// Returns the relative luminance of this color, as defined by
+    /// WCAG 2, in the range [0..1].  Alpha is ignored.
+    pub fn relative_luminance(self) -> f64 {
+        let ([red, green, blue], _) = self.as_linear_rgba();
+
+        0.2126 * red + 0.7152 * green + 0.0722 * blue
+    }
+
+    /// Returns the WCAG 2 contrast ratio between this color and
+    /// `other`, in the range [1..21].  Alpha is ignored, see
+    /// [`Color::over()`].
+    pub fn contrast_ratio(self, other: Color) -> f64 {
+        let l1 = self.relative_luminance();
+        let l2 = other.relative_luminance();
+
+        (l1.max(l2) + 0.05) / (l1.min(l2) + 0.05)
+    }
+
+    /// Returns this color composited over an opaque `background`.
+    pub fn over(self, background: Color) -> Self {
+        let Rgba::<f64>(red, green, blue, alpha) = self.into();
+        let Rgba::<f64>(bg_red, bg_green, bg_blue, _) = background.into();
+        let alpha = alpha / 255.0;
+
+        Color::rgb(
+            (red * alpha + bg_red * (1.0 - alpha)) / 255.0,
+            (green * alpha + bg_green * (1.0 - alpha)) / 255.0,
+            (blue * alpha + bg_blue * (1.0 - alpha)) / 255.0,
+        )
+    }
+
+    /// Returns the CIE L\*a\*b\* coordinates of this color (for the
+    /// D65 white point), and alpha in the range [0..1].
+    ///
+    /// _Lightness_ is in the range [0..100].
+    pub fn as_lab(self) -> (f64, f64, f64, f64) {
+        let (rgb, alpha) = self.as_linear_rgba();
+        let xyz = mul_matrix(&RGB_TO_XYZ, rgb);
+        let f = |t: f64| if t > LAB_EPSILON { t.cbrt() } else { (LAB_KAPPA * t + 16.0) / 116.0 };
+        let fx = f(xyz[0] / WHITE_XYZ[0]);
+        let fy = f(xyz[1] / WHITE_XYZ[1]);
+        let fz = f(xyz[2] / WHITE_XYZ[2]);
+
+        (116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz), alpha)
+    }
+
+    /// Creates a color from CIE L\*a\*b\* coordinates, see
+    /// [`Color::as_lab()`].  Colors out of the sRGB gamut are clipped.
+    pub fn from_lab(lightness: f64, a: f64, b: f64, alpha: f64) -> Self {
+        let fy = (lightness + 16.0) / 116.0;
+        let fx = fy + a / 500.0;
+        let fz = fy - b / 200.0;
+        let f_inv = |f: f64| {
+            let t = f * f * f;
+
+            if t > LAB_EPSILON {
+                t
+            } else {
+                (116.0 * f - 16.0) / LAB_KAPPA
+            }
+        };
+        let xyz = [f_inv(fx) * WHITE_XYZ[0], f_inv(fy) * WHITE_XYZ[1], f_inv(fz) * WHITE_XYZ[2]];
+
+        Color::from_linear_rgba(mul_matrix(&XYZ_TO_RGB, xyz), alpha)
+    }
+
+    /// Returns the OKLab coordinates of this color, and alpha in the
+    /// range [0..1].
+    ///
+    /// _Lightness_ is in the range [0..1].
+    pub fn as_oklab(self) -> (f64, f64, f64, f64) {
+        let (rgb, alpha) = self.as_linear_rgba();
+        let [l, m, s] = mul_matrix(&RGB_TO_LMS, rgb);
+        let [lightness, a, b] = mul_matrix(&LMS_TO_OKLAB, [l.cbrt(), m.cbrt(), s.cbrt()]);
+
+        (lightness, a, b, alpha)
+    }
+
+    /// Creates a color from OKLab coordinates, see
+    /// [`Color::as_oklab()`].  Colors out of the sRGB gamut are
+    /// clipped.
+    pub fn from_oklab(lightness: f64, a: f64, b: f64, alpha: f64) -> Self {
+        let [l, m, s] = mul_matrix(&OKLAB_TO_LMS, [lightness, a, b]);
+
+        Color::from_linear_rgba(mul_matrix(&LMS_TO_RGB, [l * l * l, m * m * m, s * s * s]), alpha)
+    }
+
+    /// Mixes this color with `other` in CIE L\*a\*b\*, taking
+    /// `amount` of the latter, where `amount` is clamped to the range
+    /// [0..1].
+    pub fn mix_lab(self, other: Color, amount: f64) -> Self {
+        let t = amount.max(0.0).min(1.0);
+        let (l0, a0, b0, alpha0) = self.as_lab();
+        let (l1, a1, b1, alpha1) = other.as_lab();
+
+        Color::from_lab(
+            lerp(l0, l1, t),
+            lerp(a0, a1, t),
+            lerp(b0, b1, t),
+            lerp(alpha0, alpha1, t),
+        )
+    }
+
+    /// Mixes this color with `other` in OKLab, taking `amount` of the
+    /// latter, where `amount` is clamped to the range [0..1].
+    pub fn mix_oklab(self, other: Color, amount: f64) -> Self {
+        let t = amount.max(0.0).min(1.0);
+        let (l0, a0, b0, alpha0) = self.as_oklab();
+        let (l1, a1, b1, alpha1) = other.as_oklab();
+
+        Color::from_oklab(
+            lerp(l0, l1, t),
+            lerp(a0, a1, t),
+            lerp(b0, b1, t),
+            lerp(alpha0, alpha1, t),
+        )
+    }
+
+    /// Returns the color in the `#rrggbb` notation, if opaque, or in
+    /// the `rgba()` notation, otherwise.
+    pub fn to_css_string(&self) -> String {
+        let (r, g, b, a) = self.as_rgba8();
+
+        if a == 255 {
+            format!("#{:02x}{:02x}{:02x}", r, g, b)
+        } else {
+            format!("rgba({}, {}, {}, {:.3})", r, g, b, f64::from(a) / 255.0)
+        }
+    }
+}
+
+impl FromStr for Color {
+    type Err = VisError;
+
+    /// Parses a color given in any of the notations of CSS Color
+    /// Level 3: `#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa`, `rgb()`,
+    /// `rgba()`, `hsl()`, `hsla()`, or a color keyword (including
+    /// `transparent`, but not `currentcolor`).
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let mut input = css::ParserInput::new(spec);
+        let mut parser = css::Parser::new(&mut input);
+
+        match parser.parse_entirely(|parser| css::Color::parse(parser).map_err(Into::into)) {
+            Ok(css::Color::RGBA(rgba)) => {
+                Ok(Color::rgba8(rgba.red, rgba.green, rgba.blue, rgba.alpha))
+            }
+            Ok(css::Color::CurrentColor) => {
+                Err(VisError::invalid_color(spec, "\"currentcolor\" isn't supported"))
+            }
+            Err(css::ParseError::<()> { kind, .. }) => {
+                Err(VisError::invalid_color(spec, format!("{:?}", kind)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_colors() -> impl Iterator<Item = Color> {
+        let levels = (0..=255).step_by(15).map(|v| v as u8);
+
+        levels.clone().flat_map(move |r| {
+            let levels = levels.clone();
+
+            levels.clone().flat_map(move |g| levels.clone().map(move |b| Color::rgb8(r, g, b)))
+        })
+    }
+
+    #[test]
+    fn hsva_primaries() {
+        assert_eq!(Color::from_hsva(0.0, 1.0, 1.0, 1.0), Color::rgb8(255, 0, 0));
+        assert_eq!(Color::from_hsva(60.0, 1.0, 1.0, 1.0), Color::rgb8(255, 255, 0));
+        assert_eq!(Color::from_hsva(120.0, 1.0, 1.0, 1.0), Color::rgb8(0, 255, 0));
+        assert_eq!(Color::from_hsva(240.0, 1.0, 0.5, 1.0), Color::rgb8(0, 0, 128));
+        assert_eq!(Color::from_hsva(-60.0, 1.0, 1.0, 0.0), Color::rgba8(255, 0, 255, 0));
+        assert_eq!(Color::from_hsva(420.0, 0.5, 1.0, 1.0), Color::rgb8(255, 255, 128));
+        assert_eq!(Color::from_hsva(90.0, 0.0, 0.5, 1.0), Color::rgb8(128, 128, 128));
+        assert_eq!(Color::rgb8(128, 128, 128).as_hsva().0, None);
+        assert_eq!(Color::rgb8(0, 255, 255).as_hsva(), (Some(180.0), 1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn hsla_primaries() {
+        assert_eq!(Color::from_hsla(0.0, 1.0, 0.5, 1.0), Color::rgb8(255, 0, 0));
+        assert_eq!(Color::from_hsla(120.0, 1.0, 0.25, 1.0), Color::rgb8(0, 128, 0));
+        assert_eq!(Color::from_hsla(240.0, 1.0, 1.0, 1.0), Color::WHITE);
+        assert_eq!(Color::from_hsla(300.0, 0.5, 0.0, 1.0), Color::BLACK);
+        assert_eq!(Color::rgb8(255, 255, 255).as_hsla(), (None, 0.0, 1.0, 1.0));
+        assert_eq!(
+            "hsl(200, 60%, 40%)".parse::<Color>().unwrap(),
+            Color::from_hsla(200.0, 0.6, 0.4, 1.0)
+        );
+    }
+
+    #[test]
+    fn hsva_round_trip() {
+        for color in sample_colors() {
+            let (hue, saturation, value, alpha) = color.as_hsva();
+
+            assert_eq!(hue.is_none(), saturation == 0.0);
+            assert_eq!(Color::from_hsva(hue.unwrap_or(0.0), saturation, value, alpha), color);
+        }
+    }
+
+    #[test]
+    fn hsla_round_trip() {
+        for color in sample_colors() {
+            let (hue, saturation, lightness, alpha) = color.as_hsla();
+
+            assert_eq!(hue.is_none(), saturation == 0.0);
+            assert_eq!(Color::from_hsla(hue.unwrap_or(0.0), saturation, lightness, alpha), color);
+        }
+    }
+
+    #[test]
+    fn oklch_round_trip() {
+        for color in sample_colors() {
+            let (lightness, chroma, hue, alpha) = color.as_oklch();
+
+            assert_eq!(Color::from_oklch(lightness, chroma, hue.unwrap_or(0.0), alpha), color);
+        }
+        assert_eq!(Color::rgb8(77, 77, 77).as_oklch().2, None);
+    }
+
+    #[test]
+    fn linear_round_trip() {
+        for color in sample_colors() {
+            let (rgb, alpha) = color.as_linear_rgba();
+
+            assert_eq!(Color::from_linear_rgba(rgb, alpha), color);
+        }
+
+        let ([red, green, blue], _) = Color::rgb8(255, 128, 0).as_linear_rgba();
+
+        assert_eq!(red, 1.0);
+        assert!((green - 0.2158605).abs() < 1e-6);
+        assert_eq!(blue, 0.0);
+    }
+
+    #[test]
+    fn mix_endpoints() {
+        let red = Color::rgb8(255, 0, 0);
+        let teal = Color::rgba8(0, 128, 128, 64);
+
+        for space in [
+            ColorSpace::Srgb,
+            ColorSpace::LinearRgb,
+            ColorSpace::Hsv,
+            ColorSpace::Hsl,
+            ColorSpace::Lab,
+            ColorSpace::Oklab,
+            ColorSpace::Oklch,
+        ] {
+            assert_eq!(red.mix(teal, 0.0, space), red, "{:?}", space);
+            assert_eq!(red.mix(teal, 1.0, space), teal, "{:?}", space);
+        }
+    }
+
+    #[test]
+    fn mix_along_shorter_arc() {
+        let red = Color::rgb8(255, 0, 0);
+        let magenta = Color::rgb8(255, 0, 255);
+        let gray = Color::rgb8(128, 128, 128);
+
+        assert_eq!(red.mix(magenta, 0.5, ColorSpace::Hsv), Color::from_hsva(330.0, 1.0, 1.0, 1.0));
+        assert_eq!(magenta.mix(red, 0.5, ColorSpace::Hsl), Color::from_hsla(330.0, 1.0, 0.5, 1.0));
+        assert_eq!(gray.mix(red, 0.5, ColorSpace::Hsv).as_hsva().0, Some(0.0));
+
+        let (_, chroma, _, _) = red.mix(Color::rgb8(0, 0, 255), 0.5, ColorSpace::Oklch).as_oklch();
+        let (_, muddy, _, _) = red.mix(Color::rgb8(0, 0, 255), 0.5, ColorSpace::Srgb).as_oklch();
+
+        assert!(chroma > muddy);
+    }
+
+    #[test]
+    fn contrast_ratios() {
+        let gray = Color::rgb8(119, 119, 119);
+
+        assert!((Color::BLACK.contrast_ratio(Color::WHITE) - 21.0).abs() < 1e-9);
+        assert_eq!(
+            Color::WHITE.contrast_ratio(Color::BLACK),
+            Color::BLACK.contrast_ratio(Color::WHITE)
+        );
+        assert_eq!(gray.contrast_ratio(gray), 1.0);
+        assert!((gray.contrast_ratio(Color::WHITE) - 4.48).abs() < 0.01);
+        assert_eq!(Color::rgba8(0, 0, 0, 0).over(Color::WHITE), Color::WHITE);
+        assert_eq!(Color::rgba8(0, 0, 0, 255).over(Color::WHITE), Color::BLACK);
+    }
+
+    #[test]
+    fn simulation_keeps_grays() {
+        let deficiencies = [
+            ColorDeficiency::Protanopia,
+            ColorDeficiency::Deuteranopia,
+            ColorDeficiency::Tritanopia,
+        ];
+
+        for deficiency in deficiencies {
+            for level in (0..=255).step_by(15) {
+                let gray = Color::rgba8(level, level, level, 200);
+
+                assert_eq!(deficiency.simulate(gray), gray, "{:?}", deficiency);
+            }
+            assert_ne!(deficiency.simulate(Color::rgb8(255, 0, 0)), Color::rgb8(255, 0, 0));
+        }
+    }
+
+    #[test]
+    fn mix_lab_midpoint() {
+        let black_to_white = Color::BLACK.mix_lab(Color::WHITE, 0.5);
+        let (lightness, ..) = black_to_white.as_lab();
+
+        assert!((lightness - 50.0).abs() < 0.5);
+        assert_eq!(Color::BLACK.mix_oklab(Color::WHITE, 0.0), Color::BLACK);
+        assert_eq!(Color::BLACK.mix_oklab(Color::WHITE, 1.0), Color::WHITE);
+    }
+
+    #[test]
+    fn parse_notations() {
+        let parse = |spec: &str| spec.parse::<Color>().unwrap();
+
+        assert_eq!(parse("#f80"), Color::rgb8(0xff, 0x88, 0));
+        assert_eq!(parse("#f808"), Color::rgba8(0xff, 0x88, 0, 0x88));
+        assert_eq!(parse("#FF8000"), Color::rgb8(0xff, 0x80, 0));
+        assert_eq!(parse("#ff800080"), Color::rgba8(0xff, 0x80, 0, 0x80));
+        assert_eq!(parse("rgb(0, 128, 255)"), Color::rgb8(0, 128, 255));
+        assert_eq!(parse("rgba(100%, 0%, 0%, 0.5)"), Color::rgba8(255, 0, 0, 128));
+        assert_eq!(parse("hsla(120, 100%, 25%, 0)"), Color::rgba8(0, 128, 0, 0));
+        assert_eq!(parse("  rebeccapurple "), Color::rgb8(0x66, 0x33, 0x99));
+        assert_eq!(parse("DarkSlateGray"), Color::rgb8(0x2f, 0x4f, 0x4f));
+        assert_eq!(parse("transparent"), Color::rgba8(0, 0, 0, 0));
+
+        for spec in &["currentcolor", "#12345", "#ggg", "rgb(1, 2)", "nocolor", "red blue", ""] {
+            assert!(spec.parse::<Color>().is_err(), "{:?} accepted", spec);
+        }
+    }
+
+    #[test]
+    fn css_string() {
+        assert_eq!(Color::rgb8(0xff, 0x80, 0).to_css_string(), "#ff8000");
+        assert_eq!(Color::rgba8(0, 0, 0, 0).to_css_string(), "rgba(0, 0, 0, 0.000)");
+        assert_eq!(Color::rgba8(255, 0, 0, 128).to_css_string(), "rgba(255, 0, 0, 0.502)");
+
+        for color in sample_colors().step_by(7) {
+            assert_eq!(color.to_css_string().parse::<Color>().unwrap(), color);
+
+            let (r, g, b, _) = color.as_rgba8();
+            let translucent = Color::rgba8(r, g, b, g);
+
+            assert_eq!(translucent.to_css_string().parse::<Color>().unwrap(), translucent);
+        }
+    }
+}
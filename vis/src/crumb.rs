@@ -1,14 +1,17 @@
 use std::f64::consts::PI;
-use kurbo::{Point, Line, Rect, RoundedRect, Circle, Arc, BezPath, TranslateScale};
-use crate::{Scene, StyleId, TextLabel, VisError};
+use kurbo::{Shape, PathEl, Point, Line, Rect, RoundedRect, Circle, Arc, BezPath, Affine};
+use crate::{Scene, Theme, Style, StyleId, TextLabel, VisError, affine::get_scale_factor};
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct CrumbId(pub usize);
 
-#[derive(Clone, Copy, Debug)]
-pub struct CrumbItem(pub CrumbId, pub TranslateScale, pub Option<StyleId>);
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct CrumbItem(pub CrumbId, pub Affine, pub Option<StyleId>);
 
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub enum Crumb {
     Line(Line),
     Rect(Rect),
@@ -39,6 +42,168 @@ impl_into_crumb!(Arc, Arc);
 impl_into_crumb!(BezPath, Path);
 impl_into_crumb!(TextLabel, Label);
 
+impl Crumb {
+    /// Checks whether a `point`, given in output coordinates, hits
+    /// this crumb transformed by `ts` and painted with `style` (or
+    /// with the default style of a `theme`).
+    ///
+    /// Interiors are hit only if the style specifies a fill, and
+    /// outlines are widened by half of the stroke width.  Text
+    /// labels are hit anywhere inside their boxes, see
+    /// [`TextLabel::get_bounds()`].
+    pub fn hit_test(
+        &self,
+        point: Point,
+        ts: Affine,
+        style: Option<&Style>,
+        theme: &Theme,
+    ) -> bool {
+        let style = style.unwrap_or_else(|| theme.get_default_style());
+        let is_filled = style.get_fill().is_some();
+        let half_width = style
+            .get_stroke()
+            .or_else(|| theme.get_default_style().get_stroke())
+            .map(|s| 0.5 * s.get_width() * get_scale_factor(ts));
+
+        match self {
+            Crumb::Line(line) => shape_hit_test(line, point, ts, false, half_width),
+            Crumb::Rect(rect) => shape_hit_test(rect, point, ts, is_filled, half_width),
+            Crumb::RoundedRect(rr) => shape_hit_test(rr, point, ts, is_filled, half_width),
+            Crumb::Circle(circ) => shape_hit_test(circ, point, ts, is_filled, half_width),
+            Crumb::Arc(arc) => shape_hit_test(arc, point, ts, is_filled, half_width),
+            Crumb::Path(path) => shape_hit_test(path, point, ts, is_filled, half_width),
+            Crumb::Pin(_) => false,
+            Crumb::Label(label) => label.get_bounds().contains(ts.inverse() * point),
+        }
+    }
+
+    /// Returns the box, in output coordinates, covered by this crumb
+    /// transformed by `ts` and painted with `style` (or with the
+    /// default style of a `theme`).
+    ///
+    /// Outlines are widened by half of the stroke width, and by the
+    /// full reach of markers, whichever orientation they take.  Pins
+    /// aren't painted, hence have no bounding box.
+    pub fn bounding_box(
+        &self,
+        ts: Affine,
+        style: Option<&Style>,
+        theme: &Theme,
+    ) -> Option<Rect> {
+        let style = style.unwrap_or_else(|| theme.get_default_style());
+        let half_width = style
+            .get_stroke()
+            .or_else(|| theme.get_default_style().get_stroke())
+            .map(|s| 0.5 * s.get_width())
+            .unwrap_or(0.0);
+
+        let rect = self.get_shape_bounds(ts)?;
+        let outset = match self {
+            Crumb::Line(_) | Crumb::Arc(_) | Crumb::Path(_) => {
+                half_width.max(theme.get_marker_extent(style))
+            }
+            Crumb::Rect(_) | Crumb::RoundedRect(_) | Crumb::Circle(_) => half_width,
+            Crumb::Pin(_) | Crumb::Label(_) => 0.0,
+        };
+        let outset = outset * get_scale_factor(ts);
+
+        Some(rect.inflate(outset, outset))
+    }
+
+    /// Returns the box, in output coordinates, covered by the bare
+    /// geometry of this crumb transformed by `ts`, i.e. ignoring
+    /// strokes and markers.
+    pub(crate) fn get_shape_bounds(&self, ts: Affine) -> Option<Rect> {
+        match self {
+            Crumb::Line(line) => Some(shape_bounding_box(line, ts)),
+            Crumb::Rect(rect) => Some(shape_bounding_box(rect, ts)),
+            Crumb::RoundedRect(rr) => Some(shape_bounding_box(rr, ts)),
+            Crumb::Circle(circ) => Some(shape_bounding_box(circ, ts)),
+            Crumb::Arc(arc) => Some(shape_bounding_box(arc, ts)),
+            Crumb::Path(path) => Some(shape_bounding_box(path, ts)),
+            Crumb::Pin(_) => None,
+            Crumb::Label(label) => Some(ts.transform_rect_bbox(label.get_bounds())),
+        }
+    }
+
+    /// Returns the outline of this crumb used as a clip shape, or
+    /// `None` if the crumb encloses no area.
+    pub(crate) fn get_clip_path(&self) -> Option<BezPath> {
+        match self {
+            Crumb::Line(_) | Crumb::Pin(_) => None,
+            Crumb::Rect(rect) => Some(rect.to_path(0.1)),
+            Crumb::RoundedRect(rr) => Some(rr.to_path(0.1)),
+            Crumb::Circle(circ) => Some(circ.to_path(0.1)),
+            Crumb::Arc(arc) => Some(arc.to_path(0.1)),
+            Crumb::Path(path) => Some(path.clone()),
+            Crumb::Label(label) => Some(label.get_bounds().to_path(0.1)),
+        }
+    }
+}
+
+fn shape_bounding_box<S: Shape>(shape: &S, ts: Affine) -> Rect {
+    (ts * shape.to_path(0.1)).bounding_box()
+}
+
+fn shape_hit_test<S: Shape>(
+    shape: &S,
+    point: Point,
+    ts: Affine,
+    is_filled: bool,
+    half_width: Option<f64>,
+) -> bool {
+    if is_filled && shape.contains(ts.inverse() * point) {
+        true
+    } else if let Some(hw) = half_width {
+        outline_distance(shape, ts, point) <= hw
+    } else {
+        false
+    }
+}
+
+/// Returns the distance between a `point` and the flattened outline
+/// of a `shape` transformed by `ts`.
+fn outline_distance<S: Shape>(shape: &S, ts: Affine, point: Point) -> f64 {
+    let mut result = f64::INFINITY;
+    let mut start_point = None;
+    let mut last_point = None;
+
+    kurbo::flatten(shape.path_elements(0.1).map(|el| ts * el), 0.1, |path_el| match path_el {
+        PathEl::MoveTo(p) => {
+            start_point = Some(p);
+            last_point = Some(p);
+        }
+        PathEl::LineTo(p) => {
+            if let Some(p0) = last_point {
+                result = result.min(segment_distance(p0, p, point));
+            }
+            last_point = Some(p);
+        }
+        PathEl::ClosePath => {
+            if let (Some(p0), Some(p1)) = (last_point, start_point) {
+                result = result.min(segment_distance(p0, p1, point));
+            }
+            last_point = start_point;
+        }
+        _ => {}
+    });
+
+    result
+}
+
+fn segment_distance(p0: Point, p1: Point, point: Point) -> f64 {
+    let segment = p1 - p0;
+    let length_sq = segment.hypot2();
+
+    if length_sq > 0.0 {
+        let t = ((point - p0).dot(segment) / length_sq).clamp(0.0, 1.0);
+
+        point.distance(p0 + segment * t)
+    } else {
+        point.distance(p0)
+    }
+}
+
 pub trait Crumbling: Into<Crumb> {
     fn end_angle(&self, points: &[Point]) -> f64 {
         if let Some((p1, head)) = points.split_last() {
@@ -108,12 +273,19 @@ impl CrumbSet {
 
     pub fn try_for_each_label<F>(&self, scene: &mut Scene, mut f: F) -> Result<(), VisError>
     where
-        F: FnMut(&mut TextLabel, TranslateScale, Option<StyleId>) -> Result<(), VisError>,
+        F: FnMut(&mut TextLabel, Affine, Option<StyleId>) -> Result<(), VisError>,
     {
         for CrumbItem(crumb_id, ts, style_id) in &self.crumbs {
-            if let Some(crumb) = scene.get_crumb_mut(*crumb_id) {
+            if let Some(crumb) = scene.get_crumb_mut_untracked(*crumb_id) {
                 if let Crumb::Label(label) = crumb {
+                    let bounds = label.get_bounds();
+
                     f(label, *ts, *style_id)?;
+
+                    // Estimated bounds of a label depend on its layout.
+                    if label.get_bounds() != bounds {
+                        scene.invalidate_spatial_index();
+                    }
                 }
             } else {
                 return Err(VisError::crumb_missing_for_id(*crumb_id))
@@ -145,7 +317,7 @@ pub struct CrumbSetIter<'a> {
 }
 
 impl<'a> Iterator for CrumbSetIter<'a> {
-    type Item = Result<(&'a Crumb, TranslateScale, Option<StyleId>), VisError>;
+    type Item = Result<(&'a Crumb, Affine, Option<StyleId>), VisError>;
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
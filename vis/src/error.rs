@@ -18,6 +18,13 @@ macro_rules! detailed_error {
 #[derive(Debug)]
 enum InnerError {
     IO(std::io::Error),
+    #[cfg(feature = "serialize")]
+    Json(serde_json::Error),
+    #[cfg(feature = "serialize")]
+    TomlRead(toml::de::Error, Option<(usize, usize)>),
+    #[cfg(feature = "serialize")]
+    TomlWrite(toml::ser::Error),
+    InvalidColor(String, String),
     CrumbMismatch(String, Crumb, CrumbId),
     CrumbMissingForId(CrumbId),
     GroupMissingForId(GroupId),
@@ -25,11 +32,23 @@ enum InnerError {
     MarkerMissingForId(MarkerId),
     GradientMismatchForName(String),
     GradientMissingForName(String),
+    HatchMissingForName(String),
+    StyleMissingForName(String),
+    MarkerMissingForName(String),
+    StrokeMissingForName(String),
+    FillMissingForName(String),
+    StyleCycleForName(String),
     GroupReuseAttempt(GroupId),
     CrumbsOfAGroupOverflow(GroupId, usize),
     GroupsOfAGroupOverflow(GroupId, usize),
     BuilderOverflow(String, usize),
     BuilderUnresolved(String),
+    #[cfg(feature = "fvg")]
+    TextRenderingFailure(String),
+    #[cfg(any(feature = "tiny", feature = "fvg"))]
+    UnsupportedGroupEffect(String),
+    #[cfg(feature = "text")]
+    MalformedFontForName(String),
 }
 
 macro_rules! impl_inner_error {
@@ -57,6 +76,10 @@ macro_rules! impl_inner_error {
 }
 
 impl_inner_error!(std::io::Error, IO);
+#[cfg(feature = "serialize")]
+impl_inner_error!(serde_json::Error, Json);
+#[cfg(feature = "serialize")]
+impl_inner_error!(toml::ser::Error, TomlWrite);
 
 impl DetailedError for InnerError {
     #[inline]
@@ -71,6 +94,17 @@ impl std::fmt::Display for InnerError {
 
         match self {
             IO(err) => write!(f, "IO error {:?}", err),
+            #[cfg(feature = "serialize")]
+            Json(err) => write!(f, "JSON error {}", err),
+            #[cfg(feature = "serialize")]
+            TomlRead(err, Some((line, column))) => {
+                write!(f, "TOML error {} at line {} column {}", err.message(), line, column)
+            }
+            #[cfg(feature = "serialize")]
+            TomlRead(err, None) => write!(f, "TOML error {}", err.message()),
+            #[cfg(feature = "serialize")]
+            TomlWrite(err) => write!(f, "TOML error {}", err),
+            InvalidColor(spec, reason) => write!(f, "Invalid color \"{}\": {}", spec, reason),
             CrumbMismatch(name, crumb, crumb_id) => {
                 write!(f, "Unexpected {:?} instead of {} for {:?}", crumb, name, crumb_id)
             }
@@ -80,6 +114,12 @@ impl std::fmt::Display for InnerError {
             MarkerMissingForId(marker_id) => write!(f, "Marker missing for {:?}", marker_id),
             GradientMismatchForName(name) => write!(f, "Gradient mismatch for '{}'", name),
             GradientMissingForName(name) => write!(f, "Gradient missing for '{}'", name),
+            HatchMissingForName(name) => write!(f, "Hatch missing for '{}'", name),
+            StyleMissingForName(name) => write!(f, "Style missing for '{}'", name),
+            MarkerMissingForName(name) => write!(f, "Marker missing for '{}'", name),
+            StrokeMissingForName(name) => write!(f, "Stroke missing for '{}'", name),
+            FillMissingForName(name) => write!(f, "Fill missing for '{}'", name),
+            StyleCycleForName(name) => write!(f, "Style '{}' extends itself", name),
             GroupReuseAttempt(group_id) => write!(f, "Reuse attempt for {:?}", group_id),
             CrumbsOfAGroupOverflow(group_id, index) => {
                 write!(f, "Index {} overflows grouped crumbs for {:?}", index, group_id)
@@ -93,6 +133,12 @@ impl std::fmt::Display for InnerError {
                 num_items, name
             ),
             BuilderUnresolved(name) => write!(f, "Unresolved {} in a builder", name),
+            #[cfg(feature = "fvg")]
+            TextRenderingFailure(reason) => write!(f, "Text rendering failure: {}", reason),
+            #[cfg(any(feature = "tiny", feature = "fvg"))]
+            UnsupportedGroupEffect(effect) => write!(f, "Unsupported group effect: {}", effect),
+            #[cfg(feature = "text")]
+            MalformedFontForName(name) => write!(f, "Malformed font data for '{}'", name),
         }
     }
 }
@@ -104,6 +150,10 @@ pub struct VisError {
 }
 
 impl VisError {
+    pub(crate) fn invalid_color<S: AsRef<str>, R: AsRef<str>>(spec: S, reason: R) -> Self {
+        InnerError::InvalidColor(spec.as_ref().to_string(), reason.as_ref().to_string()).into()
+    }
+
     pub(crate) fn crumb_mismatch<S: AsRef<str>>(name: S, crumb: Crumb, crumb_id: CrumbId) -> Self {
         InnerError::CrumbMismatch(name.as_ref().to_string(), crumb, crumb_id).into()
     }
@@ -132,6 +182,30 @@ impl VisError {
         InnerError::GradientMissingForName(name.as_ref().to_string()).into()
     }
 
+    pub(crate) fn hatch_missing_for_name<S: AsRef<str>>(name: S) -> Self {
+        InnerError::HatchMissingForName(name.as_ref().to_string()).into()
+    }
+
+    pub(crate) fn style_missing_for_name<S: AsRef<str>>(name: S) -> Self {
+        InnerError::StyleMissingForName(name.as_ref().to_string()).into()
+    }
+
+    pub(crate) fn marker_missing_for_name<S: AsRef<str>>(name: S) -> Self {
+        InnerError::MarkerMissingForName(name.as_ref().to_string()).into()
+    }
+
+    pub(crate) fn stroke_missing_for_name<S: AsRef<str>>(name: S) -> Self {
+        InnerError::StrokeMissingForName(name.as_ref().to_string()).into()
+    }
+
+    pub(crate) fn fill_missing_for_name<S: AsRef<str>>(name: S) -> Self {
+        InnerError::FillMissingForName(name.as_ref().to_string()).into()
+    }
+
+    pub(crate) fn style_cycle_for_name<S: AsRef<str>>(name: S) -> Self {
+        InnerError::StyleCycleForName(name.as_ref().to_string()).into()
+    }
+
     pub(crate) fn group_reuse_attempt(group_id: GroupId) -> Self {
         InnerError::GroupReuseAttempt(group_id).into()
     }
@@ -152,12 +226,54 @@ impl VisError {
         InnerError::BuilderUnresolved(name.as_ref().to_string()).into()
     }
 
+    #[cfg(feature = "fvg")]
+    pub(crate) fn text_rendering_failure<S: AsRef<str>>(reason: S) -> Self {
+        InnerError::TextRenderingFailure(reason.as_ref().to_string()).into()
+    }
+
+    #[cfg(any(feature = "tiny", feature = "fvg"))]
+    pub(crate) fn unsupported_group_effect<S: AsRef<str>>(effect: S) -> Self {
+        InnerError::UnsupportedGroupEffect(effect.as_ref().to_string()).into()
+    }
+
+    #[cfg(feature = "text")]
+    pub(crate) fn malformed_font_for_name<S: AsRef<str>>(name: S) -> Self {
+        InnerError::MalformedFontForName(name.as_ref().to_string()).into()
+    }
+
+    /// Wraps an error of parsing a TOML `source`, resolving the byte
+    /// offset of the error into a line and column.
+    #[cfg(feature = "serialize")]
+    pub(crate) fn toml_read(err: toml::de::Error, source: &str) -> Self {
+        let position = err.span().map(|span| {
+            let head = &source[..span.start.min(source.len())];
+            let line = head.matches('\n').count() + 1;
+            let column = head.rsplit('\n').next().map_or(0, |tail| tail.chars().count()) + 1;
+
+            (line, column)
+        });
+
+        InnerError::TomlRead(err, position).into()
+    }
+
     pub(crate) fn std_io<E>(err: E) -> Self
     where
         E: Into<Box<dyn std::error::Error + Send + Sync>>,
     {
         std::io::Error::new(std::io::ErrorKind::Other, err).into()
     }
+
+    /// Returns the line and column, both counted from one, at which
+    /// parsing of a JSON or TOML source failed.
+    pub fn get_position(&self) -> Option<(usize, usize)> {
+        match &self.inner {
+            #[cfg(feature = "serialize")]
+            InnerError::Json(err) if err.line() > 0 => Some((err.line(), err.column())),
+            #[cfg(feature = "serialize")]
+            InnerError::TomlRead(_, position) => *position,
+            _ => None,
+        }
+    }
 }
 
 impl DetailedError for VisError {
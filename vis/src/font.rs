@@ -1,6 +1,8 @@
+use std::sync::atomic::{AtomicU64, Ordering};
 use crate::AsCss;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct Font {
     names:  Vec<String>,
     class:  GenericFontFamily,
@@ -140,9 +142,33 @@ impl Font {
     pub fn get_size(&self) -> f64 {
         self.size
     }
+
+    #[inline]
+    pub(crate) fn get_class(&self) -> GenericFontFamily {
+        self.class
+    }
+
+    #[cfg(any(feature = "text", feature = "svg"))]
+    #[inline]
+    pub(crate) fn get_names(&self) -> &[String] {
+        self.names.as_slice()
+    }
+
+    #[cfg(feature = "text")]
+    #[inline]
+    pub(crate) fn get_weight(&self) -> &FontWeight {
+        &self.weight
+    }
+
+    #[cfg(feature = "text")]
+    #[inline]
+    pub(crate) fn get_style(&self) -> &FontStyle {
+        &self.style
+    }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub enum GenericFontFamily {
     Serif,
     SansSerif,
@@ -165,15 +191,17 @@ impl AsCss for GenericFontFamily {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) enum FontWeight {
+    #[default]
     Normal,
     Bold,
     Number(u16),
 }
 
 impl FontWeight {
-    #[allow(dead_code)]
+    #[cfg(any(feature = "text", feature = "svg"))]
     #[inline]
     pub(crate) fn as_number(&self) -> u16 {
         use FontWeight::*;
@@ -186,9 +214,182 @@ impl FontWeight {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) enum FontStyle {
+    #[default]
     Normal,
     Italic,
     Oblique,
 }
+
+impl AsCss for FontStyle {
+    fn as_css(&self) -> &str {
+        use FontStyle::*;
+
+        match self {
+            Normal => "normal",
+            Italic => "italic",
+            Oblique => "oblique",
+        }
+    }
+}
+
+/// The data of an [`EmbeddedFont`]: TTF or OTF bytes, or a path to
+/// a font file.
+///
+/// Note: the bytes are serialized as a base64 string.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum FontSource {
+    #[cfg_attr(feature = "serialize", serde(with = "crate::base64"))]
+    Data(Vec<u8>),
+    Path(std::path::PathBuf),
+}
+
+/// A font face carried by a [`Theme`](crate::Theme), which is used
+/// by all backends instead of resolving its family name on the host.
+///
+/// The family name is the one by which [`Font`]s and fallback chains
+/// refer to the face, see [`Theme::with_embedded_fonts()`].  Weight
+/// and style are declared, not read from the font data, and used for
+/// choosing between faces of the same family.
+///
+/// Each font is given a process-wide unique id on creation, which is
+/// kept by its clones, and by which a [`FontLibrary`] tells whether
+/// the font is already loaded, without comparing its data.
+///
+/// [`Theme::with_embedded_fonts()`]: crate::Theme::with_embedded_fonts()
+/// [`FontLibrary`]: crate::FontLibrary
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct EmbeddedFont {
+    family: String,
+    #[cfg_attr(feature = "serialize", serde(default))]
+    weight: FontWeight,
+    #[cfg_attr(feature = "serialize", serde(default))]
+    style:  FontStyle,
+    source: FontSource,
+    #[cfg_attr(feature = "serialize", serde(skip, default = "next_font_id"))]
+    id:     u64,
+}
+
+fn next_font_id() -> u64 {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Fonts are equal if declared alike, with equal sources, regardless
+/// of their ids.
+impl PartialEq for EmbeddedFont {
+    fn eq(&self, other: &Self) -> bool {
+        self.family == other.family
+            && self.weight == other.weight
+            && self.style == other.style
+            && self.source == other.source
+    }
+}
+
+impl EmbeddedFont {
+    pub fn from_data<S: AsRef<str>>(family: S, data: Vec<u8>) -> Self {
+        EmbeddedFont {
+            family: family.as_ref().to_string(),
+            weight: FontWeight::Normal,
+            style:  FontStyle::Normal,
+            source: FontSource::Data(data),
+            id:     next_font_id(),
+        }
+    }
+
+    pub fn from_path<S, P>(family: S, path: P) -> Self
+    where
+        S: AsRef<str>,
+        P: AsRef<std::path::Path>,
+    {
+        EmbeddedFont {
+            family: family.as_ref().to_string(),
+            weight: FontWeight::Normal,
+            style:  FontStyle::Normal,
+            source: FontSource::Path(path.as_ref().to_path_buf()),
+            id:     next_font_id(),
+        }
+    }
+
+    pub fn with_weight(mut self, value: u16) -> Self {
+        self.weight = FontWeight::Number(value.clamp(1, 1000));
+        self
+    }
+
+    pub fn with_bold_weight(mut self) -> Self {
+        self.weight = FontWeight::Bold;
+        self
+    }
+
+    pub fn with_italic_style(mut self) -> Self {
+        self.style = FontStyle::Italic;
+        self
+    }
+
+    pub fn with_oblique_style(mut self) -> Self {
+        self.style = FontStyle::Oblique;
+        self
+    }
+
+    #[inline]
+    pub fn get_family_name(&self) -> &str {
+        self.family.as_str()
+    }
+
+    #[inline]
+    pub fn get_source(&self) -> &FontSource {
+        &self.source
+    }
+
+    /// Returns the font data, reading it from a file, if the source is
+    /// a path.
+    pub fn load_data(&self) -> std::io::Result<std::borrow::Cow<'_, [u8]>> {
+        match &self.source {
+            FontSource::Data(data) => Ok(data.as_slice().into()),
+            FontSource::Path(path) => Ok(std::fs::read(path)?.into()),
+        }
+    }
+
+    #[cfg(feature = "text")]
+    #[inline]
+    pub(crate) fn get_id(&self) -> u64 {
+        self.id
+    }
+
+    #[cfg(any(feature = "text", feature = "svg"))]
+    #[inline]
+    pub(crate) fn get_weight(&self) -> &FontWeight {
+        &self.weight
+    }
+
+    #[cfg(any(feature = "text", feature = "svg"))]
+    #[inline]
+    pub(crate) fn get_style(&self) -> &FontStyle {
+        &self.style
+    }
+}
+
+#[cfg(all(test, feature = "serialize"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn font_data_as_base64() {
+        let font = EmbeddedFont::from_data("Foo", b"foobar".to_vec());
+        let json = serde_json::to_string(&font).unwrap();
+
+        let loaded: EmbeddedFont = serde_json::from_str(&json).unwrap();
+
+        assert!(json.contains(r#""source":{"Data":"Zm9vYmFy"}"#));
+        assert!(!json.contains("\"id\""));
+        assert_eq!(loaded, font);
+        assert_ne!(loaded.id, font.id);
+        assert_eq!(font.clone().id, font.id);
+        assert!(serde_json::from_str::<FontSource>(r#"{"Data":"Zm9v!"}"#).is_err());
+    }
+}
@@ -1,19 +1,108 @@
-use kurbo::TranslateScale;
-use crate::{CrumbId, CrumbItem, StyleId};
+use kurbo::Affine;
+use crate::{Crumb, CrumbId, CrumbItem, StyleId};
 
-const IDENTITY: TranslateScale = TranslateScale::scale(1.0);
+const IDENTITY: Affine = Affine::IDENTITY;
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct GroupId(pub usize);
 
-#[derive(Clone, Copy, Debug)]
-pub struct GroupItem(pub GroupId, pub TranslateScale);
+/// Replaces styles of crumbs in an instance of a group, see
+/// [`GroupItem`].
+///
+/// Overrides of nested group items are applied innermost first.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum StyleOverride {
+    /// Paints all crumbs, styled or not, with a single style.
+    ReplaceAll(StyleId),
+    /// Replaces each style found among the keys of a map with the
+    /// corresponding value.  Other styles, and unstyled crumbs, are
+    /// left alone.
+    Map(Vec<(StyleId, StyleId)>),
+}
+
+impl StyleOverride {
+    pub fn from_map<I>(map: I) -> Self
+    where
+        I: IntoIterator<Item = (StyleId, StyleId)>,
+    {
+        StyleOverride::Map(map.into_iter().collect())
+    }
+
+    pub fn apply(&self, style_id: Option<StyleId>) -> Option<StyleId> {
+        match self {
+            StyleOverride::ReplaceAll(new_id) => Some(*new_id),
+            StyleOverride::Map(map) => style_id.map(|old_id| {
+                map.iter().find(|(key, _)| *key == old_id).map_or(old_id, |(_, new_id)| *new_id)
+            }),
+        }
+    }
+}
+
+/// Applies a chain of `overrides`, ordered from the outermost to the
+/// innermost group item.
+pub(crate) fn apply_style_overrides(
+    overrides: &[&StyleOverride],
+    style_id: Option<StyleId>,
+) -> Option<StyleId> {
+    overrides.iter().rev().fold(style_id, |style_id, o| o.apply(style_id))
+}
+
+/// An instance of a group, transformed, and optionally restyled, see
+/// [`StyleOverride`].
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct GroupItem(pub GroupId, pub Affine, pub Option<StyleOverride>);
+
+/// Specifies how the contents of a [`Group`] are blended with
+/// whatever was painted below the group.
+///
+/// Note: blend modes other than `Normal` are supported only by the
+/// svg backend, and other backends fail to render groups using them.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum BlendMode {
+    #[default]
+    Normal,
+    Multiply,
+    Screen,
+    Overlay,
+    Darken,
+    Lighten,
+    Difference,
+}
+
+impl BlendMode {
+    /// Returns the name of this mode, as used by the CSS
+    /// `mix-blend-mode` property.
+    pub fn as_css_name(&self) -> &'static str {
+        match self {
+            BlendMode::Normal => "normal",
+            BlendMode::Multiply => "multiply",
+            BlendMode::Screen => "screen",
+            BlendMode::Overlay => "overlay",
+            BlendMode::Darken => "darken",
+            BlendMode::Lighten => "lighten",
+            BlendMode::Difference => "difference",
+        }
+    }
+}
 
-#[derive(Clone, Default, Debug)]
+#[derive(Clone, Default, PartialEq, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct Group {
-    name:   Option<String>,
-    crumbs: Vec<CrumbItem>,
-    groups: Vec<GroupItem>,
+    name:       Option<String>,
+    crumbs:     Vec<CrumbItem>,
+    groups:     Vec<GroupItem>,
+    #[cfg_attr(feature = "serialize", serde(default))]
+    opacity:    Option<f64>,
+    #[cfg_attr(feature = "serialize", serde(default))]
+    clip:       Option<Crumb>,
+    #[cfg_attr(feature = "serialize", serde(default))]
+    blend_mode: Option<BlendMode>,
+    #[cfg_attr(feature = "serialize", serde(default))]
+    style:      Option<StyleId>,
 }
 
 impl Group {
@@ -24,7 +113,7 @@ impl Group {
         let crumbs = crumbs.into_iter().collect();
         let groups = Vec::new();
 
-        Group { name: None, crumbs, groups }
+        Group { name: None, crumbs, groups, ..Default::default() }
     }
 
     pub fn from_group_items<I>(groups: I) -> Self
@@ -34,7 +123,7 @@ impl Group {
         let crumbs = Vec::new();
         let groups = groups.into_iter().collect();
 
-        Group { name: None, crumbs, groups }
+        Group { name: None, crumbs, groups, ..Default::default() }
     }
 
     pub fn from_crumbs<I>(crumbs: I) -> Self
@@ -44,7 +133,7 @@ impl Group {
         let crumbs = crumbs.into_iter().map(|(p, s)| CrumbItem(p, IDENTITY, s)).collect();
         let groups = Vec::new();
 
-        Group { name: None, crumbs, groups }
+        Group { name: None, crumbs, groups, ..Default::default() }
     }
 
     pub fn from_groups<I>(groups: I) -> Self
@@ -52,9 +141,9 @@ impl Group {
         I: IntoIterator<Item = GroupId>,
     {
         let crumbs = Vec::new();
-        let groups = groups.into_iter().map(|g| GroupItem(g, IDENTITY)).collect();
+        let groups = groups.into_iter().map(|g| GroupItem(g, IDENTITY, None)).collect();
 
-        Group { name: None, crumbs, groups }
+        Group { name: None, crumbs, groups, ..Default::default() }
     }
 
     #[inline]
@@ -63,6 +152,30 @@ impl Group {
         self
     }
 
+    #[inline]
+    pub fn with_opacity(mut self, opacity: f64) -> Self {
+        self.set_opacity(opacity);
+        self
+    }
+
+    #[inline]
+    pub fn with_clip<C: Into<Crumb>>(mut self, clip: C) -> Self {
+        self.set_clip(clip);
+        self
+    }
+
+    #[inline]
+    pub fn with_blend_mode(mut self, blend_mode: BlendMode) -> Self {
+        self.set_blend_mode(blend_mode);
+        self
+    }
+
+    #[inline]
+    pub fn with_default_style(mut self, style_id: StyleId) -> Self {
+        self.set_default_style(style_id);
+        self
+    }
+
     #[inline]
     pub fn with_crumb_item(mut self, crumb: CrumbItem) -> Self {
         self.crumbs.push(crumb);
@@ -119,7 +232,7 @@ impl Group {
     where
         I: IntoIterator<Item = GroupId>,
     {
-        self.groups.extend(groups.into_iter().map(|g| GroupItem(g, IDENTITY)));
+        self.groups.extend(groups.into_iter().map(|g| GroupItem(g, IDENTITY, None)));
         self
     }
 
@@ -128,6 +241,52 @@ impl Group {
         self.name = Some(name.as_ref().to_string());
     }
 
+    /// Sets the opacity of the group as a whole, which is clamped to
+    /// the range `0.0..=1.0`.
+    #[inline]
+    pub fn set_opacity(&mut self, opacity: f64) {
+        self.opacity = Some(opacity.clamp(0.0, 1.0));
+    }
+
+    /// Sets the shape, given in coordinates of the group, outside of
+    /// which nothing in the group is painted.
+    ///
+    /// Lines and pins enclose no area and clip everything out.  Text
+    /// labels clip to their estimated boxes.
+    #[inline]
+    pub fn set_clip<C: Into<Crumb>>(&mut self, clip: C) {
+        self.clip = Some(clip.into());
+    }
+
+    #[inline]
+    pub fn set_blend_mode(&mut self, blend_mode: BlendMode) {
+        self.blend_mode = Some(blend_mode);
+    }
+
+    /// Sets the style of crumbs added to the group without a style,
+    /// and of such crumbs of subgroups, unless overridden by a default
+    /// style of a subgroup.
+    ///
+    /// Crumbs without a style, for which no default style is found in
+    /// any enclosing group, are painted with the default style of a
+    /// theme.
+    #[inline]
+    pub fn set_default_style(&mut self, style_id: StyleId) {
+        self.style = Some(style_id);
+    }
+
+    #[inline]
+    pub fn clear_default_style(&mut self) {
+        self.style = None;
+    }
+
+    /// Sets the style of all items of the group referring to a crumb.
+    pub fn set_crumb_style(&mut self, crumb_id: CrumbId, style_id: Option<StyleId>) {
+        for item in self.crumbs.iter_mut().filter(|item| item.0 == crumb_id) {
+            item.2 = style_id;
+        }
+    }
+
     #[inline]
     pub fn add_crumb(&mut self, crumb_id: CrumbId, style_id: Option<StyleId>) {
         self.crumbs.push(CrumbItem(crumb_id, IDENTITY, style_id));
@@ -135,7 +294,7 @@ impl Group {
 
     #[inline]
     pub fn add_group(&mut self, group_id: GroupId) {
-        self.groups.push(GroupItem(group_id, IDENTITY));
+        self.groups.push(GroupItem(group_id, IDENTITY, None));
     }
 
     #[inline]
@@ -151,7 +310,7 @@ impl Group {
     where
         I: IntoIterator<Item = GroupId>,
     {
-        self.groups.extend(groups.into_iter().map(|g| GroupItem(g, IDENTITY)));
+        self.groups.extend(groups.into_iter().map(|g| GroupItem(g, IDENTITY, None)));
     }
 
     #[inline]
@@ -159,6 +318,35 @@ impl Group {
         self.name.as_deref()
     }
 
+    #[inline]
+    pub fn get_opacity(&self) -> Option<f64> {
+        self.opacity
+    }
+
+    #[inline]
+    pub fn get_clip(&self) -> Option<&Crumb> {
+        self.clip.as_ref()
+    }
+
+    #[inline]
+    pub fn get_blend_mode(&self) -> Option<BlendMode> {
+        self.blend_mode
+    }
+
+    #[inline]
+    pub fn get_default_style(&self) -> Option<StyleId> {
+        self.style
+    }
+
+    /// Checks whether contents of the group need to be composited
+    /// before being painted, i.e. whether the group has an opacity
+    /// below one, a clip or a blend mode other than `Normal`.
+    pub fn has_effects(&self) -> bool {
+        matches!(self.opacity, Some(opacity) if opacity < 1.0)
+            || self.clip.is_some()
+            || matches!(self.blend_mode, Some(mode) if mode != BlendMode::Normal)
+    }
+
     #[inline]
     pub fn get_crumb_items(&self) -> &[CrumbItem] {
         self.crumbs.as_slice()
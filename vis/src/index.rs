@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use crate::{CrumbId, GroupId};
+
+/// Lookup tables mapping names of groups, and names and tags of
+/// crumbs, to their ids.
+///
+/// Note: this is a cache, rebuilt by a [`Scene`](crate::Scene) on
+/// demand, whenever names or tags might have changed.
+#[derive(Clone, Default, Debug)]
+pub(crate) struct NameIndex {
+    groups:     HashMap<String, Vec<GroupId>>,
+    crumbs:     HashMap<String, Vec<CrumbId>>,
+    crumb_tags: HashMap<String, Vec<CrumbId>>,
+}
+
+impl NameIndex {
+    pub(crate) fn add_group<S: AsRef<str>>(&mut self, name: S, group_id: GroupId) {
+        self.groups.entry(name.as_ref().into()).or_default().push(group_id);
+    }
+
+    pub(crate) fn add_crumb<S: AsRef<str>>(&mut self, name: S, crumb_id: CrumbId) {
+        self.crumbs.entry(name.as_ref().into()).or_default().push(crumb_id);
+    }
+
+    pub(crate) fn add_crumb_tag<S: AsRef<str>>(&mut self, tag: S, crumb_id: CrumbId) {
+        self.crumb_tags.entry(tag.as_ref().into()).or_default().push(crumb_id);
+    }
+
+    /// Sorts all lists of ids, so that lookups return ids in the
+    /// order of creation.
+    pub(crate) fn sort(&mut self) {
+        for ids in self.groups.values_mut() {
+            ids.sort_by_key(|id| id.0);
+        }
+
+        for ids in self.crumbs.values_mut().chain(self.crumb_tags.values_mut()) {
+            ids.sort_by_key(|id| id.0);
+        }
+    }
+
+    #[inline]
+    pub(crate) fn get_groups(&self, name: &str) -> &[GroupId] {
+        self.groups.get(name).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    #[inline]
+    pub(crate) fn get_crumbs(&self, name: &str) -> &[CrumbId] {
+        self.crumbs.get(name).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    #[inline]
+    pub(crate) fn get_tagged_crumbs(&self, tag: &str) -> &[CrumbId] {
+        self.crumb_tags.get(tag).map(Vec::as_slice).unwrap_or(&[])
+    }
+}
+
+/// Checks whether a `pattern` is a glob, i.e. contains any of the
+/// wildcards `*` or `?`.
+#[inline]
+pub(crate) fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?'])
+}
+
+/// Matches a `name` against a glob `pattern`, in which `*` stands for
+/// any sequence of characters, and `?` for any single character.
+pub(crate) fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack = None;
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = backtrack {
+            // Let the last star swallow one more character.
+            p = star_p + 1;
+            n = star_n + 1;
+            backtrack = Some((star_p, star_n + 1));
+        } else {
+            return false
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn globs() {
+        assert!(is_glob("node-*"));
+        assert!(is_glob("a?c"));
+        assert!(!is_glob("nodes"));
+    }
+
+    #[test]
+    fn glob_matching() {
+        assert!(glob_match("", ""));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("*", "anything"));
+        assert!(glob_match("node-*", "node-1"));
+        assert!(glob_match("node-*", "node-"));
+        assert!(glob_match("a?c", "abc"));
+        assert!(glob_match("*b*b", "abcbdb"));
+        assert!(glob_match("a*?", "ab"));
+        assert!(glob_match("ŝ?ŭ", "ŝoŭ"));
+        assert!(!glob_match("a?c", "ac"));
+        assert!(!glob_match("node-*", "nodes"));
+        assert!(!glob_match("*b", "abc"));
+        assert!(!glob_match("", "a"));
+    }
+}
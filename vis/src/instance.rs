@@ -0,0 +1,163 @@
+use crate::{CrumbItem, GroupId};
+
+/// Identifies an instance of a group, or of a crumb, in a
+/// [`Scene`](crate::Scene).
+///
+/// Instance ids are computed from [`InstancePath`]s, hence they are
+/// stable: an id doesn't change as long as the groups and item
+/// indices along its path don't change.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub struct InstanceId(pub u64);
+
+/// A 64-bit FNV-1a hasher, which, unlike the hashers of `std`, is
+/// guaranteed to give the same results across runs and versions.
+struct InstanceHasher(u64);
+
+impl InstanceHasher {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    fn new() -> Self {
+        InstanceHasher(Self::OFFSET_BASIS)
+    }
+
+    fn write(&mut self, value: u64) {
+        for byte in value.to_le_bytes().iter() {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+}
+
+/// The path leading from a layer to an instance of a group, which
+/// may be shared by several parents, or reached several times
+/// through different [`GroupItem`](crate::GroupItem)s of one parent.
+///
+/// A path consists of the [`GroupId`]s of the layer and all its
+/// descendants on the way down, together with the index of the group
+/// item through which each descendant is reached from its parent.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct InstancePath {
+    groups: Vec<GroupId>,
+    items:  Vec<usize>,
+}
+
+impl InstancePath {
+    pub(crate) fn new(layer_id: GroupId) -> Self {
+        InstancePath { groups: vec![layer_id], items: Vec::new() }
+    }
+
+    pub(crate) fn push(&mut self, item_index: usize, group_id: GroupId) {
+        self.groups.push(group_id);
+        self.items.push(item_index);
+    }
+
+    pub(crate) fn pop(&mut self) {
+        if self.items.pop().is_some() {
+            self.groups.pop();
+        }
+    }
+
+    pub(crate) fn with_subgroup(&self, item_index: usize, group_id: GroupId) -> Self {
+        let mut result = self.clone();
+
+        result.push(item_index, group_id);
+
+        result
+    }
+
+    #[inline]
+    pub fn get_layer(&self) -> GroupId {
+        self.groups[0]
+    }
+
+    /// Returns the group at the end of the path.
+    #[inline]
+    pub fn get_group(&self) -> GroupId {
+        self.groups[self.groups.len() - 1]
+    }
+
+    /// Lists the groups on the path, the layer's group first.
+    #[inline]
+    pub fn get_groups(&self) -> &[GroupId] {
+        self.groups.as_slice()
+    }
+
+    /// Lists the indices of group items through which the groups on
+    /// the path are reached (all groups but the layer's one).
+    #[inline]
+    pub fn get_item_indices(&self) -> &[usize] {
+        self.items.as_slice()
+    }
+
+    fn get_hasher(&self) -> InstanceHasher {
+        let mut hasher = InstanceHasher::new();
+
+        hasher.write(self.groups[0].0 as u64);
+
+        for (item_index, group_id) in self.items.iter().zip(self.groups[1..].iter()) {
+            hasher.write(*item_index as u64);
+            hasher.write(group_id.0 as u64);
+        }
+
+        hasher
+    }
+
+    /// Returns the id of the group instance at the end of the path.
+    pub fn get_id(&self) -> InstanceId {
+        InstanceId(self.get_hasher().0)
+    }
+
+    /// Returns the id of the crumb instance reached through the
+    /// crumb item at `item_index` of the group at the end of the path.
+    pub fn get_crumb_id(&self, item_index: usize) -> InstanceId {
+        let mut hasher = self.get_hasher();
+
+        // Separates crumb item indices from group item indices.
+        hasher.write(u64::MAX);
+        hasher.write(item_index as u64);
+
+        InstanceId(hasher.0)
+    }
+}
+
+/// An instance of a crumb found in a traversal of a
+/// [`Scene`](crate::Scene).
+#[derive(Clone, Debug)]
+pub struct CrumbInstance {
+    path:       InstancePath,
+    item_index: usize,
+    item:       CrumbItem,
+}
+
+impl CrumbInstance {
+    pub(crate) fn new(path: InstancePath, item_index: usize, item: CrumbItem) -> Self {
+        CrumbInstance { path, item_index, item }
+    }
+
+    #[inline]
+    pub fn get_id(&self) -> InstanceId {
+        self.path.get_crumb_id(self.item_index)
+    }
+
+    /// Returns the path of the group instance containing this crumb
+    /// instance.
+    #[inline]
+    pub fn get_path(&self) -> &InstancePath {
+        &self.path
+    }
+
+    /// Returns the index of the crumb item in the group at the end of
+    /// the path.
+    #[inline]
+    pub fn get_item_index(&self) -> usize {
+        self.item_index
+    }
+
+    /// Returns the crumb item with its effective transformation.
+    #[inline]
+    pub fn get_item(&self) -> CrumbItem {
+        self.item
+    }
+}
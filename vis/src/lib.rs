@@ -3,6 +3,7 @@
 mod crumb;
 mod group;
 mod style;
+mod color;
 mod font;
 mod theme;
 mod scene;
@@ -10,25 +11,39 @@ mod tweener;
 mod joint;
 mod text;
 mod builder;
+mod change;
+mod instance;
 mod error;
+mod affine;
+mod index;
+mod spatial;
+#[cfg(any(feature = "svg", feature = "serialize"))]
+mod base64;
+#[cfg(feature = "text")]
+mod typeset;
 pub mod backend;
 
 pub use crumb::{Crumb, CrumbId, CrumbItem, CrumbSet, Crumbling};
-pub use group::{Group, GroupId, GroupItem};
+pub use group::{Group, GroupId, GroupItem, StyleOverride, BlendMode};
 pub use style::{
-    Style, StyleId, Color, Rgba, Stroke, Fill, UnitPoint, ScaleMode, GradientStop, GradientStops,
-    Gradient, Marker, MarkerId,
+    Style, StyleId, Color, Rgba, Stroke, LineCap, LineJoin, Fill, UnitPoint, ScaleMode,
+    GradientStop, GradientStops, Gradient, Hatch, Marker, MarkerId,
 };
-pub use font::Font;
-pub use scene::Scene;
-pub use theme::{Theme, Variation, NamedMarkersIter};
+pub use color::{ColorDeficiency, ColorSpace};
+pub use font::{Font, GenericFontFamily, EmbeddedFont, FontSource};
+pub use scene::{Scene, FitMode, CrumbHit, PaintStep};
+pub use change::SceneChanges;
+pub use instance::{InstanceId, InstancePath, CrumbInstance};
+pub use theme::{Theme, Variation, VariationStack, ContrastIssue, PaintRole, NamedMarkersIter};
 pub use tweener::{Tweener, Tweenable, Steppable, LinearEasing};
 pub use joint::Joint;
-pub use text::TextLabel;
+pub use text::{TextLabel, TextMetrics, TextBox, BaselineShift};
+#[cfg(feature = "text")]
+pub use typeset::{FontLibrary, GlyphRun};
 pub use builder::{PinBuilder, NodeLabelBuilder};
 pub use error::VisError;
 
-pub use kurbo::{self, Line, Rect, RoundedRect, Circle, TranslateScale, Vec2};
+pub use kurbo::{self, Line, Rect, RoundedRect, Circle, Affine, TranslateScale, Vec2};
 
 pub trait AsCss {
     fn as_css(&self) -> &str;
@@ -37,7 +52,7 @@ pub trait AsCss {
 pub trait PreprocessWithStyle {
     fn preprocess_with_style(
         &mut self,
-        ts: TranslateScale,
+        ts: Affine,
         style: Option<&Style>,
         theme: &Theme,
     ) -> Result<(), VisError>;
@@ -1,7 +1,22 @@
-use kurbo::{Line, Rect, RoundedRect, Circle, Arc, TranslateScale, Size};
-use crate::{Crumb, CrumbId, CrumbItem, Group, GroupId, GroupItem, StyleId, Theme, VisError};
-
-#[derive(Clone, Debug)]
+#[cfg(feature = "serialize")]
+use std::{convert::TryFrom, collections::BTreeMap};
+use std::{
+    collections::HashMap,
+    ops::Range,
+    sync::OnceLock,
+};
+use kurbo::{Shape, Point, Line, Rect, RoundedRect, Circle, Arc, Affine, Size, Vec2};
+use crate::{
+    Crumb, CrumbId, CrumbItem, Group, GroupId, GroupItem, StyleId, StyleOverride, Theme,
+    SceneChanges, VisError, CrumbInstance, InstancePath,
+    affine::get_scale_factor,
+    group::apply_style_overrides,
+    index::{NameIndex, is_glob, glob_match},
+    spatial::{SpatialIndex, SpatialCache, GroupSpan, is_overlapping},
+};
+
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 struct Layer {
     group_id:   GroupId,
     is_visible: bool,
@@ -14,12 +29,55 @@ impl Layer {
     }
 }
 
+/// Specifies which extent of a [`Scene`] is fitted into the output
+/// area by the backends.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum FitMode {
+    /// Fits the hand-set size of a scene, anchored at the origin.
+    #[default]
+    Size,
+    /// Fits the bounding box of all visible crumbs, centered.
+    Content,
+}
+
+/// A step of painting a [`Scene`], see
+/// [`Scene::visible_paint_steps()`].
+#[derive(Clone, Copy, Debug)]
+pub enum PaintStep<'a> {
+    /// Starts compositing the contents of a group transformed by the
+    /// effective transformation of the group.
+    BeginGroup(&'a Group, Affine),
+    /// Paints a crumb transformed by its effective transformation.
+    Crumb(&'a Crumb, Affine, Option<StyleId>),
+    /// Composites the contents of the innermost group started.
+    EndGroup,
+}
+
+/// A crumb found under a point, together with the instance of the
+/// crumb which was hit, telling apart copies of shared groups.
+#[derive(Clone, Debug)]
+pub struct CrumbHit(pub CrumbId, pub CrumbInstance);
+
+/// Note: the name index and the spatial index of a scene are built
+/// lazily and cached behind locks, so that a [`Scene`] is `Sync` and
+/// may be queried from many threads at once.  Since bounds of labels
+/// depend on fonts, the spatial index of a scene with labels is
+/// rebuilt whenever it's queried with other fonts, see
+/// [`FontLibrary::get_state_key()`](crate::FontLibrary::get_state_key).
 #[derive(Clone, Default, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Deserialize), serde(try_from = "SceneData"))]
 pub struct Scene {
-    size:   Size,
-    crumbs: Vec<Crumb>,
-    groups: Vec<Group>,
-    layers: Vec<Layer>,
+    size:          Size,
+    fit_mode:      FitMode,
+    crumbs:        Vec<Option<Crumb>>,
+    groups:        Vec<Option<Group>>,
+    layers:        Vec<Layer>,
+    crumb_names:   HashMap<CrumbId, String>,
+    crumb_tags:    HashMap<CrumbId, Vec<String>>,
+    index:         OnceLock<NameIndex>,
+    spatial_index: SpatialCache,
+    changes:       SceneChanges,
 }
 
 impl Scene {
@@ -27,35 +85,77 @@ impl Scene {
         Scene { size: size.into(), ..Default::default() }
     }
 
+    pub fn with_fit_mode(mut self, fit_mode: FitMode) -> Self {
+        self.fit_mode = fit_mode;
+        self
+    }
+
+    #[inline]
+    pub fn set_fit_mode(&mut self, fit_mode: FitMode) {
+        if fit_mode != self.fit_mode {
+            self.fit_mode = fit_mode;
+            self.changes_mut().mark_resized();
+        }
+    }
+
     #[inline]
     pub fn get_size(&self) -> &Size {
         &self.size
     }
 
+    #[inline]
+    pub fn get_fit_mode(&self) -> FitMode {
+        self.fit_mode
+    }
+
     #[inline]
     pub fn get_crumb(&self, crumb_id: CrumbId) -> Option<&Crumb> {
-        self.crumbs.get(crumb_id.0)
+        self.crumbs.get(crumb_id.0).and_then(Option::as_ref)
     }
 
+    /// Note: the crumb is recorded as changed, see
+    /// [`Scene::take_changes()`], and the spatial index is
+    /// invalidated, since the returned crumb may be reshaped.
     #[inline]
     pub fn get_crumb_mut(&mut self, crumb_id: CrumbId) -> Option<&mut Crumb> {
-        self.crumbs.get_mut(crumb_id.0)
+        self.invalidate_spatial_index();
+        self.changes_mut().mark_crumb(crumb_id);
+        self.get_crumb_mut_untracked(crumb_id)
+    }
+
+    /// Provides access to a crumb without recording it as changed,
+    /// e.g. for caching the layout of a text label.
+    ///
+    /// Note: the spatial index is kept, so the caller must invalidate
+    /// it, if the crumb's bounds change.
+    #[inline]
+    pub(crate) fn get_crumb_mut_untracked(&mut self, crumb_id: CrumbId) -> Option<&mut Crumb> {
+        self.crumbs.get_mut(crumb_id.0).and_then(Option::as_mut)
     }
 
     #[inline]
     pub fn get_group(&self, group_id: GroupId) -> Option<&Group> {
-        self.groups.get(group_id.0)
+        self.groups.get(group_id.0).and_then(Option::as_ref)
     }
 
+    /// Note: the group is recorded as changed, see
+    /// [`Scene::take_changes()`], and both indices are invalidated,
+    /// since the returned group may be renamed or rearranged.  Use
+    /// [`Scene::set_group_name()`], [`Scene::set_group_style()`] or
+    /// [`Scene::set_crumb_style()`] to keep the spatial index.
     #[inline]
     pub fn get_group_mut(&mut self, group_id: GroupId) -> Option<&mut Group> {
-        self.groups.get_mut(group_id.0)
+        self.invalidate_index();
+        self.invalidate_spatial_index();
+        self.changes_mut().mark_group(group_id);
+        self.groups.get_mut(group_id.0).and_then(Option::as_mut)
     }
 
     pub fn add_line(&mut self, line: Line) -> CrumbId {
         let id = self.crumbs.len();
 
-        self.crumbs.push(Crumb::Line(line));
+        self.crumbs.push(Some(Crumb::Line(line)));
+        self.changes_mut().mark_crumb(CrumbId(id));
 
         CrumbId(id)
     }
@@ -63,7 +163,8 @@ impl Scene {
     pub fn add_rect(&mut self, rect: Rect) -> CrumbId {
         let id = self.crumbs.len();
 
-        self.crumbs.push(Crumb::Rect(rect));
+        self.crumbs.push(Some(Crumb::Rect(rect)));
+        self.changes_mut().mark_crumb(CrumbId(id));
 
         CrumbId(id)
     }
@@ -71,7 +172,8 @@ impl Scene {
     pub fn add_rounded_rect(&mut self, rect: RoundedRect) -> CrumbId {
         let id = self.crumbs.len();
 
-        self.crumbs.push(Crumb::RoundedRect(rect));
+        self.crumbs.push(Some(Crumb::RoundedRect(rect)));
+        self.changes_mut().mark_crumb(CrumbId(id));
 
         CrumbId(id)
     }
@@ -79,7 +181,8 @@ impl Scene {
     pub fn add_circle(&mut self, circ: Circle) -> CrumbId {
         let id = self.crumbs.len();
 
-        self.crumbs.push(Crumb::Circle(circ));
+        self.crumbs.push(Some(Crumb::Circle(circ)));
+        self.changes_mut().mark_crumb(CrumbId(id));
 
         CrumbId(id)
     }
@@ -87,7 +190,8 @@ impl Scene {
     pub fn add_arc(&mut self, arc: Arc) -> CrumbId {
         let id = self.crumbs.len();
 
-        self.crumbs.push(Crumb::Arc(arc));
+        self.crumbs.push(Some(Crumb::Arc(arc)));
+        self.changes_mut().mark_crumb(CrumbId(id));
 
         CrumbId(id)
     }
@@ -95,7 +199,8 @@ impl Scene {
     pub fn add_crumb(&mut self, crumb: Crumb) -> CrumbId {
         let id = self.crumbs.len();
 
-        self.crumbs.push(crumb);
+        self.crumbs.push(Some(crumb));
+        self.changes_mut().mark_crumb(CrumbId(id));
 
         CrumbId(id)
     }
@@ -103,7 +208,9 @@ impl Scene {
     pub fn add_group(&mut self, group: Group) -> GroupId {
         let id = self.groups.len();
 
-        self.groups.push(group);
+        self.invalidate_index();
+        self.groups.push(Some(group));
+        self.changes_mut().mark_group(GroupId(id));
 
         GroupId(id)
     }
@@ -152,8 +259,12 @@ impl Scene {
         let group_id = GroupId(self.groups.len());
         let layer = Layer::new(group_id);
 
-        self.groups.push(group);
+        self.invalidate_index();
+        self.invalidate_spatial_index();
+        self.groups.push(Some(group));
         self.layers.push(layer);
+        self.changes_mut().mark_group(group_id);
+        self.changes_mut().mark_layer(group_id);
 
         group_id
     }
@@ -165,6 +276,8 @@ impl Scene {
             let layer = Layer::new(group_id);
 
             self.layers.push(layer);
+            self.invalidate_spatial_index();
+            self.changes_mut().mark_layer(group_id);
             Ok(())
         }
     }
@@ -172,6 +285,8 @@ impl Scene {
     pub fn set_z_index(&mut self, group_id: GroupId, z_index: i64) -> Result<(), VisError> {
         if let Some(layer) = self.layers.iter_mut().find(|layer| layer.group_id == group_id) {
             layer.z_index = z_index;
+            self.invalidate_spatial_index();
+            self.changes_mut().mark_layer(group_id);
             Ok(())
         } else {
             Err(VisError::layer_missing_for_id(group_id))
@@ -181,6 +296,8 @@ impl Scene {
     pub fn hide_layer(&mut self, group_id: GroupId) -> Result<(), VisError> {
         if let Some(layer) = self.layers.iter_mut().find(|layer| layer.group_id == group_id) {
             layer.is_visible = false;
+            self.invalidate_spatial_index();
+            self.changes_mut().mark_layer(group_id);
             Ok(())
         } else {
             Err(VisError::layer_missing_for_id(group_id))
@@ -190,12 +307,321 @@ impl Scene {
     pub fn show_layer(&mut self, group_id: GroupId) -> Result<(), VisError> {
         if let Some(layer) = self.layers.iter_mut().find(|layer| layer.group_id == group_id) {
             layer.is_visible = true;
+            self.invalidate_spatial_index();
+            self.changes_mut().mark_layer(group_id);
+            Ok(())
+        } else {
+            Err(VisError::layer_missing_for_id(group_id))
+        }
+    }
+
+    /// Removes a crumb from the [`Scene`] and returns it.
+    ///
+    /// The [`CrumbId`] of a removed crumb is never reused, so that
+    /// stale ids are detected instead of silently resolving to other
+    /// crumbs.  Group items referring to the removed crumb are left in
+    /// place, see [`Scene::validate()`].  The name and tags of the
+    /// removed crumb are dropped.
+    pub fn remove_crumb(&mut self, crumb_id: CrumbId) -> Result<Crumb, VisError> {
+        let crumb = self
+            .crumbs
+            .get_mut(crumb_id.0)
+            .and_then(Option::take)
+            .ok_or_else(|| VisError::crumb_missing_for_id(crumb_id))?;
+
+        self.crumb_names.remove(&crumb_id);
+        self.crumb_tags.remove(&crumb_id);
+        self.invalidate_index();
+        self.invalidate_spatial_index();
+        self.changes_mut().mark_crumb(crumb_id);
+
+        Ok(crumb)
+    }
+
+    /// Removes a group from the [`Scene`] and returns it.
+    ///
+    /// The [`GroupId`] of a removed group is never reused.  Neither
+    /// subgroups and crumbs of the removed group, nor group items and
+    /// layers referring to it, are removed, see [`Scene::validate()`].
+    pub fn remove_group(&mut self, group_id: GroupId) -> Result<Group, VisError> {
+        let group = self
+            .groups
+            .get_mut(group_id.0)
+            .and_then(Option::take)
+            .ok_or_else(|| VisError::group_missing_for_id(group_id))?;
+
+        self.invalidate_index();
+        self.invalidate_spatial_index();
+        self.changes_mut().mark_group(group_id);
+
+        Ok(group)
+    }
+
+    /// Removes a top-level group from the list of layers.  The group
+    /// itself remains in the [`Scene`].
+    pub fn remove_layer(&mut self, group_id: GroupId) -> Result<(), VisError> {
+        if let Some(pos) = self.layers.iter().position(|layer| layer.group_id == group_id) {
+            self.layers.remove(pos);
+            self.invalidate_spatial_index();
+            self.changes_mut().mark_layer(group_id);
             Ok(())
         } else {
             Err(VisError::layer_missing_for_id(group_id))
         }
     }
 
+    pub fn set_group_name<S: AsRef<str>>(
+        &mut self,
+        group_id: GroupId,
+        name: S,
+    ) -> Result<(), VisError> {
+        let group = self
+            .groups
+            .get_mut(group_id.0)
+            .and_then(Option::as_mut)
+            .ok_or_else(|| VisError::group_missing_for_id(group_id))?;
+
+        group.set_name(name);
+        self.invalidate_index();
+        self.changes_mut().mark_group(group_id);
+
+        Ok(())
+    }
+
+    /// Sets or clears the default style of a group, see
+    /// [`Group::set_default_style()`].
+    ///
+    /// Unlike changes made through [`Scene::get_group_mut()`], this
+    /// keeps the spatial index, since styles don't move crumbs.
+    pub fn set_group_style(
+        &mut self,
+        group_id: GroupId,
+        style_id: Option<StyleId>,
+    ) -> Result<(), VisError> {
+        let group = self
+            .groups
+            .get_mut(group_id.0)
+            .and_then(Option::as_mut)
+            .ok_or_else(|| VisError::group_missing_for_id(group_id))?;
+
+        if let Some(style_id) = style_id {
+            group.set_default_style(style_id);
+        } else {
+            group.clear_default_style();
+        }
+        self.changes_mut().mark_group(group_id);
+
+        Ok(())
+    }
+
+    /// Sets the style of all items of a crumb in a group, see
+    /// [`Group::set_crumb_style()`].
+    ///
+    /// Unlike changes made through [`Scene::get_group_mut()`], this
+    /// keeps the spatial index, since styles don't move crumbs.
+    pub fn set_crumb_style(
+        &mut self,
+        group_id: GroupId,
+        crumb_id: CrumbId,
+        style_id: Option<StyleId>,
+    ) -> Result<(), VisError> {
+        let group = self
+            .groups
+            .get_mut(group_id.0)
+            .and_then(Option::as_mut)
+            .ok_or_else(|| VisError::group_missing_for_id(group_id))?;
+
+        group.set_crumb_style(crumb_id, style_id);
+        self.changes_mut().mark_group(group_id);
+
+        Ok(())
+    }
+
+    pub fn set_crumb_name<S: AsRef<str>>(
+        &mut self,
+        crumb_id: CrumbId,
+        name: S,
+    ) -> Result<(), VisError> {
+        if self.get_crumb(crumb_id).is_some() {
+            self.invalidate_index();
+            self.crumb_names.insert(crumb_id, name.as_ref().to_string());
+            Ok(())
+        } else {
+            Err(VisError::crumb_missing_for_id(crumb_id))
+        }
+    }
+
+    #[inline]
+    pub fn get_crumb_name(&self, crumb_id: CrumbId) -> Option<&str> {
+        self.crumb_names.get(&crumb_id).map(String::as_str)
+    }
+
+    pub fn add_crumb_tag<S: AsRef<str>>(
+        &mut self,
+        crumb_id: CrumbId,
+        tag: S,
+    ) -> Result<(), VisError> {
+        if self.get_crumb(crumb_id).is_some() {
+            let tags = self.crumb_tags.entry(crumb_id).or_default();
+            let tag = tag.as_ref();
+
+            if !tags.iter().any(|t| t == tag) {
+                tags.push(tag.to_string());
+                self.invalidate_index();
+            }
+            Ok(())
+        } else {
+            Err(VisError::crumb_missing_for_id(crumb_id))
+        }
+    }
+
+    #[inline]
+    pub fn get_crumb_tags(&self, crumb_id: CrumbId) -> &[String] {
+        self.crumb_tags.get(&crumb_id).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    #[inline]
+    fn invalidate_index(&mut self) {
+        self.index.take();
+    }
+
+    fn build_index(&self) -> NameIndex {
+        let mut index = NameIndex::default();
+
+        for (ndx, slot) in self.groups.iter().enumerate() {
+            if let Some(name) = slot.as_ref().and_then(Group::get_name) {
+                index.add_group(name, GroupId(ndx));
+            }
+        }
+
+        for (crumb_id, name) in self.crumb_names.iter() {
+            index.add_crumb(name, *crumb_id);
+        }
+
+        for (crumb_id, tags) in self.crumb_tags.iter() {
+            for tag in tags.iter() {
+                index.add_crumb_tag(tag, *crumb_id);
+            }
+        }
+
+        index.sort();
+
+        index
+    }
+
+    /// Runs a query against the name index, rebuilding the index
+    /// first, if it was invalidated by a change of the [`Scene`].
+    fn with_index<T, F>(&self, f: F) -> T
+    where
+        F: FnOnce(&NameIndex) -> T,
+    {
+        f(self.index.get_or_init(|| self.build_index()))
+    }
+
+    /// Returns the first (oldest) group having the given `name`.
+    pub fn find_group_by_name(&self, name: &str) -> Option<GroupId> {
+        self.with_index(|index| index.get_groups(name).first().copied())
+    }
+
+    /// Returns all groups having the given `name`, in the order of
+    /// creation.
+    pub fn find_groups_by_name(&self, name: &str) -> Vec<GroupId> {
+        self.with_index(|index| index.get_groups(name).to_vec())
+    }
+
+    /// Returns the first (oldest) crumb having the given `name`.
+    pub fn find_crumb_by_name(&self, name: &str) -> Option<CrumbId> {
+        self.with_index(|index| index.get_crumbs(name).first().copied())
+    }
+
+    /// Returns all crumbs tagged with `tag`, in the order of creation.
+    pub fn find_crumbs_by_tag(&self, tag: &str) -> Vec<CrumbId> {
+        self.with_index(|index| index.get_tagged_crumbs(tag).to_vec())
+    }
+
+    /// Returns all groups matching a `path` of group names separated
+    /// with `/`, e.g. `"nodes/a"`.
+    ///
+    /// The first segment of a path matches any named group of the
+    /// [`Scene`], and each following segment matches direct
+    /// subgroups of groups matched so far.  Segments may contain
+    /// wildcards `*` and `?`.  Groups are listed in the order of
+    /// creation, without duplicates.
+    pub fn find_groups_by_path(&self, path: &str) -> Vec<GroupId> {
+        let mut segments = path.split('/');
+        let mut result = match segments.next() {
+            Some(head) if is_glob(head) => self
+                .groups
+                .iter()
+                .enumerate()
+                .filter_map(|(ndx, slot)| {
+                    slot.as_ref()
+                        .and_then(Group::get_name)
+                        .filter(|name| glob_match(head, name))
+                        .map(|_| GroupId(ndx))
+                })
+                .collect(),
+            Some(head) => self.find_groups_by_name(head),
+            None => Vec::new(),
+        };
+
+        for segment in segments {
+            let mut subgroups: Vec<GroupId> = result
+                .iter()
+                .filter_map(|group_id| self.get_group(*group_id))
+                .flat_map(|group| group.get_group_items().iter())
+                .map(|GroupItem(group_id, ..)| *group_id)
+                .filter(|group_id| {
+                    matches!(
+                        self.get_group(*group_id).and_then(Group::get_name),
+                        Some(name) if glob_match(segment, name)
+                    )
+                })
+                .collect();
+
+            subgroups.sort_by_key(|id| id.0);
+            subgroups.dedup();
+
+            result = subgroups;
+        }
+
+        result
+    }
+
+    /// Returns all crumbs matching a `path` of group names followed
+    /// by a crumb name, e.g. `"nodes/a/label"`.
+    ///
+    /// All segments but the last are resolved as in
+    /// [`Scene::find_groups_by_path()`], and the last one matches
+    /// names of crumbs contained directly in the groups found.  A
+    /// single-segment path matches any named crumb of the [`Scene`].
+    pub fn find_crumbs_by_path(&self, path: &str) -> Vec<CrumbId> {
+        let mut result: Vec<CrumbId> = if let Some((group_path, name)) = path.rsplit_once('/') {
+            self.find_groups_by_path(group_path)
+                .iter()
+                .filter_map(|group_id| self.get_group(*group_id))
+                .flat_map(|group| group.get_crumb_items().iter())
+                .map(|CrumbItem(crumb_id, ..)| *crumb_id)
+                .filter(|crumb_id| {
+                    matches!(self.get_crumb_name(*crumb_id), Some(n) if glob_match(name, n))
+                })
+                .collect()
+        } else if is_glob(path) {
+            self.crumb_names
+                .iter()
+                .filter(|(_, name)| glob_match(path, name))
+                .map(|(crumb_id, _)| *crumb_id)
+                .collect()
+        } else {
+            self.with_index(|index| index.get_crumbs(path).to_vec())
+        };
+
+        result.sort_by_key(|id| id.0);
+        result.dedup();
+
+        result
+    }
+
     /// Lists all top-level groups of the [`Scene`] in reversed
     /// stacking order (top-down).
     pub fn get_layers(&self) -> Vec<GroupId> {
@@ -216,21 +642,209 @@ impl Scene {
         z_stack.iter().map(|layer| layer.group_id).collect()
     }
 
+    #[inline]
+    fn changes_mut(&mut self) -> &mut SceneChanges {
+        &mut self.changes
+    }
+
+    /// Returns `true` if anything changed since the last call of
+    /// [`Scene::take_changes()`].
+    #[inline]
+    pub fn has_changes(&self) -> bool {
+        !self.changes.is_empty()
+    }
+
+    /// Returns the record of all changes made since the last call of
+    /// this method (or since creation of the [`Scene`]), and starts
+    /// a new record.
+    #[inline]
+    pub fn take_changes(&mut self) -> SceneChanges {
+        std::mem::take(&mut self.changes)
+    }
+
+    /// Compares this [`Scene`] with an `other` one, returning the
+    /// changes which turn the latter into the former.
+    ///
+    /// Crumbs and groups are compared by id, hence the result is
+    /// meaningful only if one scene was derived from the other.
+    pub fn diff(&self, other: &Scene) -> SceneChanges {
+        let mut changes = SceneChanges::default();
+
+        if self.size != other.size || self.fit_mode != other.fit_mode {
+            changes.mark_resized();
+        }
+
+        for ndx in 0..self.crumbs.len().max(other.crumbs.len()) {
+            let crumb_id = CrumbId(ndx);
+
+            if self.get_crumb(crumb_id) != other.get_crumb(crumb_id) {
+                changes.mark_crumb(crumb_id);
+            }
+        }
+
+        for ndx in 0..self.groups.len().max(other.groups.len()) {
+            let group_id = GroupId(ndx);
+
+            if self.get_group(group_id) != other.get_group(group_id) {
+                changes.mark_group(group_id);
+            }
+        }
+
+        let (z_stack, other_z_stack) = (self.get_layers(), other.get_layers());
+
+        for group_id in z_stack.iter().chain(other_z_stack.iter()) {
+            let layer = self.layers.iter().find(|layer| layer.group_id == *group_id);
+            let other_layer = other.layers.iter().find(|layer| layer.group_id == *group_id);
+            let pos = z_stack.iter().position(|id| id == group_id);
+            let other_pos = other_z_stack.iter().position(|id| id == group_id);
+
+            if layer != other_layer || pos != other_pos {
+                changes.mark_layer(*group_id);
+            }
+        }
+
+        changes
+    }
+
+    /// Lists all top-level groups of the [`Scene`] affected by
+    /// `changes`, in reversed stacking order (top-down).
+    ///
+    /// A layer is affected if it was changed itself, or if any of the
+    /// groups or crumbs it contains, directly or indirectly, was
+    /// changed.  If the scene was resized, all layers are affected.
+    pub fn get_dirty_layers(&self, changes: &SceneChanges) -> Result<Vec<GroupId>, VisError> {
+        let mut result = Vec::new();
+
+        for group_id in self.get_layers() {
+            if changes.is_resized()
+                || changes.has_layer(group_id)
+                || self.is_group_dirty(group_id, changes)?
+            {
+                result.push(group_id);
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn is_group_dirty(&self, group_id: GroupId, changes: &SceneChanges) -> Result<bool, VisError> {
+        if changes.has_group(group_id) {
+            return Ok(true)
+        }
+
+        let group =
+            self.get_group(group_id).ok_or_else(|| VisError::group_missing_for_id(group_id))?;
+
+        for CrumbItem(crumb_id, ..) in group.get_crumb_items().iter() {
+            if changes.has_crumb(*crumb_id) {
+                return Ok(true)
+            }
+        }
+
+        for GroupItem(subgroup_id, ..) in group.get_group_items().iter() {
+            if self.is_group_dirty(*subgroup_id, changes)? {
+                return Ok(true)
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Returns the box, in output coordinates, which needs repainting
+    /// after `changes` were applied to the [`Scene`], or `None` if
+    /// nothing visible changed.
+    ///
+    /// Only the current state of the scene is examined.  Areas
+    /// previously covered by crumbs which were moved or removed
+    /// since, are included only in the result of this method called
+    /// before applying `changes` (or called on the other argument of
+    /// [`Scene::diff()`]).
+    pub fn dirty_bounds(
+        &self,
+        changes: &SceneChanges,
+        root_ts: Affine,
+        theme: &Theme,
+    ) -> Result<Option<Rect>, VisError> {
+        let mut bounds = None;
+
+        let placement = Placement::new(root_ts);
+
+        for group_id in self.get_visible_layers() {
+            if changes.is_resized() || changes.has_layer(group_id) {
+                self.union_bounds_of_a_group(group_id, &placement, theme, &mut bounds)?;
+            } else {
+                self.union_dirty_bounds_of_a_group(
+                    group_id,
+                    &placement,
+                    changes,
+                    theme,
+                    &mut bounds,
+                )?;
+            }
+        }
+
+        Ok(bounds)
+    }
+
+    fn union_dirty_bounds_of_a_group(
+        &self,
+        group_id: GroupId,
+        placement: &Placement,
+        changes: &SceneChanges,
+        theme: &Theme,
+        bounds: &mut Option<Rect>,
+    ) -> Result<(), VisError> {
+        if changes.has_group(group_id) {
+            return self.union_bounds_of_a_group(group_id, placement, theme, bounds)
+        }
+
+        let group =
+            self.get_group(group_id).ok_or_else(|| VisError::group_missing_for_id(group_id))?;
+        let placement = &placement.enter(group);
+
+        for item in group.get_crumb_items().iter() {
+            if changes.has_crumb(item.0) {
+                let CrumbItem(crumb_id, ts, style_id) = placement.place(item);
+                let crumb = self
+                    .get_crumb(crumb_id)
+                    .ok_or_else(|| VisError::crumb_missing_for_id(crumb_id))?;
+
+                if let Some(rect) = crumb.bounding_box(ts, theme.get_style(style_id), theme) {
+                    *bounds = Some(bounds.map_or(rect, |r| r.union(rect)));
+                }
+            }
+        }
+
+        for item in group.get_group_items().iter() {
+            self.union_dirty_bounds_of_a_group(
+                item.0,
+                &placement.nest(item),
+                changes,
+                theme,
+                bounds,
+            )?;
+        }
+
+        Ok(())
+    }
+
     fn push_crumbs_of_a_group<'a>(
         &'a self,
         group: &'a Group,
         mut level: usize,
-        ts: TranslateScale,
-        crumb_chain: &mut Vec<(usize, CrumbList<'a>, TranslateScale)>,
+        placement: Placement<'a>,
+        crumb_chain: &mut Vec<(usize, CrumbList<'a>, Placement<'a>)>,
     ) -> Result<(), VisError> {
+        let placement = placement.enter(group);
+
         level += 1;
-        crumb_chain.push((level, group.get_crumb_items().iter(), ts));
+        crumb_chain.push((level, group.get_crumb_items().iter(), placement.clone()));
 
-        for GroupItem(group_id, group_ts) in group.get_group_items().iter() {
-            if let Some(group) = self.groups.get(group_id.0) {
-                self.push_crumbs_of_a_group(group, level, ts * *group_ts, crumb_chain)?;
+        for item in group.get_group_items().iter() {
+            if let Some(group) = self.get_group(item.0) {
+                self.push_crumbs_of_a_group(group, level, placement.nest(item), crumb_chain)?;
             } else {
-                return Err(VisError::group_missing_for_id(*group_id))
+                return Err(VisError::group_missing_for_id(item.0))
             }
         }
         Ok(())
@@ -239,13 +853,13 @@ impl Scene {
     fn traverse_crumbs(
         &self,
         z_stack: Vec<GroupId>,
-        root_ts: TranslateScale,
-    ) -> Result<CrumbChainIter, VisError> {
+        root_ts: Affine,
+    ) -> Result<CrumbChainIter<'_>, VisError> {
         let mut crumb_chain = Vec::new();
 
         for group_id in z_stack.iter() {
-            if let Some(group) = self.groups.get(group_id.0) {
-                self.push_crumbs_of_a_group(group, 0, root_ts, &mut crumb_chain)?;
+            if let Some(group) = self.get_group(*group_id) {
+                self.push_crumbs_of_a_group(group, 0, Placement::new(root_ts), &mut crumb_chain)?;
             } else {
                 return Err(VisError::group_missing_for_id(*group_id))
             }
@@ -261,7 +875,7 @@ impl Scene {
     /// post-order traversal of the scene tree.  Stacking order of
     /// layers is respected.
     #[inline]
-    pub fn all_crumbs(&self, root_ts: TranslateScale) -> Result<CrumbChainIter, VisError> {
+    pub fn all_crumbs(&self, root_ts: Affine) -> Result<CrumbChainIter<'_>, VisError> {
         self.traverse_crumbs(self.get_layers(), root_ts)
     }
 
@@ -272,116 +886,1009 @@ impl Scene {
     /// post-order traversal of the scene tree.  Stacking order of
     /// layers is respected.
     #[inline]
-    pub fn all_visible_crumbs(&self, root_ts: TranslateScale) -> Result<CrumbChainIter, VisError> {
+    pub fn all_visible_crumbs(&self, root_ts: Affine) -> Result<CrumbChainIter<'_>, VisError> {
         self.traverse_crumbs(self.get_visible_layers(), root_ts)
     }
 
-    fn push_subgroups_of_a_group<'a>(
+    fn push_crumb_instances_of_a_group<'a>(
         &'a self,
         group: &'a Group,
         mut level: usize,
-        group_chain: &mut Vec<(usize, GroupList<'a>)>,
+        placement: Placement<'a>,
+        path: InstancePath,
+        crumb_chain: &mut Vec<(usize, CrumbInstanceList<'a>, Placement<'a>, InstancePath)>,
     ) -> Result<(), VisError> {
-        level += 1;
-        group_chain.push((level, GroupList::Items(group.get_group_items().iter())));
+        let placement = placement.enter(group);
 
-        for GroupItem(group_id, ..) in group.get_group_items().iter() {
-            if let Some(group) = self.groups.get(group_id.0) {
-                self.push_subgroups_of_a_group(group, level, group_chain)?;
+        level += 1;
+        crumb_chain.push((
+            level,
+            group.get_crumb_items().iter().enumerate(),
+            placement.clone(),
+            path.clone(),
+        ));
+
+        for (ndx, item) in group.get_group_items().iter().enumerate() {
+            if let Some(subgroup) = self.get_group(item.0) {
+                self.push_crumb_instances_of_a_group(
+                    subgroup,
+                    level,
+                    placement.nest(item),
+                    path.with_subgroup(ndx, item.0),
+                    crumb_chain,
+                )?;
             } else {
-                return Err(VisError::group_missing_for_id(*group_id))
+                return Err(VisError::group_missing_for_id(item.0))
             }
         }
         Ok(())
     }
 
-    /// Collects all groups of a scene.
-    ///
-    /// Returns an iterator listing [`GroupId`]s in the bottom-up
-    /// level order traversal of the scene tree.  Stacking order of
-    /// layers is ignored.
-    pub fn all_groups(&self) -> Result<GroupChainIter, VisError> {
-        let mut group_chain = vec![(0, GroupList::Layers(self.layers.iter()))];
+    fn traverse_crumb_instances(
+        &self,
+        z_stack: Vec<GroupId>,
+        root_ts: Affine,
+    ) -> Result<CrumbInstanceIter<'_>, VisError> {
+        let mut crumb_chain = Vec::new();
 
-        for Layer { group_id, .. } in self.layers.iter() {
-            if let Some(group) = self.groups.get(group_id.0) {
-                self.push_subgroups_of_a_group(group, 0, &mut group_chain)?;
+        for group_id in z_stack.iter() {
+            if let Some(group) = self.get_group(*group_id) {
+                let placement = Placement::new(root_ts);
+                let path = InstancePath::new(*group_id);
+
+                self.push_crumb_instances_of_a_group(group, 0, placement, path, &mut crumb_chain)?;
             } else {
                 return Err(VisError::group_missing_for_id(*group_id))
             }
         }
 
-        Ok(GroupChainIter { group_chain })
+        Ok(CrumbInstanceIter { crumb_chain })
     }
 
-    pub fn simple_demo(theme: &Theme) -> Self {
-        let mut scene = Scene::new((1000., 1000.));
+    /// Collects all crumb instances of a scene.
+    ///
+    /// Returns an iterator listing [`CrumbInstance`]s in the same
+    /// order as [`Scene::all_crumbs()`] lists [`CrumbItem`]s.  Unlike
+    /// the latter, each instance carries its [`InstancePath`], so that
+    /// crumbs of shared groups may be told apart.
+    #[inline]
+    pub fn all_crumb_instances(&self, root_ts: Affine) -> Result<CrumbInstanceIter<'_>, VisError> {
+        self.traverse_crumb_instances(self.get_layers(), root_ts)
+    }
 
-        let border = scene.add_rect(Rect::new(0., 0., 1000., 1000.));
-        let button = scene.add_rounded_rect(RoundedRect::new(250., 400., 450., 600., 10.));
+    /// Collects all visible crumb instances of a scene, see
+    /// [`Scene::all_crumb_instances()`].
+    #[inline]
+    pub fn all_visible_crumb_instances(
+        &self,
+        root_ts: Affine,
+    ) -> Result<CrumbInstanceIter<'_>, VisError> {
+        self.traverse_crumb_instances(self.get_visible_layers(), root_ts)
+    }
 
-        let lines = scene.add_grouped_lines(vec![
-            (Line::new((0., 500.), (250., 0.)), theme.get("line-1")),
-            (Line::new((0., 500.), (250., 1000.)), theme.get("line-1")),
-            (Line::new((250., 1000.), (250., 0.)), theme.get("line-2")),
-        ]);
+    fn push_paint_steps_of_a_group<'a>(
+        &'a self,
+        group_id: GroupId,
+        placement: &Placement<'a>,
+        paint_steps: &mut Vec<PaintStep<'a>>,
+    ) -> Result<(), VisError> {
+        let group =
+            self.get_group(group_id).ok_or_else(|| VisError::group_missing_for_id(group_id))?;
+        let placement = &placement.enter(group);
+        let has_effects = group.has_effects();
 
-        let rects = scene.add_group(Group::from_crumbs(vec![
-            (button, theme.get("rect-1")),
-            (button, theme.get("rect-2")),
-        ]));
+        if has_effects {
+            paint_steps.push(PaintStep::BeginGroup(group, placement.ts));
+        }
 
-        let circle = scene.add_circle(Circle::new((133., 500.), 110.));
+        for item in group.get_group_items().iter().rev() {
+            self.push_paint_steps_of_a_group(item.0, &placement.nest(item), paint_steps)?;
+        }
 
-        let mixed_group = scene.add_group(
-            Group::from_groups(vec![lines, rects]).with_crumb(circle, theme.get("circ-1")),
-        );
+        for item in group.get_crumb_items().iter() {
+            let CrumbItem(crumb_id, ts, style_id) = placement.place(item);
+            let crumb =
+                self.get_crumb(crumb_id).ok_or_else(|| VisError::crumb_missing_for_id(crumb_id))?;
 
-        let triple_group = scene.add_group(
-            Group::from_groups(vec![mixed_group])
-                .with_group_item(GroupItem(
-                    mixed_group,
-                    0.5 * TranslateScale::translate((750., 0.).into()),
-                ))
-                .with_group_item(GroupItem(
-                    mixed_group,
-                    0.5 * TranslateScale::translate((750., 1000.).into()),
-                )),
-        );
+            paint_steps.push(PaintStep::Crumb(crumb, ts, style_id));
+        }
 
-        scene.add_layer(
-            Group::from_crumbs(vec![(border, theme.get("border"))])
-                .with_group(triple_group)
-                .with_group_item(GroupItem(
-                    triple_group,
-                    TranslateScale::translate((500., 0.).into()),
-                )),
-        );
+        if has_effects {
+            paint_steps.push(PaintStep::EndGroup);
+        }
 
-        scene
+        Ok(())
     }
-}
 
-type CrumbList<'a> = std::slice::Iter<'a, CrumbItem>;
+    /// Lists the steps of painting all visible crumbs of a scene.
+    ///
+    /// Crumbs are listed in the painting order of
+    /// [`Scene::all_visible_crumbs()`], but those belonging to a
+    /// group with effects (see [`Group::has_effects()`]) are enclosed
+    /// in a [`PaintStep::BeginGroup`]/[`PaintStep::EndGroup`] pair.
+    pub fn visible_paint_steps(&self, root_ts: Affine) -> Result<Vec<PaintStep<'_>>, VisError> {
+        let placement = Placement::new(root_ts);
+        let mut paint_steps = Vec::new();
+
+        for group_id in self.get_visible_layers().into_iter().rev() {
+            self.push_paint_steps_of_a_group(group_id, &placement, &mut paint_steps)?;
+        }
 
-/// An iterator traversing all [`CrumbItem`]s of a [`Scene`].
+        Ok(paint_steps)
+    }
+
+    fn index_crumbs_of_a_group(
+        &self,
+        group_id: GroupId,
+        ts: Affine,
+        order: &mut usize,
+        entries: &mut Vec<(Rect, (usize, Affine))>,
+        spans: &mut Vec<GroupSpan>,
+    ) -> Result<(), VisError> {
+        let group =
+            self.get_group(group_id).ok_or_else(|| VisError::group_missing_for_id(group_id))?;
+        let span_ndx = spans.len();
+        let first_crumb = *order;
+        let mut bounds: Option<Rect> = None;
+
+        spans.push(GroupSpan { bounds: None, num_groups: 1, crumbs: first_crumb..first_crumb });
+
+        for item in group.get_group_items().iter().rev() {
+            let ndx = spans.len();
+
+            self.index_crumbs_of_a_group(item.0, ts * item.1, order, entries, spans)?;
+
+            if let Some(rect) = spans[ndx].bounds {
+                bounds = Some(bounds.map_or(rect, |b| b.union(rect)));
+            }
+        }
+
+        for item in group.get_crumb_items().iter() {
+            let crumb =
+                self.get_crumb(item.0).ok_or_else(|| VisError::crumb_missing_for_id(item.0))?;
+            let ts = ts * item.1;
+
+            if let Some(rect) = crumb.get_shape_bounds(ts) {
+                entries.push((rect, (*order, ts)));
+                bounds = Some(bounds.map_or(rect, |b| b.union(rect)));
+            }
+
+            *order += 1;
+        }
+
+        spans[span_ndx] =
+            GroupSpan { bounds, num_groups: spans.len() - span_ndx, crumbs: first_crumb..*order };
+
+        Ok(())
+    }
+
+    /// Indexes crumbs and group instances in the order of
+    /// [`Scene::visible_paint_steps()`].
+    fn build_spatial_index(&self) -> Result<SpatialIndex, VisError> {
+        let mut order = 0;
+        let mut entries = Vec::new();
+        let mut spans = Vec::new();
+
+        for group_id in self.get_visible_layers().into_iter().rev() {
+            self.index_crumbs_of_a_group(
+                group_id,
+                Affine::IDENTITY,
+                &mut order,
+                &mut entries,
+                &mut spans,
+            )?;
+        }
+
+        Ok(SpatialIndex::new(entries, spans))
+    }
+
+    /// Invalidates the spatial index, whenever crumbs might have been
+    /// moved, reshaped, added to or removed from visible groups.
+    #[inline]
+    pub(crate) fn invalidate_spatial_index(&mut self) {
+        self.spatial_index.clear();
+    }
+
+    /// Runs a query against the spatial index, rebuilding the index
+    /// first, if it was invalidated by a change of the [`Scene`], or
+    /// if its labels were measured with other fonts than those of the
+    /// current thread.
+    fn with_spatial_index<T, F>(&self, f: F) -> Result<T, VisError>
+    where
+        F: FnOnce(&SpatialIndex) -> Result<T, VisError>,
+    {
+        let index = match self.spatial_index.get() {
+            Some(index) => index,
+            None => {
+                let index = std::sync::Arc::new(self.build_spatial_index()?);
+                let has_labels =
+                    self.crumbs.iter().flatten().any(|crumb| matches!(crumb, Crumb::Label(_)));
+
+                self.spatial_index.set(index.clone(), has_labels);
+
+                index
+            }
+        };
+
+        f(&index)
+    }
+
+    fn push_culled_paint_steps_of_a_group<'a>(
+        &'a self,
+        group_id: GroupId,
+        placement: &Placement<'a>,
+        culling: &mut Culling,
+        paint_steps: &mut Vec<PaintStep<'a>>,
+        crumb_items: &mut Vec<CrumbItem>,
+    ) -> Result<(), VisError> {
+        let span = culling.spans[culling.next_span].clone();
+
+        if !culling.may_see(span.bounds) {
+            culling.next_span += span.num_groups;
+            return Ok(())
+        }
+
+        culling.next_span += 1;
+
+        let group =
+            self.get_group(group_id).ok_or_else(|| VisError::group_missing_for_id(group_id))?;
+        let placement = &placement.enter(group);
+        let has_effects = group.has_effects();
+        let start = paint_steps.len();
+
+        if has_effects {
+            paint_steps.push(PaintStep::BeginGroup(group, placement.ts));
+        }
+
+        for item in group.get_group_items().iter().rev() {
+            self.push_culled_paint_steps_of_a_group(
+                item.0,
+                &placement.nest(item),
+                culling,
+                paint_steps,
+                crumb_items,
+            )?;
+        }
+
+        let items = group.get_crumb_items();
+        let first_crumb = span.crumbs.end - items.len();
+
+        for &order in culling.hits_in(first_crumb..span.crumbs.end) {
+            let item = placement.place(&items[order - first_crumb]);
+            let CrumbItem(crumb_id, ts, style_id) = item;
+            let crumb =
+                self.get_crumb(crumb_id).ok_or_else(|| VisError::crumb_missing_for_id(crumb_id))?;
+
+            let style = culling.theme.get_style(style_id);
+
+            if let Some(bbox) = crumb.bounding_box(ts, style, culling.theme) {
+                if is_overlapping(&bbox, &culling.rect) {
+                    paint_steps.push(PaintStep::Crumb(crumb, ts, style_id));
+                    crumb_items.push(item);
+                }
+            }
+        }
+
+        if has_effects {
+            if paint_steps.len() > start + 1 {
+                paint_steps.push(PaintStep::EndGroup);
+            } else {
+                // Skips a group left empty.
+                paint_steps.truncate(start);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Lists the paint steps and the crumb items of all visible crumbs
+    /// which may be seen through a `rect` given in output coordinates.
+    ///
+    /// Group instances are skipped as a whole, if their bounds, found
+    /// in the spatial index, don't overlap the `rect`.
+    fn cull(
+        &self,
+        rect: Rect,
+        root_ts: Affine,
+        theme: &Theme,
+    ) -> Result<(Vec<PaintStep<'_>>, Vec<CrumbItem>), VisError> {
+        self.with_spatial_index(|index| {
+            // Strokes and markers reach out of crumbs' geometry.
+            let outset =
+                theme.get_max_outset() * index.get_max_scale() * get_scale_factor(root_ts);
+            let outset_rect = rect.inflate(outset, outset);
+            let mut culling = Culling {
+                rect,
+                outset_rect,
+                root_ts,
+                theme,
+                spans: index.get_spans(),
+                hits: index.query(outset_rect, root_ts),
+                next_span: 0,
+            };
+            let placement = Placement::new(root_ts);
+            let mut paint_steps = Vec::new();
+            let mut crumb_items = Vec::new();
+
+            for group_id in self.get_visible_layers().into_iter().rev() {
+                self.push_culled_paint_steps_of_a_group(
+                    group_id,
+                    &placement,
+                    &mut culling,
+                    &mut paint_steps,
+                    &mut crumb_items,
+                )?;
+            }
+
+            Ok((paint_steps, crumb_items))
+        })
+    }
+
+    /// Lists all visible crumbs of a scene, which overlap a `rect`
+    /// given in output coordinates, when painted with the `theme`.
+    ///
+    /// Crumbs are listed in painting order, as [`CrumbItem`]s
+    /// containing their effective transformations.  Candidates are
+    /// found with an R-tree over crumb bounds, built on first use
+    /// and rebuilt after any change of the scene's geometry.
+    pub fn visible_crumbs_in(
+        &self,
+        rect: Rect,
+        root_ts: Affine,
+        theme: &Theme,
+    ) -> Result<Vec<CrumbItem>, VisError> {
+        Ok(self.cull(rect, root_ts, theme)?.1)
+    }
+
+    /// Lists the steps of painting all visible crumbs of a scene,
+    /// which overlap a `rect` given in output coordinates, when
+    /// painted with the `theme`.
+    ///
+    /// This is [`Scene::visible_paint_steps()`], with crumbs outside
+    /// of the `rect` culled, and groups left empty skipped.  Groups
+    /// lying outside of the `rect` aren't traversed at all.
+    pub fn visible_paint_steps_in(
+        &self,
+        rect: Rect,
+        root_ts: Affine,
+        theme: &Theme,
+    ) -> Result<Vec<PaintStep<'_>>, VisError> {
+        Ok(self.cull(rect, root_ts, theme)?.0)
+    }
+
+    /// Finds the topmost visible crumb under a `point` given in
+    /// output coordinates.
+    ///
+    /// Stacking order and visibility of layers are respected.
+    /// Strokes are widened according to the `theme`, see
+    /// [`Crumb::hit_test()`].
+    pub fn hit_test(
+        &self,
+        point: Point,
+        root_ts: Affine,
+        theme: &Theme,
+    ) -> Result<Option<CrumbHit>, VisError> {
+        let mut hits = Vec::new();
+
+        self.collect_hits(point, root_ts, theme, false, &mut hits)?;
+
+        Ok(hits.pop())
+    }
+
+    /// Finds all visible crumbs under a `point` given in output
+    /// coordinates.
+    ///
+    /// Returns a list of [`CrumbHit`]s in reversed stacking order
+    /// (top-down).
+    pub fn hit_test_all(
+        &self,
+        point: Point,
+        root_ts: Affine,
+        theme: &Theme,
+    ) -> Result<Vec<CrumbHit>, VisError> {
+        let mut hits = Vec::new();
+
+        self.collect_hits(point, root_ts, theme, true, &mut hits)?;
+
+        Ok(hits)
+    }
+
+    fn collect_hits(
+        &self,
+        point: Point,
+        root_ts: Affine,
+        theme: &Theme,
+        find_all: bool,
+        hits: &mut Vec<CrumbHit>,
+    ) -> Result<(), VisError> {
+        let placement = Placement::new(root_ts);
+
+        for group_id in self.get_visible_layers() {
+            let mut path = InstancePath::new(group_id);
+
+            if self.collect_hits_in_a_group(&mut path, point, &placement, theme, find_all, hits)? {
+                break
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Visits crumbs of the group at the end of a `path` in reversed
+    /// painting order, which is the reversal of the order of
+    /// [`CrumbChainIter`].  Returns `true` if the search is over.
+    fn collect_hits_in_a_group(
+        &self,
+        path: &mut InstancePath,
+        point: Point,
+        placement: &Placement,
+        theme: &Theme,
+        find_all: bool,
+        hits: &mut Vec<CrumbHit>,
+    ) -> Result<bool, VisError> {
+        let group_id = path.get_group();
+        let group =
+            self.get_group(group_id).ok_or_else(|| VisError::group_missing_for_id(group_id))?;
+        let placement = &placement.enter(group);
+        let local_point = placement.ts.inverse() * point;
+
+        if let Some(clip) = group.get_clip() {
+            if !matches!(clip.get_clip_path(), Some(path) if path.contains(local_point)) {
+                return Ok(false)
+            }
+        }
+
+        for (ndx, item) in group.get_crumb_items().iter().enumerate().rev() {
+            let item = placement.place(item);
+            let CrumbItem(crumb_id, ts, style_id) = item;
+            let crumb =
+                self.get_crumb(crumb_id).ok_or_else(|| VisError::crumb_missing_for_id(crumb_id))?;
+
+            if crumb.hit_test(point, ts, theme.get_style(style_id), theme) {
+                hits.push(CrumbHit(crumb_id, CrumbInstance::new(path.clone(), ndx, item)));
+
+                if !find_all {
+                    return Ok(true)
+                }
+            }
+        }
+
+        for (ndx, item) in group.get_group_items().iter().enumerate() {
+            path.push(ndx, item.0);
+
+            if self.collect_hits_in_a_group(
+                path,
+                point,
+                &placement.nest(item),
+                theme,
+                find_all,
+                hits,
+            )? {
+                return Ok(true)
+            }
+
+            path.pop();
+        }
+
+        Ok(false)
+    }
+
+    /// Returns the box, in output coordinates, covered by all crumbs
+    /// of a group and its subgroups, or `None` if nothing is painted
+    /// in the group.
+    ///
+    /// Stroke widths and marker extents are taken from the `theme`,
+    /// see [`Crumb::bounding_box()`].
+    pub fn group_bounds(
+        &self,
+        group_id: GroupId,
+        root_ts: Affine,
+        theme: &Theme,
+    ) -> Result<Option<Rect>, VisError> {
+        let mut bounds = None;
+
+        self.union_bounds_of_a_group(group_id, &Placement::new(root_ts), theme, &mut bounds)?;
+
+        Ok(bounds)
+    }
+
+    /// Returns the box covered by all visible crumbs of a scene, in
+    /// scene coordinates, or `None` if nothing is painted.
+    pub fn content_bounds(&self, theme: &Theme) -> Result<Option<Rect>, VisError> {
+        let placement = Placement::new(Affine::IDENTITY);
+        let mut bounds = None;
+
+        for group_id in self.get_visible_layers() {
+            self.union_bounds_of_a_group(group_id, &placement, theme, &mut bounds)?;
+        }
+
+        Ok(bounds)
+    }
+
+    fn union_bounds_of_a_group(
+        &self,
+        group_id: GroupId,
+        placement: &Placement,
+        theme: &Theme,
+        bounds: &mut Option<Rect>,
+    ) -> Result<(), VisError> {
+        let group =
+            self.get_group(group_id).ok_or_else(|| VisError::group_missing_for_id(group_id))?;
+        let placement = &placement.enter(group);
+        let mut group_bounds = None;
+
+        for item in group.get_crumb_items().iter() {
+            let CrumbItem(crumb_id, ts, style_id) = placement.place(item);
+            let crumb =
+                self.get_crumb(crumb_id).ok_or_else(|| VisError::crumb_missing_for_id(crumb_id))?;
+
+            if let Some(rect) = crumb.bounding_box(ts, theme.get_style(style_id), theme) {
+                group_bounds = Some(group_bounds.map_or(rect, |r: Rect| r.union(rect)));
+            }
+        }
+
+        for item in group.get_group_items().iter() {
+            self.union_bounds_of_a_group(item.0, &placement.nest(item), theme, &mut group_bounds)?;
+        }
+
+        if let Some(mut rect) = group_bounds {
+            if let Some(clip) = group.get_clip() {
+                if let Some(clip_path) = clip.get_clip_path() {
+                    rect = rect.intersect((placement.ts * clip_path).bounding_box());
+                } else {
+                    return Ok(())
+                }
+            }
+
+            *bounds = Some(bounds.map_or(rect, |r| r.union(rect)));
+        }
+
+        Ok(())
+    }
+
+    /// Computes the root transformation mapping this scene into the
+    /// output area of size `out_size`, less `out_margin` on each
+    /// side, according to the scene's [`FitMode`].
+    ///
+    /// In [`FitMode::Content`], an empty scene falls back to
+    /// [`FitMode::Size`].
+    pub fn get_root_ts(
+        &self,
+        theme: &Theme,
+        out_size: Size,
+        out_margin: Size,
+    ) -> Result<Affine, VisError> {
+        let out_width = out_size.width - 2. * out_margin.width;
+        let out_height = out_size.height - 2. * out_margin.height;
+
+        if self.fit_mode == FitMode::Content {
+            if let Some(bounds) = self.content_bounds(theme)? {
+                let scale_factor = match (bounds.width() > 0.0, bounds.height() > 0.0) {
+                    (true, true) => {
+                        (out_width / bounds.width()).min(out_height / bounds.height())
+                    }
+                    (true, false) => out_width / bounds.width(),
+                    (false, true) => out_height / bounds.height(),
+                    (false, false) => 1.0,
+                };
+                let translation = out_margin.to_vec2()
+                    + 0.5 * Vec2::new(out_width, out_height)
+                    - scale_factor * bounds.center().to_vec2();
+
+                return Ok(Affine::translate(translation) * Affine::scale(scale_factor))
+            }
+        }
+
+        let scale_factor = (out_width / self.size.width).min(out_height / self.size.height);
+
+        Ok(Affine::translate(out_margin.to_vec2()) * Affine::scale(scale_factor))
+    }
+
+    fn push_subgroups_of_a_group<'a>(
+        &'a self,
+        group: &'a Group,
+        mut level: usize,
+        group_chain: &mut Vec<(usize, GroupList<'a>)>,
+    ) -> Result<(), VisError> {
+        level += 1;
+        group_chain.push((level, GroupList::Items(group.get_group_items().iter())));
+
+        for GroupItem(group_id, ..) in group.get_group_items().iter() {
+            if let Some(group) = self.get_group(*group_id) {
+                self.push_subgroups_of_a_group(group, level, group_chain)?;
+            } else {
+                return Err(VisError::group_missing_for_id(*group_id))
+            }
+        }
+        Ok(())
+    }
+
+    /// Collects all groups of a scene.
+    ///
+    /// Returns an iterator listing [`GroupId`]s in the bottom-up
+    /// level order traversal of the scene tree.  Stacking order of
+    /// layers is ignored.
+    pub fn all_groups(&self) -> Result<GroupChainIter<'_>, VisError> {
+        let mut group_chain = vec![(0, GroupList::Layers(self.layers.iter()))];
+
+        for Layer { group_id, .. } in self.layers.iter() {
+            if let Some(group) = self.get_group(*group_id) {
+                self.push_subgroups_of_a_group(group, 0, &mut group_chain)?;
+            } else {
+                return Err(VisError::group_missing_for_id(*group_id))
+            }
+        }
+
+        Ok(GroupChainIter { group_chain })
+    }
+
+    /// Checks that all [`CrumbId`]s and [`GroupId`]s referenced by
+    /// groups, layers, and crumb names or tags of the [`Scene`]
+    /// resolve to crumbs and groups which weren't removed, and that no
+    /// group contains itself.
+    ///
+    /// The first dangling reference is reported as an error.
+    pub fn validate(&self) -> Result<(), VisError> {
+        for group in self.groups.iter().flatten() {
+            for CrumbItem(crumb_id, ..) in group.get_crumb_items().iter() {
+                if self.get_crumb(*crumb_id).is_none() {
+                    return Err(VisError::crumb_missing_for_id(*crumb_id))
+                }
+            }
+
+            for GroupItem(group_id, ..) in group.get_group_items().iter() {
+                if self.get_group(*group_id).is_none() {
+                    return Err(VisError::group_missing_for_id(*group_id))
+                }
+            }
+        }
+
+        for Layer { group_id, .. } in self.layers.iter() {
+            if self.get_group(*group_id).is_none() {
+                return Err(VisError::group_missing_for_id(*group_id))
+            }
+        }
+
+        let mut named_ids: Vec<_> = self.crumb_names.keys().chain(self.crumb_tags.keys()).collect();
+
+        named_ids.sort_unstable_by_key(|crumb_id| crumb_id.0);
+
+        for crumb_id in named_ids {
+            if self.get_crumb(*crumb_id).is_none() {
+                return Err(VisError::crumb_missing_for_id(*crumb_id))
+            }
+        }
+
+        let mut is_checked = vec![false; self.groups.len()];
+        let mut group_path = Vec::new();
+
+        for (ndx, slot) in self.groups.iter().enumerate() {
+            if slot.is_some() {
+                self.check_subgroups_of_a_group(GroupId(ndx), &mut group_path, &mut is_checked)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_subgroups_of_a_group(
+        &self,
+        group_id: GroupId,
+        group_path: &mut Vec<GroupId>,
+        is_checked: &mut [bool],
+    ) -> Result<(), VisError> {
+        if group_path.contains(&group_id) {
+            Err(VisError::group_reuse_attempt(group_id))
+        } else if is_checked[group_id.0] {
+            Ok(())
+        } else {
+            let group =
+                self.get_group(group_id).ok_or_else(|| VisError::group_missing_for_id(group_id))?;
+
+            group_path.push(group_id);
+
+            for GroupItem(subgroup_id, ..) in group.get_group_items().iter() {
+                self.check_subgroups_of_a_group(*subgroup_id, group_path, is_checked)?;
+            }
+
+            group_path.pop();
+            is_checked[group_id.0] = true;
+
+            Ok(())
+        }
+    }
+
+    pub fn simple_demo(theme: &Theme) -> Self {
+        let mut scene = Scene::new((1000., 1000.));
+
+        let border = scene.add_rect(Rect::new(0., 0., 1000., 1000.));
+        let button = scene.add_rounded_rect(RoundedRect::new(250., 400., 450., 600., 10.));
+
+        let lines = scene.add_grouped_lines(vec![
+            (Line::new((0., 500.), (250., 0.)), theme.get("line-1")),
+            (Line::new((0., 500.), (250., 1000.)), theme.get("line-1")),
+            (Line::new((250., 1000.), (250., 0.)), theme.get("line-2")),
+        ]);
+
+        let rects = scene.add_group(Group::from_crumbs(vec![
+            (button, theme.get("rect-1")),
+            (button, theme.get("rect-2")),
+        ]));
+
+        let circle = scene.add_circle(Circle::new((133., 500.), 110.));
+
+        let mixed_group = scene.add_group(
+            Group::from_groups(vec![lines, rects]).with_crumb(circle, theme.get("circ-1")),
+        );
+
+        let triple_group = scene.add_group(
+            Group::from_groups(vec![mixed_group])
+                .with_group_item(GroupItem(
+                    mixed_group,
+                    Affine::scale(0.5) * Affine::translate((750., 0.)),
+                    None,
+                ))
+                .with_group_item(GroupItem(
+                    mixed_group,
+                    Affine::scale(0.5) * Affine::translate((750., 1000.)),
+                    None,
+                )),
+        );
+
+        scene.add_layer(
+            Group::from_crumbs(vec![(border, theme.get("border"))])
+                .with_group(triple_group)
+                .with_group_item(GroupItem(triple_group, Affine::translate((500., 0.)), None)),
+        );
+
+        scene
+    }
+}
+
+#[cfg(feature = "serialize")]
+impl Scene {
+    /// Loads a [`Scene`] from its JSON representation.
+    ///
+    /// All references to crumbs and groups are checked, and the
+    /// first dangling one is reported as an error.
+    pub fn from_json_str<S: AsRef<str>>(json: S) -> Result<Self, VisError> {
+        let data: SceneData = serde_json::from_str(json.as_ref())?;
+
+        Scene::try_from(data)
+    }
+
+    /// Loads a [`Scene`] from a reader providing its JSON
+    /// representation.
+    pub fn from_json_reader<R: std::io::Read>(reader: R) -> Result<Self, VisError> {
+        let data: SceneData = serde_json::from_reader(reader)?;
+
+        Scene::try_from(data)
+    }
+
+    /// Saves the [`Scene`] in a pretty-printed JSON format, after
+    /// checking that all references to crumbs and groups resolve.
+    pub fn to_json_string(&self) -> Result<String, VisError> {
+        self.validate()?;
+
+        Ok(serde_json::to_string_pretty(&UncheckedScene(self))?)
+    }
+
+    /// Writes the [`Scene`] in a pretty-printed JSON format, after
+    /// checking that all references to crumbs and groups resolve.
+    pub fn write_json<W: std::io::Write>(&self, writer: W) -> Result<(), VisError> {
+        self.validate()?;
+
+        Ok(serde_json::to_writer_pretty(writer, &UncheckedScene(self))?)
+    }
+}
+
+/// Serialization of a [`Scene`] already checked by the caller, see
+/// [`Scene::validate()`].
+#[cfg(feature = "serialize")]
+struct UncheckedScene<'a>(&'a Scene);
+
+#[cfg(feature = "serialize")]
+impl serde::Serialize for UncheckedScene<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let scene = self.0;
+        let mut state = serializer.serialize_struct("Scene", 7)?;
+
+        state.serialize_field("size", &scene.size)?;
+        state.serialize_field("fit_mode", &scene.fit_mode)?;
+        state.serialize_field("crumbs", &scene.crumbs)?;
+        state.serialize_field("groups", &scene.groups)?;
+        state.serialize_field("layers", &scene.layers)?;
+        // Names and tags are sorted by crumb id, for the sake of
+        // stable output.
+        state.serialize_field(
+            "crumb_names",
+            &scene.crumb_names.iter().map(|(id, name)| (id.0, name)).collect::<BTreeMap<_, _>>(),
+        )?;
+        state.serialize_field(
+            "crumb_tags",
+            &scene.crumb_tags.iter().map(|(id, tags)| (id.0, tags)).collect::<BTreeMap<_, _>>(),
+        )?;
+
+        state.end()
+    }
+}
+
+#[cfg(feature = "serialize")]
+impl serde::Serialize for Scene {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::Error;
+
+        self.validate().map_err(S::Error::custom)?;
+
+        UncheckedScene(self).serialize(serializer)
+    }
+}
+
+/// Deserialized contents of a [`Scene`], before reference checking.
+#[cfg(feature = "serialize")]
+#[derive(serde::Deserialize)]
+#[serde(rename = "Scene")]
+struct SceneData {
+    size:        Size,
+    #[serde(default)]
+    fit_mode:    FitMode,
+    crumbs:      Vec<Option<Crumb>>,
+    groups:      Vec<Option<Group>>,
+    layers:      Vec<Layer>,
+    #[serde(default)]
+    crumb_names: HashMap<CrumbId, String>,
+    #[serde(default)]
+    crumb_tags:  HashMap<CrumbId, Vec<String>>,
+}
+
+#[cfg(feature = "serialize")]
+impl TryFrom<SceneData> for Scene {
+    type Error = VisError;
+
+    fn try_from(data: SceneData) -> Result<Self, Self::Error> {
+        let SceneData { size, fit_mode, crumbs, groups, layers, crumb_names, crumb_tags } = data;
+        let scene = Scene {
+            size,
+            fit_mode,
+            crumbs,
+            groups,
+            layers,
+            crumb_names,
+            crumb_tags,
+            index: OnceLock::new(),
+            spatial_index: SpatialCache::default(),
+            changes: SceneChanges::default(),
+        };
+
+        scene.validate()?;
+
+        Ok(scene)
+    }
+}
+
+/// The effective transformation of a group instance, together with
+/// the chain of [`StyleOverride`]s of group items leading to it, and
+/// the default style of the innermost enclosing group which has one.
+#[derive(Clone)]
+struct Placement<'a> {
+    ts:            Affine,
+    overrides:     Vec<&'a StyleOverride>,
+    default_style: Option<StyleId>,
+}
+
+impl<'a> Placement<'a> {
+    fn new(root_ts: Affine) -> Self {
+        Placement { ts: root_ts, overrides: Vec::new(), default_style: None }
+    }
+
+    /// Places a subgroup instance, given its group item.
+    fn nest(&self, item: &'a GroupItem) -> Self {
+        let mut overrides = self.overrides.clone();
+
+        overrides.extend(item.2.as_ref());
+
+        Placement { ts: self.ts * item.1, overrides, default_style: self.default_style }
+    }
+
+    /// Enters the placed `group`, whose default style, if any, applies
+    /// to crumbs of the group and its subgroups.
+    fn enter(&self, group: &Group) -> Self {
+        let default_style = group.get_default_style().or(self.default_style);
+
+        Placement { ts: self.ts, overrides: self.overrides.clone(), default_style }
+    }
+
+    /// Returns a crumb item with the effective transformation and the
+    /// effective style of a crumb instance.
+    fn place(&self, item: &CrumbItem) -> CrumbItem {
+        let style_id = item.2.or(self.default_style);
+
+        CrumbItem(item.0, self.ts * item.1, apply_style_overrides(&self.overrides, style_id))
+    }
+}
+
+/// State of a traversal of a [`Scene`], which skips crumbs and group
+/// instances lying outside of a rectangle.
+struct Culling<'a> {
+    rect:        Rect,
+    outset_rect: Rect,
+    root_ts:     Affine,
+    theme:       &'a Theme,
+    spans:       &'a [GroupSpan],
+    hits:        Vec<usize>,
+    next_span:   usize,
+}
+
+impl Culling<'_> {
+    /// Checks whether anything within scene `bounds` may be seen.
+    fn may_see(&self, bounds: Option<Rect>) -> bool {
+        matches!(bounds, Some(bounds)
+            if is_overlapping(&self.root_ts.transform_rect_bbox(bounds), &self.outset_rect))
+    }
+
+    /// Returns candidate crumbs found in the spatial index, whose
+    /// positions in painting order fall within `range`.
+    fn hits_in(&self, range: Range<usize>) -> &[usize] {
+        let start = self.hits.partition_point(|order| *order < range.start);
+        let end = self.hits.partition_point(|order| *order < range.end);
+
+        &self.hits[start..end]
+    }
+}
+
+type CrumbList<'a> = std::slice::Iter<'a, CrumbItem>;
+
+/// An iterator traversing all [`CrumbItem`]s of a [`Scene`].
 ///
-/// Note: the effective [`TranslateScale`] transform of each
-/// [`CrumbItem`] is composed on-the-fly, in the iterator's `next()`
-/// method.
+/// Note: the effective [`Affine`] transform and the effective style
+/// of each [`CrumbItem`] are computed on-the-fly, in the iterator's
+/// `next()` method.
 pub struct CrumbChainIter<'a> {
-    crumb_chain: Vec<(usize, CrumbList<'a>, TranslateScale)>,
+    crumb_chain: Vec<(usize, CrumbList<'a>, Placement<'a>)>,
 }
 
 impl<'a> Iterator for CrumbChainIter<'a> {
     type Item = (usize, CrumbItem);
 
     fn next(&mut self) -> Option<Self::Item> {
-        while let Some((level, mut crumb_list, ts)) = self.crumb_chain.pop() {
+        while let Some((level, mut crumb_list, placement)) = self.crumb_chain.pop() {
             if let Some(item) = crumb_list.next() {
-                self.crumb_chain.push((level, crumb_list, ts));
+                let item = placement.place(item);
 
-                return Some((level, CrumbItem(item.0, item.1 * ts, item.2)))
+                self.crumb_chain.push((level, crumb_list, placement));
+
+                return Some((level, item))
+            }
+        }
+
+        None
+    }
+}
+
+type CrumbInstanceList<'a> = std::iter::Enumerate<std::slice::Iter<'a, CrumbItem>>;
+
+/// An iterator traversing all crumb instances of a [`Scene`].
+///
+/// Note: as in [`CrumbChainIter`], the effective [`Affine`] transform
+/// and the effective style of each [`CrumbInstance`] are computed
+/// on-the-fly.
+pub struct CrumbInstanceIter<'a> {
+    crumb_chain: Vec<(usize, CrumbInstanceList<'a>, Placement<'a>, InstancePath)>,
+}
+
+impl<'a> Iterator for CrumbInstanceIter<'a> {
+    type Item = (usize, CrumbInstance);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((level, mut crumb_list, placement, path)) = self.crumb_chain.pop() {
+            if let Some((ndx, item)) = crumb_list.next() {
+                let instance = CrumbInstance::new(path.clone(), ndx, placement.place(item));
+
+                self.crumb_chain.push((level, crumb_list, placement, path));
+
+                return Some((level, instance))
             }
         }
 
@@ -429,3 +1936,500 @@ impl<'a> Iterator for GroupChainIter<'a> {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Style, Stroke, Fill, Color, InstanceId};
+
+    /// A layer with a crumb in view and a subgroup with effects,
+    /// holding a crumb far out of view.
+    fn offscreen_scene() -> (Scene, CrumbId, CrumbId) {
+        let mut scene = Scene::new((100.0, 100.0));
+        let near = scene.add_rect(Rect::new(10.0, 10.0, 20.0, 20.0));
+        let far = scene.add_rect(Rect::new(1000.0, 1000.0, 1010.0, 1010.0));
+        let subgroup = scene.add_group(Group::from_crumbs(vec![(far, None)]).with_opacity(0.5));
+
+        scene.add_layer(Group::from_crumbs(vec![(near, None)]).with_group(subgroup));
+
+        (scene, near, far)
+    }
+
+    #[test]
+    fn scene_is_sync() {
+        fn assert_sync<T: Sync>() {}
+
+        assert_sync::<Scene>();
+    }
+
+    #[test]
+    fn offscreen_crumbs_culled() {
+        let (scene, near, _) = offscreen_scene();
+        let theme = Theme::new();
+        let rect = Rect::new(0.0, 0.0, 100.0, 100.0);
+        let items = scene.visible_crumbs_in(rect, Affine::IDENTITY, &theme).unwrap();
+
+        assert_eq!(items.iter().map(|item| item.0).collect::<Vec<_>>(), vec![near]);
+
+        let steps = scene.visible_paint_steps_in(rect, Affine::IDENTITY, &theme).unwrap();
+
+        assert_eq!(scene.visible_paint_steps(Affine::IDENTITY).unwrap().len(), 4);
+        assert_eq!(steps.len(), 1);
+        assert!(matches!(steps[0], PaintStep::Crumb(Crumb::Rect(_), ..)));
+    }
+
+    #[test]
+    fn culled_after_changes() {
+        let (mut scene, near, far) = offscreen_scene();
+        let theme = Theme::new();
+        let rect = Rect::new(990.0, 990.0, 1020.0, 1020.0);
+        let steps = scene.visible_paint_steps_in(rect, Affine::IDENTITY, &theme).unwrap();
+
+        assert!(matches!(steps.as_slice(), [
+            PaintStep::BeginGroup(..),
+            PaintStep::Crumb(..),
+            PaintStep::EndGroup
+        ]));
+
+        *scene.get_crumb_mut(near).unwrap() = Crumb::Rect(Rect::new(995.0, 995.0, 998.0, 998.0));
+
+        let items = scene.visible_crumbs_in(rect, Affine::IDENTITY, &theme).unwrap();
+
+        assert_eq!(items.iter().map(|item| item.0).collect::<Vec<_>>(), vec![far, near]);
+    }
+
+    #[test]
+    fn style_changes_keep_spatial_index() {
+        let (mut scene, near, _) = offscreen_scene();
+        let theme = Theme::new();
+        let layer = scene.get_layers()[0];
+        let rect = Rect::new(0.0, 0.0, 100.0, 100.0);
+
+        scene.visible_crumbs_in(rect, Affine::IDENTITY, &theme).unwrap();
+        scene.set_crumb_style(layer, near, Some(StyleId(0))).unwrap();
+        scene.set_group_style(layer, None).unwrap();
+        scene.set_group_name(layer, "layer").unwrap();
+
+        assert!(!scene.spatial_index.is_empty());
+
+        scene.get_group_mut(layer).unwrap();
+
+        assert!(scene.spatial_index.is_empty());
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn json_round_trip() {
+        let theme = Theme::simple_demo();
+        let mut scene = Scene::simple_demo(&theme);
+        let border = CrumbId(0);
+
+        scene.set_crumb_name(border, "border").unwrap();
+        scene.add_crumb_tag(border, "frame").unwrap();
+
+        let json = scene.to_json_string().unwrap();
+        let loaded = Scene::from_json_str(&json).unwrap();
+
+        assert_eq!(loaded.to_json_string().unwrap(), json);
+        assert_eq!(loaded.find_crumb_by_name("border"), Some(border));
+        assert_eq!(loaded.find_crumbs_by_tag("frame"), vec![border]);
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn json_stable_order() {
+        let named_scene = || {
+            let mut scene = Scene::new((100.0, 100.0));
+            let crumbs: Vec<_> = (0..12)
+                .map(|ndx| {
+                    let x = f64::from(ndx) * 5.0;
+
+                    (scene.add_rect(Rect::new(x, 0.0, x + 4.0, 4.0)), None)
+                })
+                .collect();
+
+            for (ndx, (crumb_id, _)) in crumbs.iter().enumerate() {
+                scene.set_crumb_name(*crumb_id, format!("crumb-{}", ndx)).unwrap();
+                scene.add_crumb_tag(*crumb_id, "all").unwrap();
+                scene.add_crumb_tag(*crumb_id, if ndx % 2 == 0 { "even" } else { "odd" }).unwrap();
+            }
+
+            scene.add_layer(Group::from_crumbs(crumbs));
+
+            scene
+        };
+        let json = named_scene().to_json_string().unwrap();
+
+        assert!(json.find("crumb-9\"").unwrap() < json.find("crumb-10\"").unwrap());
+
+        for _ in 0..4 {
+            assert_eq!(named_scene().to_json_string().unwrap(), json);
+        }
+
+        let loaded = Scene::from_json_str(&json).unwrap();
+
+        assert_eq!(loaded.to_json_string().unwrap(), json);
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn json_lowest_dangling_id() {
+        let (scene, ..) = offscreen_scene();
+        let mut value = serde_json::to_value(&scene).unwrap();
+
+        value["crumb_names"] = serde_json::json!({ "40": "a", "7": "b", "25": "c" });
+        value["crumb_tags"] = serde_json::json!({ "9": ["d"], "5": ["e"] });
+
+        for _ in 0..4 {
+            let err = serde_json::from_value::<Scene>(value.clone()).unwrap_err();
+
+            assert_eq!(err.to_string(), VisError::crumb_missing_for_id(CrumbId(5)).to_string());
+        }
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn json_dangling_ids() {
+        let (mut scene, near, _) = offscreen_scene();
+
+        scene.remove_crumb(near).unwrap();
+
+        assert!(scene.to_json_string().is_err());
+
+        let json = serde_json::to_string(&UncheckedScene(&scene)).unwrap();
+        let err = Scene::from_json_str(json).unwrap_err();
+
+        assert_eq!(err.to_string(), VisError::crumb_missing_for_id(near).to_string());
+    }
+
+    fn filled_theme() -> Theme {
+        let style = Style::new()
+            .with_fill(Fill::Color(Color::WHITE))
+            .with_stroke(Stroke::new().with_width(2.0));
+
+        Theme::new().with_styles(vec![("filled", style)])
+    }
+
+    /// Two overlapping crumbs of a layer, over a crumb of a subgroup,
+    /// all painted in the default style of the layer.
+    fn stacked_scene(theme: &Theme) -> (Scene, [CrumbId; 3]) {
+        let mut scene = Scene::new((100.0, 100.0));
+        let bottom = scene.add_rect(Rect::new(0.0, 0.0, 50.0, 50.0));
+        let top = scene.add_rect(Rect::new(10.0, 10.0, 60.0, 60.0));
+        let under = scene.add_circle(Circle::new((30.0, 30.0), 20.0));
+        let subgroup = scene.add_group(Group::from_crumbs(vec![(under, None)]));
+        let mut layer = Group::from_crumbs(vec![(bottom, None), (top, None)]).with_group(subgroup);
+
+        if let Some(style_id) = theme.get("filled") {
+            layer.set_default_style(style_id);
+        }
+
+        scene.add_layer(layer);
+
+        (scene, [bottom, top, under])
+    }
+
+    #[test]
+    fn hit_test_order() {
+        let theme = filled_theme();
+        let (mut scene, [bottom, top, under]) = stacked_scene(&theme);
+        let point = Point::new(30.0, 30.0);
+        let hits = scene.hit_test_all(point, Affine::IDENTITY, &theme).unwrap();
+
+        assert_eq!(hits.iter().map(|hit| hit.0).collect::<Vec<_>>(), vec![top, bottom, under]);
+        assert_eq!(hits[2].1.get_path().get_groups().len(), 2);
+        assert_eq!(scene.hit_test(point, Affine::IDENTITY, &theme).unwrap().unwrap().0, top);
+
+        let hit = scene.hit_test(Point::new(5.0, 5.0), Affine::IDENTITY, &theme).unwrap();
+
+        assert_eq!(hit.unwrap().0, bottom);
+
+        // Output coordinates are mapped back through the root transformation.
+        let hit = scene.hit_test(Point::new(10.0, 10.0), Affine::scale(2.0), &theme).unwrap();
+
+        assert_eq!(hit.unwrap().0, bottom);
+        let hit = scene.hit_test(Point::new(90.0, 90.0), Affine::IDENTITY, &theme).unwrap();
+
+        assert!(hit.is_none());
+
+        scene.hide_layer(scene.get_layers()[0]).unwrap();
+
+        assert!(scene.hit_test_all(point, Affine::IDENTITY, &theme).unwrap().is_empty());
+    }
+
+    #[test]
+    fn content_bounds() {
+        let theme = filled_theme();
+        let (mut scene, _) = stacked_scene(&theme);
+        let layer = scene.get_layers()[0];
+
+        // Outlines are widened by half of the stroke width.
+        assert_eq!(scene.content_bounds(&theme).unwrap(), Some(Rect::new(-1.0, -1.0, 61.0, 61.0)));
+        assert_eq!(
+            scene.group_bounds(layer, Affine::scale(2.0), &theme).unwrap(),
+            Some(Rect::new(-2.0, -2.0, 122.0, 122.0))
+        );
+
+        scene.hide_layer(layer).unwrap();
+
+        assert_eq!(scene.content_bounds(&theme).unwrap(), None);
+        assert_eq!(Scene::new((10.0, 10.0)).content_bounds(&theme).unwrap(), None);
+    }
+
+    #[test]
+    fn changes_and_diff() {
+        let theme = filled_theme();
+        let (mut scene, [bottom, top, under]) = stacked_scene(&theme);
+        let layer = scene.get_layers()[0];
+        let changes = scene.take_changes();
+
+        assert_eq!(changes.get_crumbs(), vec![bottom, top, under]);
+        assert_eq!(changes.get_layers(), vec![layer]);
+        assert!(changes.has_group(layer));
+        assert!(!scene.has_changes());
+        assert!(scene.take_changes().is_empty());
+
+        let old_scene = scene.clone();
+
+        *scene.get_crumb_mut(top).unwrap() = Crumb::Rect(Rect::new(0.0, 0.0, 5.0, 5.0));
+        scene.set_z_index(layer, 1).unwrap();
+        scene.set_fit_mode(FitMode::Content);
+
+        assert!(scene.has_changes());
+
+        let changes = scene.take_changes();
+        let diff = scene.diff(&old_scene);
+
+        for changes in &[changes, diff] {
+            assert_eq!(changes.get_crumbs(), vec![top]);
+            assert!(changes.get_groups().is_empty());
+            assert_eq!(changes.get_layers(), vec![layer]);
+            assert!(changes.is_resized());
+        }
+
+        assert_eq!(scene.get_dirty_layers(&scene.diff(&old_scene)).unwrap(), vec![layer]);
+
+        // Mutable access is recorded even without modification, but a
+        // diff compares actual contents.
+        let old_scene = scene.clone();
+        let _ = scene.get_crumb_mut(under);
+
+        assert!(scene.take_changes().has_crumb(under));
+        assert!(scene.diff(&old_scene).is_empty());
+
+        scene.remove_crumb(under).unwrap();
+
+        let diff = scene.diff(&old_scene);
+
+        assert_eq!(diff.get_crumbs(), vec![under]);
+        assert!(!diff.has_layer(layer));
+    }
+
+    /// Lists widths of painted rectangles with their effective styles.
+    fn painted_styles(scene: &Scene) -> Vec<(f64, Option<usize>)> {
+        let mut result: Vec<_> = scene
+            .visible_paint_steps(Affine::IDENTITY)
+            .unwrap()
+            .into_iter()
+            .filter_map(|step| match step {
+                PaintStep::Crumb(Crumb::Rect(rect), _, style_id) => {
+                    Some((rect.width(), style_id.map(|id| id.0)))
+                }
+                _ => None,
+            })
+            .collect();
+
+        result.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        result
+    }
+
+    #[test]
+    fn style_overrides() {
+        let mut scene = Scene::new((10.0, 10.0));
+        let styled = scene.add_rect(Rect::new(0.0, 0.0, 1.0, 1.0));
+        let unstyled = scene.add_rect(Rect::new(0.0, 0.0, 2.0, 2.0));
+        let defaulted = scene.add_rect(Rect::new(0.0, 0.0, 3.0, 3.0));
+        let shared = scene
+            .add_group(Group::from_crumbs(vec![(styled, Some(StyleId(1))), (unstyled, None)]));
+        let with_default = scene.add_group(
+            Group::from_crumbs(vec![(defaulted, None)]).with_default_style(StyleId(2)),
+        );
+        let inner = StyleOverride::from_map(vec![(StyleId(1), StyleId(2))]);
+        let outer =
+            StyleOverride::from_map(vec![(StyleId(1), StyleId(3)), (StyleId(2), StyleId(4))]);
+        let middle = scene.add_group(Group::from_group_items(vec![
+            GroupItem(shared, Affine::IDENTITY, Some(inner)),
+            GroupItem(with_default, Affine::IDENTITY, None),
+            GroupItem(shared, Affine::IDENTITY, Some(StyleOverride::ReplaceAll(StyleId(5)))),
+        ]));
+        let layer = scene.add_layer(Group::from_group_items(vec![GroupItem(
+            middle,
+            Affine::IDENTITY,
+            Some(outer),
+        )]));
+
+        // The inner map turns 1 into 2, then the outer one turns 2
+        // into 4; maps leave unstyled crumbs alone, and default styles
+        // are overridden like explicit ones.
+        assert_eq!(painted_styles(&scene), vec![
+            (1.0, Some(4)),
+            (1.0, Some(5)),
+            (2.0, None),
+            (2.0, Some(5)),
+            (3.0, Some(4)),
+        ]);
+
+        // A default style of an enclosing group applies to unstyled
+        // crumbs before any overrides.
+        scene.get_group_mut(layer).unwrap().set_default_style(StyleId(1));
+
+        assert_eq!(painted_styles(&scene), vec![
+            (1.0, Some(4)),
+            (1.0, Some(5)),
+            (2.0, Some(4)),
+            (2.0, Some(5)),
+            (3.0, Some(4)),
+        ]);
+    }
+
+    #[test]
+    fn remove_and_validate() {
+        let theme = filled_theme();
+        let (mut scene, [bottom, top, under]) = stacked_scene(&theme);
+        let layer = scene.get_layers()[0];
+        let subgroup = scene.get_group(layer).unwrap().get_group_items()[0].0;
+
+        scene.set_crumb_name(top, "top").unwrap();
+
+        assert!(matches!(scene.remove_crumb(top), Ok(Crumb::Rect(_))));
+        assert!(scene.get_crumb(top).is_none());
+        assert!(scene.find_crumb_by_name("top").is_none());
+        assert!(scene.remove_crumb(top).is_err());
+        assert_eq!(
+            scene.validate().unwrap_err().to_string(),
+            VisError::crumb_missing_for_id(top).to_string()
+        );
+
+        // Ids of removed crumbs aren't reused.
+        let next = scene.add_rect(Rect::new(0.0, 0.0, 1.0, 1.0));
+
+        assert_ne!(next, top);
+        assert!(scene.get_crumb(bottom).is_some());
+
+        scene.remove_group(layer).unwrap();
+
+        assert_eq!(
+            scene.validate().unwrap_err().to_string(),
+            VisError::group_missing_for_id(layer).to_string()
+        );
+
+        scene.remove_layer(layer).unwrap();
+
+        assert!(scene.remove_layer(layer).is_err());
+        assert!(scene.validate().is_ok());
+
+        scene.remove_crumb(under).unwrap();
+
+        assert_eq!(
+            scene.validate().unwrap_err().to_string(),
+            VisError::crumb_missing_for_id(under).to_string()
+        );
+
+        scene.remove_group(subgroup).unwrap();
+
+        assert!(scene.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_group_cycle() {
+        let mut scene = Scene::new((10.0, 10.0));
+        let inner = scene.add_group(Group::default());
+        let outer = scene.add_layer(Group::default().with_group(inner));
+
+        assert!(scene.validate().is_ok());
+
+        scene.get_group_mut(inner).unwrap().add_group(outer);
+
+        assert!(scene.validate().is_err());
+    }
+
+    #[test]
+    fn find_by_path() {
+        let mut scene = Scene::new((10.0, 10.0));
+        let label = scene.add_rect(Rect::new(0.0, 0.0, 1.0, 1.0));
+        let a = scene.add_group(Group::from_crumbs(vec![(label, None)]).with_name("a"));
+        let b = scene.add_group(Group::default().with_name("b"));
+        let nodes = scene.add_layer(Group::from_groups(vec![a, b]).with_name("nodes"));
+        let more = scene.add_layer(Group::from_groups(vec![b]).with_name("more-nodes"));
+
+        scene.set_crumb_name(label, "label").unwrap();
+
+        assert_eq!(scene.find_groups_by_path("nodes"), vec![nodes]);
+        assert_eq!(scene.find_groups_by_path("nodes/a"), vec![a]);
+        assert_eq!(scene.find_groups_by_path("nodes/?"), vec![a, b]);
+        assert_eq!(scene.find_groups_by_path("*nodes/b"), vec![b]);
+        assert_eq!(scene.find_groups_by_path("*nodes"), vec![nodes, more]);
+        assert_eq!(scene.find_groups_by_path("more-nodes/a"), vec![]);
+        assert_eq!(scene.find_groups_by_path("a/b"), vec![]);
+        assert_eq!(scene.find_crumbs_by_path("nodes/a/label"), vec![label]);
+        assert_eq!(scene.find_crumbs_by_path("*/*/lab*"), vec![label]);
+        assert_eq!(scene.find_crumbs_by_path("label"), vec![label]);
+        assert_eq!(scene.find_crumbs_by_path("nodes/label"), vec![]);
+
+        scene.set_group_name(b, "a").unwrap();
+
+        assert_eq!(scene.find_groups_by_name("a"), vec![a, b]);
+        assert_eq!(scene.find_group_by_name("a"), Some(a));
+        assert_eq!(scene.find_groups_by_path("more-nodes/a"), vec![b]);
+    }
+
+    fn instance_ids(scene: &Scene) -> Vec<((CrumbId, Vec<usize>), InstanceId)> {
+        scene
+            .all_crumb_instances(Affine::IDENTITY)
+            .unwrap()
+            .map(|(_, instance)| {
+                let path = instance.get_path().get_item_indices().to_vec();
+
+                ((instance.get_item().0, path), instance.get_id())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn instance_ids_of_shared_group() {
+        let mut scene = Scene::new((200.0, 100.0));
+        let left = scene.add_rect(Rect::new(0.0, 0.0, 10.0, 10.0));
+        let right = scene.add_rect(Rect::new(20.0, 0.0, 30.0, 10.0));
+        let shared = scene.add_group(Group::from_crumbs(vec![(left, None), (right, None)]));
+        let layer = scene.add_layer(Group::from_groups(vec![shared]).with_group_item(GroupItem(
+            shared,
+            Affine::translate((100.0, 0.0)),
+            None,
+        )));
+        let ids = instance_ids(&scene);
+        let unique: std::collections::HashSet<_> = ids.iter().map(|(_, id)| *id).collect();
+
+        assert_eq!(ids.len(), 4);
+        assert_eq!(unique.len(), 4);
+        assert_eq!(ids, instance_ids(&scene));
+
+        // Group instances are told apart from crumb instances.
+        for (_, instance) in scene.all_crumb_instances(Affine::IDENTITY).unwrap() {
+            assert_eq!(instance.get_path().get_groups(), &[layer, shared]);
+            assert!(!unique.contains(&instance.get_path().get_id()));
+        }
+
+        // Ids survive edits which don't touch the paths.
+        *scene.get_crumb_mut(left).unwrap() = Crumb::Rect(Rect::new(0.0, 0.0, 5.0, 5.0));
+
+        let extra = scene.add_rect(Rect::new(0.0, 50.0, 10.0, 60.0));
+
+        scene.get_group_mut(layer).unwrap().add_crumb(extra, None);
+
+        let new_ids = instance_ids(&scene);
+
+        assert_eq!(new_ids.len(), 5);
+        assert!(ids.iter().all(|entry| new_ids.contains(entry)));
+    }
+}
@@ -0,0 +1,336 @@
+use std::{
+    ops::Range,
+    sync::{Arc, RwLock, PoisonError},
+};
+use kurbo::{Rect, Affine};
+use crate::affine::get_scale_factor;
+
+/// Maximal number of children of a node.
+const NODE_CAPACITY: usize = 16;
+
+#[derive(Clone, Debug)]
+enum Node {
+    Leaf(Vec<usize>),
+    Branch(Vec<usize>),
+}
+
+/// A static R-tree, bulk-loaded with the Sort-Tile-Recursive
+/// algorithm.
+///
+/// Note: the tree isn't updated incrementally, but rebuilt from
+/// scratch whenever its entries change.
+#[derive(Clone, Debug)]
+pub(crate) struct RTree<T> {
+    entries: Vec<(Rect, T)>,
+    nodes:   Vec<(Rect, Node)>,
+    root:    Option<usize>,
+}
+
+impl<T> RTree<T> {
+    pub(crate) fn new(entries: Vec<(Rect, T)>) -> Self {
+        let mut tree = RTree { entries, nodes: Vec::new(), root: None };
+        let boxes: Vec<_> = tree.entries.iter().map(|(rect, _)| *rect).collect();
+
+        if !boxes.is_empty() {
+            let mut level: Vec<usize> = tree
+                .pack(&boxes, (0..boxes.len()).collect())
+                .into_iter()
+                .map(|children| tree.push_node(&boxes, Node::Leaf(children)))
+                .collect();
+
+            while level.len() > 1 {
+                let boxes: Vec<_> = tree.nodes.iter().map(|(rect, _)| *rect).collect();
+
+                level = tree
+                    .pack(&boxes, level)
+                    .into_iter()
+                    .map(|children| tree.push_node(&boxes, Node::Branch(children)))
+                    .collect();
+            }
+
+            tree.root = level.first().copied();
+        }
+
+        tree
+    }
+
+    /// Splits `ids` of `boxes` into runs of at most
+    /// [`NODE_CAPACITY`] spatially close boxes: `ids` are sorted by
+    /// horizontal centers, cut into vertical slices, and each slice
+    /// is sorted by vertical centers.
+    fn pack(&self, boxes: &[Rect], mut ids: Vec<usize>) -> Vec<Vec<usize>> {
+        let num_runs = ids.len().div_ceil(NODE_CAPACITY);
+        let num_slices = (num_runs as f64).sqrt().ceil() as usize;
+        let slice_len = num_slices * NODE_CAPACITY;
+        let mut result = Vec::with_capacity(num_runs);
+
+        ids.sort_by(|a, b| boxes[*a].center().x.total_cmp(&boxes[*b].center().x));
+
+        for slice in ids.chunks_mut(slice_len) {
+            slice.sort_by(|a, b| boxes[*a].center().y.total_cmp(&boxes[*b].center().y));
+
+            for run in slice.chunks(NODE_CAPACITY) {
+                result.push(run.to_vec());
+            }
+        }
+
+        result
+    }
+
+    fn push_node(&mut self, boxes: &[Rect], node: Node) -> usize {
+        let children = match node {
+            Node::Leaf(ref children) | Node::Branch(ref children) => children,
+        };
+        let rect = children.iter().map(|id| boxes[*id]).reduce(|a, b| a.union(b));
+
+        self.nodes.push((rect.unwrap_or(Rect::ZERO), node));
+
+        self.nodes.len() - 1
+    }
+
+    /// Visits all entries, such that `is_hit` accepts their boxes.
+    ///
+    /// The predicate must be monotone, i.e. accept a box whenever it
+    /// accepts any box contained in it, since it's also used for
+    /// pruning subtrees by their bounding boxes.
+    pub(crate) fn query<P, F>(&self, is_hit: P, mut f: F)
+    where
+        P: Fn(&Rect) -> bool,
+        F: FnMut(&Rect, &T),
+    {
+        let mut stack: Vec<usize> = self.root.into_iter().collect();
+
+        while let Some(ndx) = stack.pop() {
+            let (rect, node) = &self.nodes[ndx];
+
+            if is_hit(rect) {
+                match node {
+                    Node::Leaf(children) => {
+                        for (rect, value) in children.iter().map(|id| &self.entries[*id]) {
+                            if is_hit(rect) {
+                                f(rect, value);
+                            }
+                        }
+                    }
+                    Node::Branch(children) => stack.extend(children.iter()),
+                }
+            }
+        }
+    }
+}
+
+/// Checks whether two rectangles overlap, or touch each other.
+#[inline]
+pub(crate) fn is_overlapping(a: &Rect, b: &Rect) -> bool {
+    a.x0 <= b.x1 && b.x0 <= a.x1 && a.y0 <= b.y1 && b.y0 <= a.y1
+}
+
+/// Crumbs of a group instance and of its subgroup instances, as
+/// visited in painting order.
+#[derive(Clone, Debug)]
+pub(crate) struct GroupSpan {
+    /// Geometric bounding box of all crumbs in scene coordinates, or
+    /// `None` if none of them has an area or a length.
+    pub(crate) bounds:     Option<Rect>,
+    /// Number of group instances, including this one.
+    pub(crate) num_groups: usize,
+    /// Positions in painting order of all crumbs.
+    pub(crate) crumbs:     Range<usize>,
+}
+
+/// An R-tree over geometric bounding boxes of all visible crumbs of a
+/// [`Scene`](crate::Scene), in scene coordinates, together with
+/// bounds of all visible group instances.
+///
+/// Only the geometry is indexed, so that the index is kept when
+/// styles change.  Note: this is a cache, rebuilt by a scene on
+/// demand, whenever the geometry might have changed.
+#[derive(Clone, Debug)]
+pub(crate) struct SpatialIndex {
+    tree:      RTree<usize>,
+    spans:     Vec<GroupSpan>,
+    max_scale: f64,
+}
+
+impl SpatialIndex {
+    /// Creates an index of crumbs, each given with its geometric
+    /// bounding box, its position in painting order and its
+    /// effective transformation, and of group instances, given in
+    /// the order of the painting traversal.
+    pub(crate) fn new(entries: Vec<(Rect, (usize, Affine))>, spans: Vec<GroupSpan>) -> Self {
+        let max_scale =
+            entries.iter().map(|(_, (_, ts))| get_scale_factor(*ts)).fold(0.0, f64::max);
+
+        let entries = entries.into_iter().map(|(rect, (order, _))| (rect, order)).collect();
+
+        SpatialIndex { tree: RTree::new(entries), spans, max_scale }
+    }
+
+    /// Returns the largest scale factor of the effective
+    /// transformations of indexed crumbs.
+    #[inline]
+    pub(crate) fn get_max_scale(&self) -> f64 {
+        self.max_scale
+    }
+
+    #[inline]
+    pub(crate) fn get_spans(&self) -> &[GroupSpan] {
+        self.spans.as_slice()
+    }
+
+    /// Lists, in painting order, positions of all indexed crumbs,
+    /// whose bounding boxes transformed by `root_ts` overlap a `rect`
+    /// given in output coordinates.
+    pub(crate) fn query(&self, rect: Rect, root_ts: Affine) -> Vec<usize> {
+        let mut result = Vec::new();
+
+        self.tree.query(
+            |bbox| is_overlapping(&root_ts.transform_rect_bbox(*bbox), &rect),
+            |_, order| result.push(*order),
+        );
+
+        result.sort_unstable();
+
+        result
+    }
+}
+
+/// A [`SpatialIndex`] cached by a [`Scene`](crate::Scene), together
+/// with the key of fonts its labels were measured with, if any, see
+/// [`FontLibrary::get_state_key()`](crate::FontLibrary::get_state_key).
+///
+/// Unlike a `OnceLock`, the cache may be refilled through a shared
+/// reference, whenever a query is run with other fonts, e.g. after
+/// another theme was loaded, or on another thread.
+#[derive(Default, Debug)]
+pub(crate) struct SpatialCache(RwLock<Option<(Option<u64>, Arc<SpatialIndex>)>>);
+
+impl SpatialCache {
+    /// Returns the cached index, unless it was measured with fonts
+    /// other than those of the current thread.
+    pub(crate) fn get(&self) -> Option<Arc<SpatialIndex>> {
+        match &*self.0.read().unwrap_or_else(PoisonError::into_inner) {
+            Some((None, index)) => Some(index.clone()),
+            Some((Some(key), index)) if *key == current_font_key() => Some(index.clone()),
+            _ => None,
+        }
+    }
+
+    /// Caches an `index`, which depends on fonts if `has_labels` is
+    /// true, replacing any index cached before.
+    pub(crate) fn set(&self, index: Arc<SpatialIndex>, has_labels: bool) {
+        let key = if has_labels { Some(current_font_key()) } else { None };
+
+        *self.0.write().unwrap_or_else(PoisonError::into_inner) = Some((key, index));
+    }
+
+    #[inline]
+    pub(crate) fn clear(&mut self) {
+        *self.0.get_mut().unwrap_or_else(PoisonError::into_inner) = None;
+    }
+
+    #[cfg(test)]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.read().unwrap_or_else(PoisonError::into_inner).is_none()
+    }
+}
+
+impl Clone for SpatialCache {
+    fn clone(&self) -> Self {
+        SpatialCache(RwLock::new(self.0.read().unwrap_or_else(PoisonError::into_inner).clone()))
+    }
+}
+
+/// Returns the key of the fonts labels are measured with on the
+/// current thread.
+#[cfg(feature = "text")]
+fn current_font_key() -> u64 {
+    crate::FontLibrary::with_system(|library| library.get_state_key())
+}
+
+/// Without text support, labels are measured by estimation only.
+#[cfg(not(feature = "text"))]
+fn current_font_key() -> u64 {
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Generates `n` pseudo-random rectangles in a 1000 x 1000 square.
+    fn random_rects(n: usize) -> Vec<Rect> {
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = move || {
+            state = state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+            (state >> 33) as f64 / (1u64 << 31) as f64
+        };
+
+        (0..n)
+            .map(|_| {
+                let (x, y) = (next() * 1000.0, next() * 1000.0);
+
+                Rect::new(x, y, x + next() * 50.0, y + next() * 50.0)
+            })
+            .collect()
+    }
+
+    fn query_all(tree: &RTree<usize>, rect: Rect) -> Vec<usize> {
+        let mut result = Vec::new();
+
+        tree.query(|bbox| is_overlapping(bbox, &rect), |_, id| result.push(*id));
+        result.sort_unstable();
+
+        result
+    }
+
+    #[test]
+    fn query_matches_brute_force() {
+        let rects = random_rects(1000);
+        let tree = RTree::new(rects.iter().copied().enumerate().map(|(id, r)| (r, id)).collect());
+
+        for query in random_rects(50).iter().map(|r| r.inflate(100.0, 100.0)) {
+            let expected: Vec<_> = (0..rects.len())
+                .filter(|id| is_overlapping(&rects[*id], &query))
+                .collect();
+
+            assert_eq!(query_all(&tree, query), expected);
+        }
+
+        assert_eq!(query_all(&tree, Rect::new(-1e9, -1e9, 1e9, 1e9)).len(), rects.len());
+        assert!(query_all(&tree, Rect::new(2000.0, 2000.0, 3000.0, 3000.0)).is_empty());
+    }
+
+    #[test]
+    fn empty_tree() {
+        let tree = RTree::new(Vec::new());
+
+        assert!(query_all(&tree, Rect::new(-1e9, -1e9, 1e9, 1e9)).is_empty());
+    }
+
+    #[test]
+    fn touching_rects_overlap() {
+        let rect = Rect::new(0.0, 0.0, 10.0, 10.0);
+
+        assert!(is_overlapping(&rect, &Rect::new(10.0, 0.0, 20.0, 10.0)));
+        assert!(is_overlapping(&rect, &Rect::new(10.0, 10.0, 20.0, 20.0)));
+        assert!(is_overlapping(&rect, &Rect::new(5.0, 5.0, 5.0, 5.0)));
+        assert!(!is_overlapping(&rect, &Rect::new(10.5, 0.0, 20.0, 10.0)));
+    }
+
+    #[test]
+    fn index_query_in_output_coordinates() {
+        let entries = vec![
+            (Rect::new(0.0, 0.0, 10.0, 10.0), (2, Affine::IDENTITY)),
+            (Rect::new(50.0, 50.0, 60.0, 60.0), (0, Affine::scale(3.0))),
+            (Rect::new(5.0, 5.0, 15.0, 15.0), (1, Affine::IDENTITY)),
+        ];
+        let index = SpatialIndex::new(entries, Vec::new());
+        let rect = Rect::new(0.0, 0.0, 20.0, 20.0);
+
+        assert_eq!(index.get_max_scale(), 3.0);
+        assert_eq!(index.query(rect, Affine::IDENTITY), vec![1, 2]);
+        assert_eq!(index.query(rect, Affine::scale(0.25)), vec![0, 1, 2]);
+        assert!(index.query(rect, Affine::translate((100.0, 0.0))).is_empty());
+    }
+}
@@ -1,7 +1,8 @@
 use kurbo::{Point, Rect};
-use crate::{Crumb, Variation, Tweener, Font};
+use crate::{Crumb, Variation, VariationStack, Tweener, Font, ColorSpace};
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct StyleId(pub usize);
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
@@ -46,56 +47,6 @@ impl Color {
         Color::Rgba32((self.as_u32() & !0xff) | a)
     }
 
-    pub fn from_hsva(hue: f64, saturation: f64, value: f64, alpha: f64) -> Self {
-        let hue = if hue < 0.0 {
-            hue + 6.0
-        } else if hue >= 6.0 {
-            hue - 6.0
-        } else {
-            hue
-        };
-        let chroma = value * saturation;
-        let hue_trunc = hue.trunc();
-        let fract = (hue - hue_trunc) * chroma;
-        let bottom = value - chroma;
-        let red;
-        let green;
-        let blue;
-
-        if hue_trunc < 1.0 {
-            red = value;
-            blue = bottom;
-            green = bottom + fract;
-        } else if hue_trunc < 2.0 {
-            green = value;
-            blue = bottom;
-            red = value - fract;
-        } else if hue_trunc < 3.0 {
-            green = value;
-            red = bottom;
-            blue = bottom + fract;
-        } else if hue_trunc < 4.0 {
-            blue = value;
-            red = bottom;
-            green = value - fract;
-        } else if hue_trunc < 5.0 {
-            blue = value;
-            green = bottom;
-            red = bottom + fract;
-        } else {
-            red = value;
-            green = bottom;
-            blue = value - fract;
-        }
-
-        Color::rgba8(
-            red.trunc() as u8,
-            green.trunc() as u8,
-            blue.trunc() as u8,
-            alpha.trunc() as u8,
-        )
-    }
-
     #[inline]
     pub fn as_u32(self) -> u32 {
         match self {
@@ -117,50 +68,9 @@ impl Color {
         }
     }
 
-    /// _Hue_ should be `None` iff _saturation_ is zero (for any
-    /// _value_ of gray).
-    pub fn as_hsva(self) -> (Option<f64>, f64, f64, f64) {
-        let Rgba::<f64>(red, green, blue, alpha) = self.into();
-        let gb = green - blue;
-        let br = blue - red;
-        let rg = red - green;
-
-        if gb > 0.0 {
-            if rg > 0.0 {
-                // r > g > b
-                // chroma = red - blue
-                (Some(gb / -br), -br / red, red, alpha)
-            } else if br > 0.0 {
-                // g > b > r
-                // chroma = green - red
-                (Some(br / -rg + 2.0), -rg / green, green, alpha)
-            } else {
-                // r <= g > b <= r
-                // chroma = green - blue
-                (Some(br / gb + 2.0), gb / green, green, alpha)
-            }
-        } else if br > 0.0 {
-            if rg > 0.0 {
-                // b > r > g
-                // chroma = blue - green
-                (Some(rg / -gb + 4.0), -gb / blue, blue, alpha)
-            } else {
-                // g <= b > r <= g
-                // chroma = blue - red
-                (Some(rg / br + 4.0), br / blue, blue, alpha)
-            }
-        } else if rg > 0.0 {
-            // b <= r > g <= b
-            // chroma = red - green
-            (Some(gb / rg + 6.0), rg / red, red, alpha)
-        } else {
-            // r = g = b
-            (None, 0.0, red, alpha)
-        }
-    }
-
     pub const BLACK: Color = Color::rgb8(0, 0, 0);
     pub const WHITE: Color = Color::rgb8(255, 255, 255);
+    pub const TRANSPARENT: Color = Color::rgba8(0, 0, 0, 0);
 }
 
 pub struct Rgba<T>(pub T, pub T, pub T, pub T);
@@ -185,21 +95,108 @@ impl std::fmt::Debug for Color {
     }
 }
 
+/// Colors are serialized as CSS strings, see
+/// [`Color::to_css_string()`], and deserialized from any CSS notation
+/// accepted by [`Color::from_str()`](std::str::FromStr::from_str).
+#[cfg(feature = "serialize")]
+impl serde::Serialize for Color {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_css_string())
+    }
+}
+
+#[cfg(feature = "serialize")]
+impl<'de> serde::Deserialize<'de> for Color {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        let spec = String::deserialize(deserializer)?;
+
+        spec.parse().map_err(D::Error::custom)
+    }
+}
+
+/// The shape of open ends of stroked lines.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum LineCap {
+    #[default]
+    Butt,
+    Round,
+    Square,
+}
+
+impl LineCap {
+    /// Returns the name of this cap, as used by the SVG
+    /// `stroke-linecap` attribute.
+    pub fn as_css_name(&self) -> &'static str {
+        match self {
+            LineCap::Butt => "butt",
+            LineCap::Round => "round",
+            LineCap::Square => "square",
+        }
+    }
+}
+
+/// The shape of corners of stroked lines.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum LineJoin {
+    #[default]
+    Miter,
+    Round,
+    Bevel,
+}
+
+impl LineJoin {
+    /// Returns the name of this join, as used by the SVG
+    /// `stroke-linejoin` attribute.
+    pub fn as_css_name(&self) -> &'static str {
+        match self {
+            LineJoin::Miter => "miter",
+            LineJoin::Round => "round",
+            LineJoin::Bevel => "bevel",
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize), serde(default))]
 pub struct Stroke {
-    brush: Color,
-    width: f64,
+    brush:         Color,
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "Option::is_none"))]
+    gradient_name: Option<String>,
+    width:         f64,
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "Vec::is_empty"))]
+    dash_array:    Vec<f64>,
+    dash_offset:   f64,
+    cap:           LineCap,
+    join:          LineJoin,
+    miter_limit:   f64,
 }
 
 impl Default for Stroke {
     fn default() -> Self {
-        Stroke { brush: Color::BLACK, width: 1.0 }
+        Stroke::new()
     }
 }
 
 impl Stroke {
+    /// The default ratio of miter length to stroke width, beyond
+    /// which miter joins are beveled, as in SVG.
+    pub const DEFAULT_MITER_LIMIT: f64 = 4.0;
+
     pub const fn new() -> Self {
-        Stroke { brush: Color::BLACK, width: 1.0 }
+        Stroke {
+            brush:         Color::BLACK,
+            gradient_name: None,
+            width:         1.0,
+            dash_array:    Vec::new(),
+            dash_offset:   0.0,
+            cap:           LineCap::Butt,
+            join:          LineJoin::Miter,
+            miter_limit:   Self::DEFAULT_MITER_LIMIT,
+        }
     }
 
     pub fn with_brush(mut self, brush: Color) -> Self {
@@ -207,21 +204,92 @@ impl Stroke {
         self
     }
 
+    /// Paints this stroke with a gradient of the given `name`,
+    /// defined in a [`Theme`](crate::Theme), instead of the brush.
+    ///
+    /// Note: gradients are mapped onto the bounding box of a crumb,
+    /// hence they aren't painted on horizontal or vertical lines.
+    pub fn with_named_gradient<S: AsRef<str>>(mut self, name: S) -> Self {
+        self.set_named_gradient(name);
+        self
+    }
+
+    /// Sets the width of the stroke, given in coordinates of a crumb,
+    /// i.e. scaled, together with the dash pattern, by the scale
+    /// factor of the crumb's effective transformation.
     pub fn with_width(mut self, width: f64) -> Self {
         self.set_width(width);
         self
     }
 
+    /// Alternating lengths of dashes and gaps, starting at `offset`
+    /// into the pattern.  An empty `dash_array` gives a solid line.
+    pub fn with_dashes<I>(mut self, dash_array: I, offset: f64) -> Self
+    where
+        I: IntoIterator<Item = f64>,
+    {
+        self.set_dashes(dash_array, offset);
+        self
+    }
+
+    pub fn with_cap(mut self, cap: LineCap) -> Self {
+        self.set_cap(cap);
+        self
+    }
+
+    pub fn with_join(mut self, join: LineJoin) -> Self {
+        self.set_join(join);
+        self
+    }
+
+    pub fn with_miter_limit(mut self, miter_limit: f64) -> Self {
+        self.set_miter_limit(miter_limit);
+        self
+    }
+
     #[inline]
     pub fn set_brush(&mut self, brush: Color) {
         self.brush = brush;
     }
 
+    #[inline]
+    pub fn set_named_gradient<S: AsRef<str>>(&mut self, name: S) {
+        self.gradient_name = Some(name.as_ref().into());
+    }
+
+    #[inline]
+    pub fn clear_gradient(&mut self) {
+        self.gradient_name = None;
+    }
+
     #[inline]
     pub fn set_width(&mut self, width: f64) {
         self.width = width;
     }
 
+    pub fn set_dashes<I>(&mut self, dash_array: I, offset: f64)
+    where
+        I: IntoIterator<Item = f64>,
+    {
+        self.dash_array = dash_array.into_iter().collect();
+        self.dash_offset = offset;
+    }
+
+    #[inline]
+    pub fn set_cap(&mut self, cap: LineCap) {
+        self.cap = cap;
+    }
+
+    #[inline]
+    pub fn set_join(&mut self, join: LineJoin) {
+        self.join = join;
+    }
+
+    #[inline]
+    pub fn set_miter_limit(&mut self, miter_limit: f64) {
+        self.miter_limit = miter_limit;
+    }
+
     #[inline]
     pub fn get_brush(&self) -> &Color {
         &self.brush
@@ -232,17 +300,71 @@ impl Stroke {
         &mut self.brush
     }
 
+    #[inline]
+    pub fn get_gradient_name(&self) -> Option<&str> {
+        self.gradient_name.as_deref()
+    }
+
     #[inline]
     pub fn get_width(&self) -> f64 {
         self.width
     }
+
+    #[inline]
+    pub fn get_dash_array(&self) -> &[f64] {
+        self.dash_array.as_slice()
+    }
+
+    #[inline]
+    pub fn get_dash_offset(&self) -> f64 {
+        self.dash_offset
+    }
+
+    /// Returns `true` if this stroke has a dash pattern.
+    #[inline]
+    pub fn is_dashed(&self) -> bool {
+        !self.dash_array.is_empty()
+    }
+
+    #[inline]
+    pub fn get_cap(&self) -> LineCap {
+        self.cap
+    }
+
+    #[inline]
+    pub fn get_join(&self) -> LineJoin {
+        self.join
+    }
+
+    #[inline]
+    pub fn get_miter_limit(&self) -> f64 {
+        self.miter_limit
+    }
+
+    /// Returns a copy of this stroke with its color replaced by
+    /// `recolor`, and its gradient's name replaced by `rename`.
+    pub(crate) fn map_colors<F, G>(&self, recolor: &F, rename: &G) -> Self
+    where
+        F: Fn(Color) -> Color,
+        G: Fn(&str) -> String,
+    {
+        Stroke {
+            brush: recolor(self.brush),
+            gradient_name: self.gradient_name.as_deref().map(rename),
+            ..self.clone()
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub enum Fill {
     Color(Color),
     Linear(String),
     Radial(String),
+    /// A [`Hatch`] of the given name, defined in a
+    /// [`Theme`](crate::Theme).
+    Hatch(String),
 }
 
 impl Default for Fill {
@@ -251,7 +373,26 @@ impl Default for Fill {
     }
 }
 
+impl Fill {
+    /// Returns a copy of this fill with its color replaced by
+    /// `recolor`, or the name of its gradient or hatch replaced by
+    /// `rename`.
+    pub(crate) fn map_colors<F, G>(&self, recolor: &F, rename: &G) -> Self
+    where
+        F: Fn(Color) -> Color,
+        G: Fn(&str) -> String,
+    {
+        match self {
+            Fill::Color(color) => Fill::Color(recolor(*color)),
+            Fill::Linear(name) => Fill::Linear(rename(name)),
+            Fill::Radial(name) => Fill::Radial(rename(name)),
+            Fill::Hatch(name) => Fill::Hatch(rename(name)),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct UnitPoint {
     u: f64,
     v: f64,
@@ -284,6 +425,7 @@ pub enum ScaleMode {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct GradientStop {
     pub pos:   f32,
     pub color: Color,
@@ -335,15 +477,131 @@ impl<'a> GradientStops for &'a [Color] {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub enum Gradient {
     Linear(UnitPoint, UnitPoint, Vec<GradientStop>),
     Radial(f64, Vec<GradientStop>),
 }
 
-#[derive(Clone, Copy, Debug)]
+impl Gradient {
+    #[inline]
+    pub fn get_stops(&self) -> &[GradientStop] {
+        match self {
+            Gradient::Linear(_, _, stops) | Gradient::Radial(_, stops) => stops.as_slice(),
+        }
+    }
+
+    /// Returns a copy of this gradient with colors of all stops
+    /// replaced by `recolor`.
+    pub(crate) fn map_colors<F: Fn(Color) -> Color>(&self, recolor: &F) -> Self {
+        let mut result = self.clone();
+
+        match result {
+            Gradient::Linear(_, _, ref mut stops) | Gradient::Radial(_, ref mut stops) => {
+                for stop in stops.iter_mut() {
+                    stop.color = recolor(stop.color);
+                }
+            }
+        }
+
+        result
+    }
+}
+
+/// A pattern of parallel lines, painted over an optional background,
+/// see [`Fill::Hatch`].
+///
+/// Line width and spacing (the distance between centers of adjacent
+/// lines) are given in scene units.  The angle, in degrees, is
+/// measured clockwise from the horizontal direction, and hatches of
+/// all crumbs are aligned to the output's origin.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize), serde(default))]
+pub struct Hatch {
+    brush:      Color,
+    background: Option<Color>,
+    width:      f64,
+    spacing:    f64,
+    angle:      f64,
+}
+
+impl Default for Hatch {
+    fn default() -> Self {
+        Hatch::new()
+    }
+}
+
+impl Hatch {
+    pub const fn new() -> Self {
+        Hatch { brush: Color::BLACK, background: None, width: 1.0, spacing: 4.0, angle: 45.0 }
+    }
+
+    pub fn with_brush(mut self, brush: Color) -> Self {
+        self.brush = brush;
+        self
+    }
+
+    pub fn with_background(mut self, background: Color) -> Self {
+        self.background = Some(background);
+        self
+    }
+
+    pub fn with_width(mut self, width: f64) -> Self {
+        self.width = width;
+        self
+    }
+
+    pub fn with_spacing(mut self, spacing: f64) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    pub fn with_angle(mut self, angle: f64) -> Self {
+        self.angle = angle;
+        self
+    }
+
+    #[inline]
+    pub fn get_brush(&self) -> &Color {
+        &self.brush
+    }
+
+    #[inline]
+    pub fn get_background(&self) -> Option<&Color> {
+        self.background.as_ref()
+    }
+
+    #[inline]
+    pub fn get_width(&self) -> f64 {
+        self.width
+    }
+
+    #[inline]
+    pub fn get_spacing(&self) -> f64 {
+        self.spacing
+    }
+
+    #[inline]
+    pub fn get_angle(&self) -> f64 {
+        self.angle
+    }
+
+    /// Returns a copy of this hatch with its colors replaced by
+    /// `recolor`.
+    pub(crate) fn map_colors<F: Fn(Color) -> Color>(&self, recolor: &F) -> Self {
+        Hatch {
+            brush: recolor(self.brush),
+            background: self.background.map(recolor),
+            ..self.clone()
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct MarkerId(pub usize);
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct Marker {
     width:      f64,
     height:     f64,
@@ -352,8 +610,8 @@ pub struct Marker {
     orient:     Option<f64>,
     crumb:      Crumb,
     style_name: Option<String>,
-    // FIXME resolve style name to StyleId: either introduce builder
-    // pattern for Theme, or add update to all relevant `with_` methods
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    style_id:   Option<StyleId>,
 }
 
 impl Marker {
@@ -366,6 +624,7 @@ impl Marker {
             orient: None,
             crumb,
             style_name: None,
+            style_id: None,
         }
     }
 
@@ -411,6 +670,16 @@ impl Marker {
         self.orient
     }
 
+    /// Returns the largest distance between the reference point of
+    /// this marker and any corner of its box, i.e. the reach of the
+    /// marker in any orientation.
+    pub fn get_extent(&self) -> f64 {
+        let dx = self.refx.abs().max((self.width - self.refx).abs());
+        let dy = self.refy.abs().max((self.height - self.refy).abs());
+
+        dx.hypot(dy)
+    }
+
     #[inline]
     pub fn get_crumb(&self) -> &Crumb {
         &self.crumb
@@ -420,20 +689,81 @@ impl Marker {
     pub fn get_style_name(&self) -> Option<&str> {
         self.style_name.as_deref()
     }
+
+    /// Returns the id of this marker's style, resolved from its name
+    /// by [`Theme::build()`](crate::Theme::build).
+    #[inline]
+    pub fn get_style_id(&self) -> Option<StyleId> {
+        self.style_id
+    }
+
+    #[inline]
+    pub(crate) fn set_style_id(&mut self, style_id: Option<StyleId>) {
+        self.style_id = style_id;
+    }
 }
 
 #[derive(Clone, Default, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize), serde(default))]
 pub(crate) struct MarkerSuit {
     start_name: Option<String>,
     mid_name:   Option<String>,
     end_name:   Option<String>,
-    // FIXME resolve marker names to ids: either introduce builder
-    // pattern for Theme, or add update to all relevant `with_` methods
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    start_id:   Option<MarkerId>,
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    mid_id:     Option<MarkerId>,
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    end_id:     Option<MarkerId>,
 }
 
 impl MarkerSuit {
     pub(crate) const fn new() -> Self {
-        MarkerSuit { start_name: None, mid_name: None, end_name: None }
+        MarkerSuit {
+            start_name: None,
+            mid_name:   None,
+            end_name:   None,
+            start_id:   None,
+            mid_id:     None,
+            end_id:     None,
+        }
+    }
+
+    /// Resolves marker names to ids with a `resolver`.
+    pub(crate) fn resolve<F>(&mut self, resolver: F)
+    where
+        F: Fn(&str) -> Option<MarkerId>,
+    {
+        self.start_id = self.start_name.as_deref().and_then(&resolver);
+        self.mid_id = self.mid_name.as_deref().and_then(&resolver);
+        self.end_id = self.end_name.as_deref().and_then(&resolver);
+    }
+
+    /// Lists the names of start, mid and end markers, in this order.
+    pub(crate) fn get_names(&self) -> impl Iterator<Item = &str> {
+        self.start_name
+            .iter()
+            .chain(self.mid_name.iter())
+            .chain(self.end_name.iter())
+            .map(String::as_str)
+    }
+
+    pub(crate) fn get_start_id(&self) -> Option<MarkerId> {
+        self.start_id
+    }
+
+    pub(crate) fn get_mid_id(&self) -> Option<MarkerId> {
+        self.mid_id
+    }
+
+    pub(crate) fn get_end_id(&self) -> Option<MarkerId> {
+        self.end_id
+    }
+
+    #[cfg(feature = "serialize")]
+    #[inline]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.start_name.is_none() && self.mid_name.is_none() && self.end_name.is_none()
     }
 
     pub(crate) fn get_start_name(&self) -> Option<&str> {
@@ -449,21 +779,52 @@ impl MarkerSuit {
     }
 }
 
+/// Properties of a [`Style`] copied from its base style, see
+/// [`Style::extends()`].
+#[derive(Clone, Copy, Default, Debug)]
+struct Inherited {
+    stroke:       bool,
+    fill:         bool,
+    start_marker: bool,
+    mid_marker:   bool,
+    end_marker:   bool,
+    font:         bool,
+}
+
+impl Inherited {
+    const NONE: Self = Inherited {
+        stroke:       false,
+        fill:         false,
+        start_marker: false,
+        mid_marker:   false,
+        end_marker:   false,
+        font:         false,
+    };
+}
+
 #[derive(Clone, Default, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize), serde(default))]
 pub struct Style {
+    extends:        Option<String>,
     stroke_name:    Option<String>,
     fill_name:      Option<String>,
     stroke:         Option<Stroke>,
     fill:           Option<Fill>,
+    #[cfg_attr(feature = "serialize", serde(skip))]
     stroke_tweener: Option<Tweener<Stroke>>,
+    #[cfg_attr(feature = "serialize", serde(skip))]
     fill_tweener:   Option<Tweener<Fill>>,
+    #[cfg_attr(feature = "serialize", serde(skip_serializing_if = "MarkerSuit::is_empty"))]
     markers:        MarkerSuit,
     font:           Option<Font>,
+    #[cfg_attr(feature = "serialize", serde(skip))]
+    inherited:      Inherited,
 }
 
 impl Style {
     pub const fn new() -> Self {
         Style {
+            extends:        None,
             stroke_name:    None,
             fill_name:      None,
             stroke:         None,
@@ -472,9 +833,21 @@ impl Style {
             fill_tweener:   None,
             markers:        MarkerSuit::new(),
             font:           None,
+            inherited:      Inherited::NONE,
         }
     }
 
+    /// Makes this style inherit, from the style of a given `name`,
+    /// all properties not specified in this style.
+    ///
+    /// Strokes, fills, fonts and each of the start, mid and end
+    /// markers are inherited separately.  Inheritance is resolved
+    /// by [`Theme::build()`](crate::Theme::build).
+    pub fn extends<S: AsRef<str>>(mut self, name: S) -> Self {
+        self.extends = Some(name.as_ref().into());
+        self
+    }
+
     pub fn with_stroke(mut self, stroke: Stroke) -> Self {
         self.set_stroke(stroke);
         self
@@ -515,6 +888,99 @@ impl Style {
         self
     }
 
+    /// Returns a copy of this style as declared, i.e. without
+    /// inherited properties, without strokes and fills resolved from
+    /// their names, and without tweening.
+    #[cfg(feature = "serialize")]
+    pub(crate) fn to_declared(&self) -> Self {
+        let mut result = self.clone();
+
+        result.clear_inherited();
+
+        Style {
+            stroke: if result.stroke_name.is_some() { None } else { result.stroke.clone() },
+            fill: if result.fill_name.is_some() { None } else { result.fill.clone() },
+            stroke_tweener: None,
+            fill_tweener: None,
+            ..result
+        }
+    }
+
+    /// Copies from a `base` style all properties not specified in
+    /// this style.
+    pub(crate) fn inherit_from(&mut self, base: &Style) {
+        if self.stroke_name.is_none() && self.stroke.is_none() {
+            self.stroke_name = base.stroke_name.clone();
+            self.stroke = base.stroke.clone();
+            self.inherited.stroke = true;
+        }
+
+        if self.fill_name.is_none() && self.fill.is_none() {
+            self.fill_name = base.fill_name.clone();
+            self.fill = base.fill.clone();
+            self.inherited.fill = true;
+        }
+
+        if self.markers.start_name.is_none() {
+            self.markers.start_name = base.markers.start_name.clone();
+            self.inherited.start_marker = true;
+        }
+
+        if self.markers.mid_name.is_none() {
+            self.markers.mid_name = base.markers.mid_name.clone();
+            self.inherited.mid_marker = true;
+        }
+
+        if self.markers.end_name.is_none() {
+            self.markers.end_name = base.markers.end_name.clone();
+            self.inherited.end_marker = true;
+        }
+
+        if self.font.is_none() {
+            self.font = base.font.clone();
+            self.inherited.font = true;
+        }
+    }
+
+    /// Removes all properties copied by [`Style::inherit_from()`].
+    pub(crate) fn clear_inherited(&mut self) {
+        if self.inherited.stroke {
+            self.stroke_name = None;
+            self.stroke = None;
+            self.stroke_tweener = None;
+        }
+
+        if self.inherited.fill {
+            self.fill_name = None;
+            self.fill = None;
+            self.fill_tweener = None;
+        }
+
+        if self.inherited.start_marker {
+            self.markers.start_name = None;
+        }
+
+        if self.inherited.mid_marker {
+            self.markers.mid_name = None;
+        }
+
+        if self.inherited.end_marker {
+            self.markers.end_name = None;
+        }
+
+        if self.inherited.font {
+            self.font = None;
+        }
+
+        self.inherited = Inherited::NONE;
+    }
+
+    /// Returns the name of the base style, see [`Style::extends()`].
+    #[inline]
+    pub fn get_base_name(&self) -> Option<&str> {
+        self.extends.as_deref()
+    }
+
     pub fn resolve_initially(&mut self, variation: &Variation) {
         if let Some(stroke) =
             self.stroke_name.as_ref().and_then(|n| variation.get_stroke_by_name(n))
@@ -545,7 +1011,12 @@ impl Style {
         }
     }
 
-    pub fn start_original_resolution(&mut self, variation: &Variation, max_subdivision: usize) {
+    pub fn start_original_resolution(
+        &mut self,
+        variation: &Variation,
+        max_subdivision: usize,
+        color_space: ColorSpace,
+    ) {
         self.stroke_tweener = None;
         self.fill_tweener = None;
 
@@ -553,21 +1024,34 @@ impl Style {
             self.stroke_name.as_ref().and_then(|n| variation.get_stroke_by_name(n))
         {
             if let Some(ref stroke_from) = self.stroke {
-                self.stroke_tweener =
-                    Some(Tweener::new(stroke_from.clone(), stroke_to.clone(), max_subdivision));
+                self.stroke_tweener = Some(Tweener::new_in_space(
+                    stroke_from.clone(),
+                    stroke_to.clone(),
+                    max_subdivision,
+                    color_space,
+                ));
             }
         }
 
         if let Some(fill_to) = self.fill_name.as_ref().and_then(|n| variation.get_fill_by_name(n)) {
             if let Some(ref fill_from) = self.fill {
-                self.fill_tweener =
-                    Some(Tweener::new(fill_from.clone(), fill_to.clone(), max_subdivision));
+                self.fill_tweener = Some(Tweener::new_in_space(
+                    fill_from.clone(),
+                    fill_to.clone(),
+                    max_subdivision,
+                    color_space,
+                ));
             }
         }
     }
 
-    pub fn start_resolution<V, I>(&mut self, variation: &Variation, path: I, max_subdivision: usize)
-    where
+    pub fn start_resolution<V, I>(
+        &mut self,
+        variation: &Variation,
+        path: I,
+        max_subdivision: usize,
+        color_space: ColorSpace,
+    ) where
         V: AsRef<str>,
         I: IntoIterator<Item = V> + Clone,
     {
@@ -578,8 +1062,12 @@ impl Style {
             self.stroke_name.as_ref().and_then(|n| variation.get_stroke_by_path(path.clone(), n))
         {
             if let Some(ref stroke_from) = self.stroke {
-                self.stroke_tweener =
-                    Some(Tweener::new(stroke_from.clone(), stroke_to.clone(), max_subdivision));
+                self.stroke_tweener = Some(Tweener::new_in_space(
+                    stroke_from.clone(),
+                    stroke_to.clone(),
+                    max_subdivision,
+                    color_space,
+                ));
             }
         }
 
@@ -587,8 +1075,72 @@ impl Style {
             self.fill_name.as_ref().and_then(|n| variation.get_fill_by_path(path.clone(), n))
         {
             if let Some(ref fill_from) = self.fill {
-                self.fill_tweener =
-                    Some(Tweener::new(fill_from.clone(), fill_to.clone(), max_subdivision));
+                self.fill_tweener = Some(Tweener::new_in_space(
+                    fill_from.clone(),
+                    fill_to.clone(),
+                    max_subdivision,
+                    color_space,
+                ));
+            }
+        }
+    }
+
+    pub fn resolve_by_stack(&mut self, variation: &Variation, stack: &VariationStack) {
+        if let Some(stroke) =
+            self.stroke_name.as_ref().and_then(|n| variation.get_stroke_by_stack(stack, n))
+        {
+            self.stroke = Some(stroke.clone());
+        }
+
+        if let Some(fill) =
+            self.fill_name.as_ref().and_then(|n| variation.get_fill_by_stack(stack, n))
+        {
+            self.fill = Some(fill.clone());
+        }
+    }
+
+    /// Starts tweening the stroke and fill of this style to those
+    /// resolved in a `to` stack of variations, either from those
+    /// resolved in a `from` stack, or, if `from` is `None`, from
+    /// their current values.
+    pub fn start_resolution_by_stack(
+        &mut self,
+        variation: &Variation,
+        from: Option<&VariationStack>,
+        to: &VariationStack,
+        max_subdivision: usize,
+        color_space: ColorSpace,
+    ) {
+        self.stroke_tweener = None;
+        self.fill_tweener = None;
+
+        if let Some(from) = from {
+            self.resolve_by_stack(variation, from);
+        }
+
+        if let Some(stroke_to) =
+            self.stroke_name.as_ref().and_then(|n| variation.get_stroke_by_stack(to, n))
+        {
+            if let Some(ref stroke_from) = self.stroke {
+                self.stroke_tweener = Some(Tweener::new_in_space(
+                    stroke_from.clone(),
+                    stroke_to.clone(),
+                    max_subdivision,
+                    color_space,
+                ));
+            }
+        }
+
+        if let Some(fill_to) =
+            self.fill_name.as_ref().and_then(|n| variation.get_fill_by_stack(to, n))
+        {
+            if let Some(ref fill_from) = self.fill {
+                self.fill_tweener = Some(Tweener::new_in_space(
+                    fill_from.clone(),
+                    fill_to.clone(),
+                    max_subdivision,
+                    color_space,
+                ));
             }
         }
     }
@@ -616,6 +1168,7 @@ impl Style {
     #[inline]
     pub fn set_stroke(&mut self, stroke: Stroke) {
         self.stroke = Some(stroke);
+        self.inherited.stroke = false;
     }
 
     #[inline]
@@ -626,6 +1179,7 @@ impl Style {
     #[inline]
     pub fn set_fill(&mut self, fill: Fill) {
         self.fill = Some(fill);
+        self.inherited.fill = false;
     }
 
     #[inline]
@@ -674,9 +1228,25 @@ impl Style {
         &self.markers
     }
 
+    #[inline]
+    pub(crate) fn get_markers_mut(&mut self) -> &mut MarkerSuit {
+        &mut self.markers
+    }
+
+    #[inline]
+    pub fn get_stroke_name(&self) -> Option<&str> {
+        self.stroke_name.as_deref()
+    }
+
+    #[inline]
+    pub fn get_fill_name(&self) -> Option<&str> {
+        self.fill_name.as_deref()
+    }
+
     #[inline]
     pub(crate) fn set_font(&mut self, font: Font) {
         self.font = Some(font);
+        self.inherited.font = false;
     }
 
     #[inline]
@@ -1,20 +1,162 @@
-use kurbo::{Point, TranslateScale};
-use crate::{Theme, Style, Font, PreprocessWithStyle, VisError};
+use std::borrow::Cow;
+use kurbo::{Point, Rect, RoundedRect, Affine};
+use crate::{Theme, Style, StyleId, Font, PreprocessWithStyle, VisError};
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) enum Anchor {
     Start,
     Middle,
     End,
 }
 
-#[derive(Clone, Debug)]
+impl Anchor {
+    /// Returns the position of an anchor within a line (or a box) of
+    /// a unit width.
+    #[inline]
+    fn as_fraction(&self) -> f64 {
+        match self {
+            Anchor::Start => 0.0,
+            Anchor::Middle => 0.5,
+            Anchor::End => 1.0,
+        }
+    }
+}
+
+/// A shift of the baseline of a span of a [`TextLabel`], relative to
+/// the baseline of its parent.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
+pub enum BaselineShift {
+    /// Lowers a span to the subscript position of the parent font.
+    Sub,
+    /// Raises a span to the superscript position of the parent font.
+    Super,
+    /// Raises a span by a length (or lowers it, if negative).
+    Length(f64),
+}
+
+impl BaselineShift {
+    // Subscript and superscript offsets, as fractions of font size,
+    // used for fonts without such metrics.
+    const ESTIMATED_SUB: f64 = 0.2;
+    const ESTIMATED_SUPER: f64 = 0.33;
+
+    /// Returns the distance, by which a baseline is raised, assuming
+    /// typical subscript and superscript offsets of a font of a given
+    /// size.
+    pub fn estimate(&self, font_size: f64) -> f64 {
+        match self {
+            BaselineShift::Sub => -font_size * Self::ESTIMATED_SUB,
+            BaselineShift::Super => font_size * Self::ESTIMATED_SUPER,
+            BaselineShift::Length(length) => *length,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) enum Item {
     Text(String),
     Span(TextLabel),
 }
 
-#[derive(Clone, Debug)]
+/// A box painted behind the lines of a [`TextLabel`], e.g. the frame
+/// of a callout annotation.
+///
+/// A box without a style isn't painted, but still contributes to the
+/// bounds of its label.
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize), serde(default))]
+pub struct TextBox {
+    style_id: Option<StyleId>,
+    padding:  f64,
+    radius:   f64,
+}
+
+impl TextBox {
+    pub fn new() -> Self {
+        TextBox::default()
+    }
+
+    pub fn with_style(mut self, style_id: StyleId) -> Self {
+        self.style_id = Some(style_id);
+        self
+    }
+
+    pub fn with_padding(mut self, padding: f64) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    pub fn with_radius(mut self, radius: f64) -> Self {
+        self.radius = radius;
+        self
+    }
+
+    #[inline]
+    pub fn get_style_id(&self) -> Option<StyleId> {
+        self.style_id
+    }
+
+    #[inline]
+    pub fn get_padding(&self) -> f64 {
+        self.padding
+    }
+
+    #[inline]
+    pub fn get_radius(&self) -> f64 {
+        self.radius
+    }
+
+    /// Returns the frame of this box enclosing a `rect`.
+    #[inline]
+    pub fn get_frame(&self, rect: Rect) -> RoundedRect {
+        RoundedRect::from_rect(rect.inflate(self.padding, self.padding), self.radius)
+    }
+}
+
+/// Extents of a [`TextLabel`], see [`TextLabel::get_metrics()`].
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TextMetrics {
+    pub(crate) advance: f64,
+    pub(crate) ascent:  f64,
+    pub(crate) descent: f64,
+    pub(crate) bounds:  Rect,
+}
+
+impl TextMetrics {
+    /// Returns the total advance of the text starting at the origin
+    /// of a label, i.e. excluding spans positioned on their own.
+    #[inline]
+    pub fn get_advance(&self) -> f64 {
+        self.advance
+    }
+
+    /// Returns the largest distance from a baseline to the top of a
+    /// line of the label, as a positive number.
+    #[inline]
+    pub fn get_ascent(&self) -> f64 {
+        self.ascent
+    }
+
+    /// Returns the largest distance from a baseline to the bottom of
+    /// a line of the label, as a positive number.
+    #[inline]
+    pub fn get_descent(&self) -> f64 {
+        self.descent
+    }
+
+    /// Returns the box enclosing all lines of a label, including its
+    /// spans, in label coordinates.
+    #[inline]
+    pub fn get_bounds(&self) -> Rect {
+        self.bounds
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Serialize, serde::Deserialize))]
 pub struct TextLabel {
     origin:           Option<Point>,
     dx:               Vec<f64>,
@@ -25,11 +167,39 @@ pub struct TextLabel {
     is_root:          bool,
     font_is_explicit: bool,
     font_size:        Option<f64>,
+    #[cfg_attr(feature = "serialize", serde(default))]
+    max_width:        Option<f64>,
+    #[cfg_attr(feature = "serialize", serde(default))]
+    line_height:      Option<f64>,
+    #[cfg_attr(feature = "serialize", serde(default))]
+    align:            Option<Anchor>,
+    #[cfg_attr(feature = "serialize", serde(default))]
+    text_box:         Option<TextBox>,
+    #[cfg_attr(feature = "serialize", serde(default))]
+    style_id:         Option<StyleId>,
+    #[cfg_attr(feature = "serialize", serde(default))]
+    baseline_shift:   Option<BaselineShift>,
+    #[cfg_attr(feature = "serialize", serde(default))]
+    letter_spacing:   Option<f64>,
 }
 
+/// A `'static` copy of [`TextLabel::DEFAULT_FONT`], which may be
+/// borrowed for as long as labels are laid out.
+static DEFAULT_FONT: Font = TextLabel::DEFAULT_FONT;
+
 impl TextLabel {
     pub const DEFAULT_FONT: Font = Font::new_sans_serif();
 
+    /// Default distance between baselines of consecutive lines, as a
+    /// fraction of font size.
+    pub const DEFAULT_LINE_HEIGHT: f64 = 1.2;
+
+    // Average glyph advance, ascent and descent, as fractions of font
+    // size, used for estimating label extents without font metrics.
+    const ESTIMATED_ADVANCE: f64 = 0.6;
+    const ESTIMATED_ASCENT: f64 = 0.8;
+    const ESTIMATED_DESCENT: f64 = 0.2;
+
     pub fn new() -> Self {
         TextLabel {
             origin:           None,
@@ -41,6 +211,13 @@ impl TextLabel {
             is_root:          true,
             font_is_explicit: false,
             font_size:        None,
+            max_width:        None,
+            line_height:      None,
+            align:            None,
+            text_box:         None,
+            style_id:         None,
+            baseline_shift:   None,
+            letter_spacing:   None,
         }
     }
 
@@ -101,6 +278,61 @@ impl TextLabel {
         self
     }
 
+    pub fn with_max_width(mut self, max_width: f64) -> Self {
+        self.set_max_width(max_width);
+        self
+    }
+
+    pub fn with_line_height(mut self, line_height: f64) -> Self {
+        self.set_line_height(line_height);
+        self
+    }
+
+    pub fn with_start_alignment(mut self) -> Self {
+        self.set_start_alignment();
+        self
+    }
+
+    pub fn with_middle_alignment(mut self) -> Self {
+        self.set_middle_alignment();
+        self
+    }
+
+    pub fn with_end_alignment(mut self) -> Self {
+        self.set_end_alignment();
+        self
+    }
+
+    pub fn with_box(mut self, text_box: TextBox) -> Self {
+        self.set_box(text_box);
+        self
+    }
+
+    pub fn with_style(mut self, style_id: StyleId) -> Self {
+        self.set_style(style_id);
+        self
+    }
+
+    pub fn with_baseline_shift(mut self, shift: BaselineShift) -> Self {
+        self.set_baseline_shift(shift);
+        self
+    }
+
+    pub fn with_subscript(mut self) -> Self {
+        self.set_baseline_shift(BaselineShift::Sub);
+        self
+    }
+
+    pub fn with_superscript(mut self) -> Self {
+        self.set_baseline_shift(BaselineShift::Super);
+        self
+    }
+
+    pub fn with_letter_spacing(mut self, letter_spacing: f64) -> Self {
+        self.set_letter_spacing(letter_spacing);
+        self
+    }
+
     pub fn append_text<S: AsRef<str>>(&mut self, body: S) {
         self.body.push(Item::Text(body.as_ref().to_string()));
     }
@@ -152,6 +384,56 @@ impl TextLabel {
         self.font_size = Some(font_size);
     }
 
+    /// Sets the width, at which lines of this label are wrapped.
+    pub fn set_max_width(&mut self, max_width: f64) {
+        self.max_width = Some(max_width);
+    }
+
+    /// Sets the distance between baselines of consecutive lines, as
+    /// a fraction of font size.
+    pub fn set_line_height(&mut self, line_height: f64) {
+        self.line_height = Some(line_height);
+    }
+
+    /// Aligns lines to the start of the box of this label.  Lines are
+    /// aligned according to the anchor of a label, by default.
+    pub fn set_start_alignment(&mut self) {
+        self.align = Some(Anchor::Start);
+    }
+
+    /// Centers lines in the box of this label.
+    pub fn set_middle_alignment(&mut self) {
+        self.align = Some(Anchor::Middle);
+    }
+
+    /// Aligns lines to the end of the box of this label.
+    pub fn set_end_alignment(&mut self) {
+        self.align = Some(Anchor::End);
+    }
+
+    pub fn set_box(&mut self, text_box: TextBox) {
+        self.text_box = Some(text_box);
+    }
+
+    /// Sets the style, which paints this label (or span) and its
+    /// spans, unless they have styles of their own.  Only the fill of
+    /// a style is used, and fonts are set independently.
+    pub fn set_style(&mut self, style_id: StyleId) {
+        self.style_id = Some(style_id);
+    }
+
+    /// Sets the shift of the baseline of this span.  Shifts of root
+    /// labels are ignored.
+    pub fn set_baseline_shift(&mut self, shift: BaselineShift) {
+        self.baseline_shift = Some(shift);
+    }
+
+    /// Sets the extra space added after each character of this label
+    /// (or span) and its spans, unless they set their own.
+    pub fn set_letter_spacing(&mut self, letter_spacing: f64) {
+        self.letter_spacing = Some(letter_spacing);
+    }
+
     #[inline]
     pub fn is_root(&self) -> bool {
         self.is_root
@@ -177,6 +459,36 @@ impl TextLabel {
         self.anchor
     }
 
+    #[inline]
+    pub fn get_max_width(&self) -> Option<f64> {
+        self.max_width
+    }
+
+    #[inline]
+    pub fn get_line_height(&self) -> f64 {
+        self.line_height.unwrap_or(Self::DEFAULT_LINE_HEIGHT)
+    }
+
+    #[inline]
+    pub fn get_box(&self) -> Option<&TextBox> {
+        self.text_box.as_ref()
+    }
+
+    #[inline]
+    pub fn get_style_id(&self) -> Option<StyleId> {
+        self.style_id
+    }
+
+    #[inline]
+    pub fn get_baseline_shift(&self) -> Option<BaselineShift> {
+        self.baseline_shift
+    }
+
+    #[inline]
+    pub fn get_letter_spacing(&self) -> Option<f64> {
+        self.letter_spacing
+    }
+
     #[inline]
     pub(crate) fn get_body(&self) -> &[Item] {
         self.body.as_slice()
@@ -192,6 +504,12 @@ impl TextLabel {
         self.font.as_ref()
     }
 
+    #[cfg(feature = "text")]
+    #[inline]
+    pub(crate) fn has_explicit_font(&self) -> bool {
+        self.font_is_explicit
+    }
+
     #[inline]
     pub fn get_font_size(&self) -> Option<f64> {
         if let Some(ref font) = self.font {
@@ -201,15 +519,306 @@ impl TextLabel {
         }
     }
 
+    /// Returns the font size set for this label, regardless of its
+    /// font.
+    #[cfg(feature = "text")]
+    #[inline]
+    pub(crate) fn get_own_font_size(&self) -> Option<f64> {
+        self.font_size
+    }
+
+    /// Returns the metrics of this label, measured with the fonts of
+    /// the shared [`FontLibrary`](crate::FontLibrary), or estimated,
+    /// if no font face matches, or text support isn't enabled.
+    ///
+    /// The label is set in its own font, if any, or else in
+    /// [`TextLabel::DEFAULT_FONT`], hence metrics are accurate only
+    /// for labels with resolved fonts, see
+    /// [`TextLabel::resolve_font()`].  Lines are broken as by
+    /// [`TextLabel::break_lines()`], and bounds include the frame of
+    /// the box, if any.
+    pub fn get_metrics(&self) -> TextMetrics {
+        let (lines, box_extent) = self.lay_out_lines(Self::default_font());
+        let mut metrics = lines.get_line_metrics(Self::default_font());
+
+        if let Some(frame) = self.get_frame(&metrics, box_extent) {
+            metrics.bounds = metrics.bounds.union(frame.rect());
+        }
+
+        metrics
+    }
+
+    /// Returns the metrics of this label, treated as a single line
+    /// set in its own font, if any, or else in `default_font`.
+    #[cfg_attr(not(feature = "text"), allow(unused_variables))]
+    fn get_line_metrics(&self, default_font: &Font) -> TextMetrics {
+        #[cfg(feature = "text")]
+        {
+            if let Some(metrics) =
+                crate::FontLibrary::with_system(|library| library.measure(self, default_font))
+            {
+                return metrics
+            }
+        }
+
+        self.get_estimated_metrics()
+    }
+
+    /// Returns the box enclosing this label, all of its spans and its
+    /// box, in label coordinates, see [`TextLabel::get_metrics()`].
+    #[inline]
+    pub fn get_bounds(&self) -> Rect {
+        self.get_metrics().get_bounds()
+    }
+
+    /// Estimates the metrics of this label, assuming a fixed average
+    /// glyph advance, ascent and descent.
+    pub fn get_estimated_metrics(&self) -> TextMetrics {
+        let font_size = self.get_font_size().unwrap_or(Font::DEFAULT_SIZE);
+        let (advance, bounds) = self.estimate_advance_and_bounds();
+
+        TextMetrics {
+            advance,
+            ascent: font_size * Self::ESTIMATED_ASCENT,
+            descent: font_size * Self::ESTIMATED_DESCENT,
+            bounds,
+        }
+    }
+
+    /// Estimates the box enclosing this label and all of its spans,
+    /// in label coordinates, assuming a fixed average glyph advance.
+    #[inline]
+    pub fn get_estimated_bounds(&self) -> Rect {
+        self.estimate_advance_and_bounds().1
+    }
+
+    fn estimate_advance_and_bounds(&self) -> (f64, Rect) {
+        let font_size = self.get_font_size().unwrap_or(Font::DEFAULT_SIZE);
+        let letter_spacing = self.letter_spacing.unwrap_or(0.0);
+        let mut origin = self.origin.unwrap_or_default();
+        let mut advance = 0.0;
+        let mut span_bounds: Option<Rect> = None;
+
+        origin.x += self.dx.first().copied().unwrap_or(0.0);
+        origin.y += self.dy.first().copied().unwrap_or(0.0);
+
+        for item in self.body.iter() {
+            match item {
+                Item::Text(text) => {
+                    advance += text.chars().count() as f64
+                        * (font_size * Self::ESTIMATED_ADVANCE + letter_spacing);
+                }
+                Item::Span(span) => {
+                    if span.origin.is_some() {
+                        let bounds = span.get_estimated_bounds();
+
+                        span_bounds = Some(span_bounds.map_or(bounds, |r| r.union(bounds)));
+                    } else {
+                        let span_size = span.get_font_size().unwrap_or(font_size);
+                        let span_spacing = span.letter_spacing.unwrap_or(letter_spacing);
+
+                        advance += span.get_num_chars() as f64
+                            * (span_size * Self::ESTIMATED_ADVANCE + span_spacing);
+                    }
+                }
+            }
+        }
+
+        let x0 = match self.anchor {
+            Anchor::Start => origin.x,
+            Anchor::Middle => origin.x - 0.5 * advance,
+            Anchor::End => origin.x - advance,
+        };
+        let bounds = Rect::new(
+            x0,
+            origin.y - font_size * Self::ESTIMATED_ASCENT,
+            x0 + advance,
+            origin.y + font_size * Self::ESTIMATED_DESCENT,
+        );
+
+        (advance, span_bounds.map_or(bounds, |r| r.union(bounds)))
+    }
+
+    /// Breaks this label into lines at line feeds of its text, and
+    /// wherever it would overflow its maximal width, if any.  Returns
+    /// an equivalent label, whose lines are spans positioned on their
+    /// own, and the frame of the box of this label, if any.
+    ///
+    /// Lines are spaced by the line height, and aligned within a box
+    /// as wide as the maximal width, or else as the widest line.  The
+    /// box is placed at the origin of this label, according to its
+    /// anchor, and shifted by the first of its `dx` and `dy`.  Spans
+    /// are never broken, and spans positioned on their own are left
+    /// as they are.
+    ///
+    /// Lines are measured in this label's font, if any, or else in
+    /// `default_font`, as by [`TextLabel::get_metrics()`].
+    pub fn break_lines(&self, default_font: &Font) -> (Cow<'_, TextLabel>, Option<RoundedRect>) {
+        let (lines, box_extent) = self.lay_out_lines(default_font);
+        let frame = if self.text_box.is_some() {
+            self.get_frame(&lines.get_line_metrics(default_font), box_extent)
+        } else {
+            None
+        };
+
+        (lines, frame)
+    }
+
+    /// Returns the frame of the box of this label, enclosing its
+    /// measured bounds, and spanning horizontally its `box_extent`,
+    /// if any.
+    fn get_frame(
+        &self,
+        metrics: &TextMetrics,
+        box_extent: Option<(f64, f64)>,
+    ) -> Option<RoundedRect> {
+        self.text_box.map(|text_box| {
+            let Rect { x0, y0, x1, y1 } = metrics.bounds;
+            let (x0, x1) = box_extent.map_or((x0, x1), |(bx0, bx1)| (x0.min(bx0), x1.max(bx1)));
+
+            text_box.get_frame(Rect::new(x0, y0, x1, y1))
+        })
+    }
+
+    fn is_multi_line(&self) -> bool {
+        self.max_width.is_some()
+            || self.body.iter().any(|item| matches!(item, Item::Text(text) if text.contains('\n')))
+    }
+
+    /// Returns this label broken into lines, and the horizontal
+    /// extent of its box, unless the label is a single line.
+    fn lay_out_lines(&self, default_font: &Font) -> (Cow<'_, TextLabel>, Option<(f64, f64)>) {
+        if !self.is_multi_line() {
+            return (Cow::Borrowed(self), None)
+        }
+
+        let mut font = self.get_font().unwrap_or(default_font).clone();
+
+        if let Some(size) = self.font_size {
+            font.set_size(size);
+        }
+
+        let mut lines = vec![Line::default()];
+        let mut positioned_spans = Vec::new();
+
+        for item in self.body.iter() {
+            match item {
+                Item::Text(text) => {
+                    for (ndx, paragraph) in text.split('\n').enumerate() {
+                        if ndx > 0 {
+                            lines.push(Line::default());
+                        }
+
+                        for word in split_words(paragraph) {
+                            self.push_word(&mut lines, Item::Text(word.to_string()), &font);
+                        }
+                    }
+                }
+                Item::Span(span) if span.origin.is_some() => positioned_spans.push(item.clone()),
+                Item::Span(_) => self.push_word(&mut lines, item.clone(), &font),
+            }
+        }
+
+        for line in lines.iter_mut() {
+            line.trim_end();
+        }
+
+        let widest = lines.iter().map(|line| line.advance).fold(0.0, f64::max);
+        let box_width = self.max_width.map_or(widest, |w| w.max(widest));
+        let mut origin = self.origin.unwrap_or_default();
+
+        origin.x += self.dx.first().copied().unwrap_or(0.0);
+        origin.y += self.dy.first().copied().unwrap_or(0.0);
+
+        let x0 = origin.x - self.anchor.as_fraction() * box_width;
+        let align = self.align.unwrap_or(self.anchor);
+        let line_x = x0 + align.as_fraction() * box_width;
+        let line_step = font.get_size() * self.get_line_height();
+        let mut label = TextLabel {
+            origin:           self.origin,
+            font:             Some(font.clone()),
+            is_root:          self.is_root,
+            font_is_explicit: self.font_is_explicit,
+            font_size:        self.font_size,
+            style_id:         self.style_id,
+            letter_spacing:   self.letter_spacing,
+            ..TextLabel::new()
+        };
+
+        for (ndx, line) in lines.into_iter().enumerate() {
+            label.body.push(Item::Span(TextLabel {
+                origin:  Some(Point::new(line_x, origin.y + ndx as f64 * line_step)),
+                anchor:  align,
+                body:    line.items,
+                font:    Some(font.clone()),
+                is_root: false,
+                ..TextLabel::new()
+            }));
+        }
+
+        label.body.extend(positioned_spans);
+
+        (Cow::Owned(label), Some((x0, x0 + box_width)))
+    }
+
+    /// Appends a `word` to the last of `lines`, or to a new line, if
+    /// it would overflow the maximal width.  Blank words are dropped
+    /// at line breaks.
+    ///
+    /// Only the `word` is measured, since glyphs are placed by their
+    /// advances, hence the advance of a line is the sum of advances
+    /// of its words.
+    fn push_word(&self, lines: &mut Vec<Line>, word: Item, font: &Font) {
+        let is_blank = matches!(&word, Item::Text(text) if text.trim().is_empty());
+        let advance = measure_item(&word, font, self.letter_spacing);
+
+        if let (Some(max_width), Some(line)) = (self.max_width, lines.last()) {
+            if !line.items.is_empty() && line.advance + advance > max_width {
+                if is_blank {
+                    return
+                }
+                lines.push(Line::default());
+            }
+        }
+
+        if let Some(line) = lines.last_mut() {
+            line.advance += advance;
+            line.blank_advance = if is_blank { line.blank_advance + advance } else { 0.0 };
+            append_item(&mut line.items, word);
+        }
+    }
+
+    pub(crate) fn get_num_chars(&self) -> usize {
+        self.body
+            .iter()
+            .map(|item| match item {
+                Item::Text(text) => text.chars().count(),
+                Item::Span(span) => span.get_num_chars(),
+            })
+            .sum()
+    }
+
+    /// Returns [`TextLabel::DEFAULT_FONT`] with a `'static` lifetime.
+    #[inline]
+    pub fn default_font() -> &'static Font {
+        &DEFAULT_FONT
+    }
+
+    /// Returns the font of a `style`, if any, or the sans-serif font
+    /// of a `theme`, or [`TextLabel::DEFAULT_FONT`], i.e. the font
+    /// used for labels without a font of their own.
+    pub fn get_default_font<'a>(style: Option<&'a Style>, theme: &'a Theme) -> &'a Font {
+        style
+            .and_then(|s| s.get_font())
+            .or_else(|| theme.get_sans_serif_font())
+            .unwrap_or(Self::default_font())
+    }
+
     pub fn resolve_font(&mut self, style: Option<&Style>, theme: &Theme) {
         if self.font_is_explicit {
             assert!(self.font.is_some())
         } else {
-            let mut font = style
-                .and_then(|s| s.get_font())
-                .or_else(|| theme.get_sans_serif_font())
-                .unwrap_or(&Self::DEFAULT_FONT)
-                .clone();
+            let mut font = Self::get_default_font(style, theme).clone();
 
             if let Some(size) = self.font_size {
                 font.set_size(size);
@@ -220,6 +829,79 @@ impl TextLabel {
     }
 }
 
+/// Splits a `text` into alternating runs of whitespace and of other
+/// characters.
+fn split_words(text: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let mut start = 0;
+    let mut last_is_blank = None;
+
+    for (pos, ch) in text.char_indices() {
+        let is_blank = ch.is_whitespace();
+
+        if last_is_blank.map_or(false, |b| b != is_blank) {
+            words.push(&text[start..pos]);
+            start = pos;
+        }
+        last_is_blank = Some(is_blank);
+    }
+
+    if start < text.len() {
+        words.push(&text[start..]);
+    }
+
+    words
+}
+
+/// Appends an `item` to a `line`, merging adjacent texts.
+fn append_item(line: &mut Vec<Item>, item: Item) {
+    match (line.last_mut(), item) {
+        (Some(Item::Text(last)), Item::Text(text)) => last.push_str(&text),
+        (_, item) => line.push(item),
+    }
+}
+
+/// A line of a multi-line label, as filled by
+/// [`TextLabel::push_word()`].
+#[derive(Default)]
+struct Line {
+    items:         Vec<Item>,
+    /// The advance of all items.
+    advance:       f64,
+    /// The advance of trailing blanks.
+    blank_advance: f64,
+}
+
+impl Line {
+    /// Removes trailing blanks, so that they don't count in aligning
+    /// the line.
+    fn trim_end(&mut self) {
+        if let Some(Item::Text(text)) = self.items.last_mut() {
+            text.truncate(text.trim_end().len());
+
+            if text.is_empty() {
+                self.items.pop();
+            }
+        }
+
+        self.advance -= self.blank_advance;
+        self.blank_advance = 0.0;
+    }
+}
+
+/// Returns the advance of an `item` set in a `font`, with
+/// `letter_spacing`, if any.
+fn measure_item(item: &Item, font: &Font, letter_spacing: Option<f64>) -> f64 {
+    let label = TextLabel {
+        body: vec![item.clone()],
+        font: Some(font.clone()),
+        letter_spacing,
+        ..TextLabel::new()
+    };
+
+    label.get_line_metrics(font).get_advance()
+}
+
 impl Default for TextLabel {
     fn default() -> Self {
         Self::new()
@@ -229,7 +911,7 @@ impl Default for TextLabel {
 impl PreprocessWithStyle for TextLabel {
     fn preprocess_with_style(
         &mut self,
-        ts: TranslateScale,
+        ts: Affine,
         style: Option<&Style>,
         theme: &Theme,
     ) -> Result<(), VisError> {
@@ -249,3 +931,134 @@ impl PreprocessWithStyle for TextLabel {
         Ok(())
     }
 }
+
+/// Note: these tests rely on estimated metrics, i.e. on glyph
+/// advances of 0.6 of the font size.
+#[cfg(all(test, not(feature = "text")))]
+mod tests {
+    use super::*;
+
+    /// Lists texts and origins of the lines of a `label`.
+    fn get_lines(label: &TextLabel) -> Vec<(String, Point)> {
+        let (lines, _) = label.break_lines(TextLabel::default_font());
+
+        lines
+            .get_body()
+            .iter()
+            .filter_map(|item| match item {
+                Item::Span(span) => {
+                    let text = span.get_body().iter().fold(String::new(), |mut text, item| {
+                        if let Item::Text(t) = item {
+                            text.push_str(t);
+                        }
+                        text
+                    });
+
+                    Some((text, span.get_origin().unwrap()))
+                }
+                Item::Text(_) => None,
+            })
+            .collect()
+    }
+
+    fn texts(lines: &[(String, Point)]) -> Vec<&str> {
+        lines.iter().map(|(text, _)| text.as_str()).collect()
+    }
+
+    #[test]
+    fn explicit_breaks() {
+        let label = TextLabel::new().with_text("ab\ncd e\n\nf").with_font_size(10.0);
+        let lines = get_lines(&label);
+
+        assert_eq!(texts(&lines), vec!["ab", "cd e", "", "f"]);
+        assert_eq!(lines[1].1, Point::new(0.0, 12.0));
+        assert_eq!(lines[3].1, Point::new(0.0, 36.0));
+
+        let label = TextLabel::new().with_text("ab");
+        let (single, frame) = label.break_lines(TextLabel::default_font());
+
+        assert!(matches!(single, Cow::Borrowed(_)));
+        assert!(frame.is_none());
+    }
+
+    #[test]
+    fn wrapping() {
+        let label = TextLabel::new()
+            .with_text("aa bb cc dddddddd e")
+            .with_font_size(10.0)
+            .with_max_width(30.0);
+
+        assert_eq!(texts(&get_lines(&label)), vec!["aa bb", "cc", "dddddddd", "e"]);
+
+        let label = label.with_line_height(2.0).with_xy(5.0, 50.0);
+        let lines = get_lines(&label);
+
+        assert_eq!(lines[0].1, Point::new(5.0, 50.0));
+        assert_eq!(lines[3].1, Point::new(5.0, 110.0));
+    }
+
+    #[test]
+    fn alignment() {
+        // Trailing blanks don't count in aligning lines.
+        let label =
+            TextLabel::new().with_text("ab  \ncdef").with_font_size(10.0).with_end_alignment();
+        let lines = get_lines(&label);
+
+        assert_eq!(texts(&lines), vec!["ab", "cdef"]);
+        assert_eq!(lines[0].1.x, 24.0);
+        assert_eq!(label.get_bounds(), Rect::new(0.0, -8.0, 24.0, 14.0));
+
+        let label = TextLabel::new()
+            .with_text("ab cd ef")
+            .with_font_size(10.0)
+            .with_max_width(40.0)
+            .with_middle_alignment()
+            .with_middle_anchor()
+            .with_xy(100.0, 0.0);
+        let lines = get_lines(&label);
+
+        assert_eq!(texts(&lines), vec!["ab cd", "ef"]);
+        assert!(lines.iter().all(|(_, origin)| origin.x == 100.0));
+        assert_eq!(label.get_bounds().x0, 85.0);
+    }
+
+    #[test]
+    fn letter_spacing() {
+        let advance = |label: TextLabel| label.with_font_size(10.0).get_metrics().get_advance();
+        let cd = || TextLabel::new().with_text("cd");
+
+        assert_eq!(advance(TextLabel::new().with_text("abcd")), 24.0);
+        assert_eq!(advance(TextLabel::new().with_text("abcd").with_letter_spacing(2.0)), 32.0);
+        assert_eq!(advance(TextLabel::new().with_text("ab").with_span(cd())), 24.0);
+        assert_eq!(
+            advance(TextLabel::new().with_text("ab").with_span(cd().with_letter_spacing(4.0))),
+            32.0
+        );
+        assert_eq!(
+            advance(TextLabel::new().with_text("ab").with_span(cd()).with_letter_spacing(1.0)),
+            28.0
+        );
+
+        let label = TextLabel::new().with_text("abcd").with_font_size(10.0);
+        let spaced = label.clone().with_letter_spacing(2.0);
+
+        assert_eq!(label.get_bounds().width(), 24.0);
+        assert_eq!(spaced.get_bounds().width(), 32.0);
+        assert_eq!(spaced.get_bounds().height(), label.get_bounds().height());
+    }
+
+    #[test]
+    fn text_box() {
+        let label = TextLabel::new()
+            .with_text("ab\nc")
+            .with_font_size(10.0)
+            .with_max_width(40.0)
+            .with_box(TextBox::new().with_padding(2.0).with_radius(1.0));
+        let (_, frame) = label.break_lines(TextLabel::default_font());
+        let frame = frame.unwrap();
+
+        assert_eq!(frame.rect(), Rect::new(-2.0, -10.0, 42.0, 16.0));
+        assert_eq!(frame.radii().as_single_radius(), Some(1.0));
+        assert_eq!(label.get_bounds(), frame.rect());
+    }
+}
@@ -1,19 +1,28 @@
 use std::{
-    collections::{HashMap, hash_map},
+    collections::{BTreeMap, HashMap, hash_map},
     iter::FromIterator,
 };
+#[cfg(feature = "serialize")]
+use std::convert::TryFrom;
 use crate::{
-    Style, StyleId, Color, Stroke, Fill, UnitPoint, GradientStops, Gradient, Marker, MarkerId,
-    Font, font::GenericFontFamily, VisError,
+    Style, StyleId, Color, Stroke, Fill, UnitPoint, GradientStops, Gradient, Hatch, Marker,
+    MarkerId, Font, EmbeddedFont, GenericFontFamily, ColorDeficiency, ColorSpace, VisError,
+    error::DetailedError,
 };
+#[cfg(feature = "serialize")]
+use crate::AsCss;
 
 const DEFAULT_NAME: &str = "default";
 const SCENE_NAME: &str = "scene";
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Deserialize))]
 pub struct Variation {
+    #[cfg_attr(feature = "serialize", serde(default))]
     strokes:    HashMap<String, Stroke>,
+    #[cfg_attr(feature = "serialize", serde(default))]
     fills:      HashMap<String, Fill>,
+    #[cfg_attr(feature = "serialize", serde(default))]
     variations: HashMap<String, Variation>,
 }
 
@@ -95,7 +104,18 @@ impl Variation {
         S: AsRef<str>,
     {
         let name = name.as_ref();
-        let mut result = self.get_stroke_by_name(name);
+
+        self.find_stroke_on_path(path, name).or_else(|| self.get_stroke_by_name(name))
+    }
+
+    /// Returns the stroke of a given `name` overridden by the
+    /// innermost variation along a `path`, which overrides it.
+    fn find_stroke_on_path<V, I>(&self, path: I, name: &str) -> Option<&Stroke>
+    where
+        V: AsRef<str>,
+        I: IntoIterator<Item = V>,
+    {
+        let mut result = None;
         let mut variation = self;
 
         for nv in path.into_iter() {
@@ -119,7 +139,18 @@ impl Variation {
         S: AsRef<str>,
     {
         let name = name.as_ref();
-        let mut result = self.get_fill_by_name(name);
+
+        self.find_fill_on_path(path, name).or_else(|| self.get_fill_by_name(name))
+    }
+
+    /// Returns the fill of a given `name` overridden by the innermost
+    /// variation along a `path`, which overrides it.
+    fn find_fill_on_path<V, I>(&self, path: I, name: &str) -> Option<&Fill>
+    where
+        V: AsRef<str>,
+        I: IntoIterator<Item = V>,
+    {
+        let mut result = None;
         let mut variation = self;
 
         for nv in path.into_iter() {
@@ -135,9 +166,154 @@ impl Variation {
 
         result
     }
+
+    /// Returns a copy of this variation, and all its subvariations,
+    /// with colors of strokes and fills replaced by `recolor`, and
+    /// names of gradients and hatches replaced by `rename`.
+    fn map_colors<F, G>(&self, recolor: &F, rename: &G) -> Self
+    where
+        F: Fn(Color) -> Color,
+        G: Fn(&str) -> String,
+    {
+        Variation {
+            strokes:    self
+                .strokes
+                .iter()
+                .map(|(name, stroke)| (name.clone(), stroke.map_colors(recolor, rename)))
+                .collect(),
+            fills:      self
+                .fills
+                .iter()
+                .map(|(name, fill)| (name.clone(), fill.map_colors(recolor, rename)))
+                .collect(),
+            variations: self
+                .variations
+                .iter()
+                .map(|(name, variation)| (name.clone(), variation.map_colors(recolor, rename)))
+                .collect(),
+        }
+    }
+
+    /// Returns the stroke of a given `name` overridden by the variation
+    /// of highest priority in a `stack`, which overrides it, or the
+    /// stroke of this variation, if none does.
+    pub fn get_stroke_by_stack<S: AsRef<str>>(
+        &self,
+        stack: &VariationStack,
+        name: S,
+    ) -> Option<&Stroke> {
+        let name = name.as_ref();
+
+        stack
+            .get_paths()
+            .rev()
+            .find_map(|path| self.find_stroke_on_path(path, name))
+            .or_else(|| self.get_stroke_by_name(name))
+    }
+
+    /// Returns the fill of a given `name` overridden by the variation
+    /// of highest priority in a `stack`, which overrides it, or the
+    /// fill of this variation, if none does.
+    pub fn get_fill_by_stack<S: AsRef<str>>(
+        &self,
+        stack: &VariationStack,
+        name: S,
+    ) -> Option<&Fill> {
+        let name = name.as_ref();
+
+        stack
+            .get_paths()
+            .rev()
+            .find_map(|path| self.find_fill_on_path(path, name))
+            .or_else(|| self.get_fill_by_name(name))
+    }
+}
+
+/// A prioritized set of variations used at once, each given by its
+/// path, as in [`Theme::use_variation()`].
+///
+/// Whenever several variations override a stroke or a fill, the one
+/// of highest priority wins.  Variations of equal priority are
+/// ordered by the time of their (last) insertion.
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct VariationStack {
+    entries: Vec<(i32, Vec<String>)>,
+}
+
+impl VariationStack {
+    pub fn new() -> Self {
+        VariationStack::default()
+    }
+
+    pub fn with_variation<V, I>(mut self, path: I, priority: i32) -> Self
+    where
+        V: AsRef<str>,
+        I: IntoIterator<Item = V>,
+    {
+        self.push(path, priority);
+        self
+    }
+
+    /// Adds a variation to the stack, or moves it, if it is already
+    /// in the stack, to the top of the variations of its new
+    /// `priority`.
+    pub fn push<V, I>(&mut self, path: I, priority: i32)
+    where
+        V: AsRef<str>,
+        I: IntoIterator<Item = V>,
+    {
+        let path: Vec<String> = path.into_iter().map(|v| v.as_ref().into()).collect();
+
+        self.entries.retain(|(_, p)| *p != path);
+
+        let ndx = self.entries.iter().position(|(prio, _)| *prio > priority);
+
+        self.entries.insert(ndx.unwrap_or(self.entries.len()), (priority, path));
+    }
+
+    /// Removes a variation from the stack.  Returns `true` if the
+    /// variation was in the stack.
+    pub fn remove<V, I>(&mut self, path: I) -> bool
+    where
+        V: AsRef<str>,
+        I: IntoIterator<Item = V>,
+    {
+        let path: Vec<String> = path.into_iter().map(|v| v.as_ref().into()).collect();
+        let old_len = self.entries.len();
+
+        self.entries.retain(|(_, p)| *p != path);
+
+        self.entries.len() < old_len
+    }
+
+    pub fn contains<V, I>(&self, path: I) -> bool
+    where
+        V: AsRef<str>,
+        I: IntoIterator<Item = V>,
+    {
+        let path: Vec<String> = path.into_iter().map(|v| v.as_ref().into()).collect();
+
+        self.entries.iter().any(|(_, p)| *p == path)
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Lists paths of variations in the stack, lowest priority first.
+    pub fn get_paths(&self) -> impl DoubleEndedIterator<Item = &[String]> {
+        self.entries.iter().map(|(_, path)| path.as_slice())
+    }
 }
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serialize", derive(serde::Deserialize), serde(try_from = "ThemeData"))]
 pub struct Theme {
     original:        Variation,
     styles:          Vec<Style>,
@@ -145,7 +321,11 @@ pub struct Theme {
     named_styles:    HashMap<String, StyleId>,
     named_markers:   HashMap<String, MarkerId>,
     named_gradspecs: HashMap<String, Gradient>,
+    named_hatches:   HashMap<String, Hatch>,
     default_fonts:   HashMap<GenericFontFamily, Font>,
+    embedded_fonts:  Vec<EmbeddedFont>,
+    font_fallbacks:  HashMap<GenericFontFamily, Vec<String>>,
+    color_space:     ColorSpace,
 }
 
 impl Default for Theme {
@@ -162,7 +342,11 @@ impl Default for Theme {
         ]);
         let named_markers = HashMap::default();
         let named_gradspecs = HashMap::default();
+        let named_hatches = HashMap::default();
         let default_fonts = HashMap::default();
+        let embedded_fonts = Vec::new();
+        let font_fallbacks = HashMap::default();
+        let color_space = ColorSpace::default();
 
         Theme {
             original,
@@ -171,7 +355,11 @@ impl Default for Theme {
             named_styles,
             named_markers,
             named_gradspecs,
+            named_hatches,
             default_fonts,
+            embedded_fonts,
+            font_fallbacks,
+            color_space,
         }
     }
 }
@@ -207,6 +395,8 @@ impl Theme {
             }
         }
 
+        self.resolve_references();
+
         self
     }
 
@@ -274,6 +464,18 @@ impl Theme {
         self
     }
 
+    pub fn with_hatches<S, I>(mut self, hatches: I) -> Self
+    where
+        S: AsRef<str>,
+        I: IntoIterator<Item = (S, Hatch)>,
+    {
+        for (name, hatch) in hatches.into_iter() {
+            self.named_hatches.insert(name.as_ref().into(), hatch);
+        }
+
+        self
+    }
+
     /// Note: calling this is the only way of adding markers to a
     /// theme.
     pub fn with_markers<S, I>(mut self, markers: I) -> Self
@@ -295,9 +497,338 @@ impl Theme {
             }
         }
 
+        self.resolve_references();
+
+        self
+    }
+
+    /// Validates this theme, see [`Theme::validate()`].
+    ///
+    /// Note: styles inherit from their base styles (see
+    /// [`Style::extends()`]), and names of markers and styles are
+    /// resolved to their ids, whenever styles or markers are added,
+    /// so that a theme renders the same whether it is built, or not.
+    /// Only building reports missing names and inheritance cycles.
+    pub fn build(self) -> Result<Self, VisError> {
+        self.validate()?;
+
+        Ok(self)
+    }
+
+    /// Copies inherited properties of styles from their base styles,
+    /// resolves names of markers referenced by styles, and names of
+    /// styles referenced by markers, to their ids.  Missing names and
+    /// styles extending themselves are skipped here, and reported by
+    /// [`Theme::validate()`].
+    fn resolve_references(&mut self) {
+        self.inherit_styles();
+
+        let named_styles = &self.named_styles;
+        let named_markers = &self.named_markers;
+
+        for style in self.styles.iter_mut() {
+            style.get_markers_mut().resolve(|name| named_markers.get(name).copied());
+        }
+
+        for marker in self.markers.iter_mut() {
+            let style_id = marker.get_style_name().and_then(|name| named_styles.get(name));
+
+            marker.set_style_id(style_id.copied());
+        }
+    }
+
+    fn inherit_styles(&mut self) {
+        let mut is_done = vec![false; self.styles.len()];
+
+        for style in self.styles.iter_mut() {
+            style.clear_inherited();
+        }
+
+        for style_id in 0..self.styles.len() {
+            self.inherit_style(style_id, &mut is_done, &mut Vec::new());
+        }
+    }
+
+    /// Resolves inheritance of a style, after resolving inheritance
+    /// of its base.  A `chain` of styles extended by the style so far
+    /// is used for detecting cycles.
+    fn inherit_style(&mut self, style_id: usize, is_done: &mut [bool], chain: &mut Vec<usize>) {
+        if is_done[style_id] {
+            return
+        }
+
+        let base_id = self.styles[style_id]
+            .get_base_name()
+            .and_then(|name| self.named_styles.get(name))
+            .map(|id| id.0);
+
+        if let Some(base_id) = base_id {
+            if base_id != style_id && !chain.contains(&base_id) {
+                chain.push(style_id);
+                self.inherit_style(base_id, is_done, chain);
+                chain.pop();
+
+                let base = self.styles[base_id].clone();
+
+                self.styles[style_id].inherit_from(&base);
+            }
+        }
+
+        is_done[style_id] = true;
+    }
+
+    /// Checks that every name referenced in this theme is defined,
+    /// i.e. base styles, strokes, fills and markers of styles, styles
+    /// of markers, gradients and hatches of all strokes and fills, as
+    /// well as strokes and fills overridden by all variations.
+    pub fn validate(&self) -> Result<(), VisError> {
+        let mut styles: Vec<_> = self.named_styles.iter().collect();
+        let mut markers: Vec<_> = self.named_markers.iter().collect();
+
+        styles.sort_by_key(|(_, id)| id.0);
+        markers.sort_by_key(|(_, id)| id.0);
+
+        for (name, id) in styles {
+            let style = &self.styles[id.0];
+            let details = || format!("in style '{}'", name);
+
+            self.validate_base(id.0).map_err(|err| err.with_string(details()))?;
+
+            if let Some(stroke_name) = style.get_stroke_name() {
+                if self.original.get_stroke_by_name(stroke_name).is_none() {
+                    return Err(
+                        VisError::stroke_missing_for_name(stroke_name).with_string(details())
+                    )
+                }
+            } else if let Some(stroke) = style.get_stroke() {
+                self.validate_stroke(stroke).map_err(|err| err.with_string(details()))?;
+            }
+
+            if let Some(fill_name) = style.get_fill_name() {
+                if self.original.get_fill_by_name(fill_name).is_none() {
+                    return Err(VisError::fill_missing_for_name(fill_name).with_string(details()))
+                }
+            } else if let Some(fill) = style.get_fill() {
+                self.validate_fill(fill).map_err(|err| err.with_string(details()))?;
+            }
+
+            for marker_name in style.get_markers().get_names() {
+                if !self.named_markers.contains_key(marker_name) {
+                    return Err(
+                        VisError::marker_missing_for_name(marker_name).with_string(details())
+                    )
+                }
+            }
+        }
+
+        for (name, id) in markers {
+            if let Some(style_name) = self.markers[id.0].get_style_name() {
+                if !self.named_styles.contains_key(style_name) {
+                    return Err(VisError::style_missing_for_name(style_name)
+                        .with_string(format!("in marker '{}'", name)))
+                }
+            }
+        }
+
+        self.validate_variation(&self.original, &mut Vec::new())
+    }
+
+    /// Checks that the chain of base styles of a style ends, without
+    /// missing names and without cycles.
+    fn validate_base(&self, style_id: usize) -> Result<(), VisError> {
+        let mut chain = vec![style_id];
+
+        while let Some(base_name) = self.styles[chain[chain.len() - 1]].get_base_name() {
+            match self.named_styles.get(base_name) {
+                Some(base_id) if chain.contains(&base_id.0) => {
+                    return Err(VisError::style_cycle_for_name(base_name))
+                }
+                Some(base_id) => chain.push(base_id.0),
+                None => return Err(VisError::style_missing_for_name(base_name)),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate_variation<'a>(
+        &self,
+        variation: &'a Variation,
+        path: &mut Vec<&'a str>,
+    ) -> Result<(), VisError> {
+        let details = |kind: &str, name: &str, path: &[&str]| {
+            if path.is_empty() {
+                format!("in {} '{}'", kind, name)
+            } else {
+                format!("in {} '{}' of variation '{}'", kind, name, path.join("/"))
+            }
+        };
+
+        for (name, stroke) in BTreeMap::from_iter(variation.strokes.iter()) {
+            if !path.is_empty() && self.original.get_stroke_by_name(name).is_none() {
+                return Err(VisError::stroke_missing_for_name(name)
+                    .with_string(format!("in variation '{}'", path.join("/"))))
+            }
+
+            self.validate_stroke(stroke)
+                .map_err(|err| err.with_string(details("stroke", name, path)))?;
+        }
+
+        for (name, fill) in BTreeMap::from_iter(variation.fills.iter()) {
+            if !path.is_empty() && self.original.get_fill_by_name(name).is_none() {
+                return Err(VisError::fill_missing_for_name(name)
+                    .with_string(format!("in variation '{}'", path.join("/"))))
+            }
+
+            self.validate_fill(fill).map_err(|err| err.with_string(details("fill", name, path)))?;
+        }
+
+        for (name, nested) in BTreeMap::from_iter(variation.variations.iter()) {
+            path.push(name);
+            self.validate_variation(nested, path)?;
+            path.pop();
+        }
+
+        Ok(())
+    }
+
+    fn validate_stroke(&self, stroke: &Stroke) -> Result<(), VisError> {
+        match stroke.get_gradient_name() {
+            Some(name) if self.get_gradspec(name).is_none() => {
+                Err(VisError::gradient_missing_for_name(name))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn validate_fill(&self, fill: &Fill) -> Result<(), VisError> {
+        match fill {
+            Fill::Color(_) => Ok(()),
+            Fill::Linear(name) => match self.get_gradspec(name) {
+                Some(Gradient::Linear(..)) => Ok(()),
+                Some(_) => Err(VisError::gradient_mismatch_for_name(name)),
+                None => Err(VisError::gradient_missing_for_name(name)),
+            },
+            Fill::Radial(name) => match self.get_gradspec(name) {
+                Some(Gradient::Radial(..)) => Ok(()),
+                Some(_) => Err(VisError::gradient_mismatch_for_name(name)),
+                None => Err(VisError::gradient_missing_for_name(name)),
+            },
+            Fill::Hatch(name) => match self.get_hatch(name) {
+                Some(_) => Ok(()),
+                None => Err(VisError::hatch_missing_for_name(name)),
+            },
+        }
+    }
+
+    /// Adds a variation simulating how this theme is seen by a person
+    /// with a color vision `deficiency`, see
+    /// [`ColorDeficiency::simulate()`].
+    ///
+    /// The variation is named after the deficiency (see
+    /// [`ColorDeficiency::as_name()`]), and overrides all strokes and
+    /// fills of the theme.  Its subvariations mirror all variations
+    /// of the theme, e.g. `["deuteranopia", "dark"]` simulates the
+    /// `"dark"` variation.  Gradients and hatches are simulated as
+    /// well, and added under names prefixed with the variation's name.
+    ///
+    /// Adding the same variation again replaces it, together with its
+    /// gradients and hatches.
+    pub fn with_color_deficiency_variation(mut self, deficiency: ColorDeficiency) -> Self {
+        let prefix = deficiency.as_name();
+        let recolor = |color| deficiency.simulate(color);
+        let rename = |name: &str| format!("{}-{}", prefix, name);
+
+        self.original.variations.remove(prefix);
+        remove_renamed(&mut self.named_gradspecs, prefix);
+        remove_renamed(&mut self.named_hatches, prefix);
+
+        let gradspecs: Vec<_> = self
+            .named_gradspecs
+            .iter()
+            .map(|(name, gradient)| (rename(name), gradient.map_colors(&recolor)))
+            .collect();
+        let hatches: Vec<_> = self
+            .named_hatches
+            .iter()
+            .map(|(name, hatch)| (rename(name), hatch.map_colors(&recolor)))
+            .collect();
+        let variation = self.original.map_colors(&recolor, &rename);
+
+        self.named_gradspecs.extend(gradspecs);
+        self.named_hatches.extend(hatches);
+        self.original.variations.insert(prefix.into(), variation);
+
+        self
+    }
+
+    /// Sets default fonts of generic families, e.g. the one used for
+    /// [`Theme::get_serif_font()`].
+    pub fn with_default_fonts<I>(mut self, fonts: I) -> Self
+    where
+        I: IntoIterator<Item = Font>,
+    {
+        for font in fonts.into_iter() {
+            self.default_fonts.insert(font.get_class(), font);
+        }
+
+        self
+    }
+
+    /// Adds font faces carried by this theme.  All backends render
+    /// text in an embedded face, whenever a font, or a fallback chain
+    /// of its generic family, names its family, see
+    /// [`Theme::with_font_fallbacks()`].
+    ///
+    /// Note: labels are measured with the shared
+    /// [`FontLibrary`](crate::FontLibrary), hence embedded fonts are
+    /// used for layout only after [`Theme::load_fonts()`].
+    pub fn with_embedded_fonts<I>(mut self, fonts: I) -> Self
+    where
+        I: IntoIterator<Item = EmbeddedFont>,
+    {
+        for font in fonts.into_iter() {
+            if !self.embedded_fonts.contains(&font) {
+                self.embedded_fonts.push(font);
+            }
+        }
+
+        self
+    }
+
+    /// Sets the fallback chain of a generic family, i.e. the family
+    /// names tried in order, after the names of a font of that
+    /// family, and before the generic family itself.  Chains of the
+    /// sans-serif family are tried for fonts of any family.
+    pub fn with_font_fallbacks<N, I>(mut self, class: GenericFontFamily, names: I) -> Self
+    where
+        N: AsRef<str>,
+        I: IntoIterator<Item = N>,
+    {
+        self.font_fallbacks
+            .insert(class, names.into_iter().map(|name| name.as_ref().to_string()).collect());
+
         self
     }
 
+    /// Sets the color space, in which colors are interpolated during
+    /// variation transitions, see [`Theme::step_variation()`].
+    pub fn with_color_space(mut self, space: ColorSpace) -> Self {
+        self.color_space = space;
+
+        self
+    }
+
+    #[inline]
+    pub fn set_color_space(&mut self, space: ColorSpace) {
+        self.color_space = space;
+    }
+
+    #[inline]
+    pub fn get_color_space(&self) -> ColorSpace {
+        self.color_space
+    }
+
     pub fn use_original_variation(&mut self) {
         for style in self.styles.iter_mut() {
             style.resolve_initially(&self.original);
@@ -316,7 +847,7 @@ impl Theme {
 
     pub fn start_original_variation(&mut self, max_subdivision: usize) {
         for style in self.styles.iter_mut() {
-            style.start_original_resolution(&self.original, max_subdivision);
+            style.start_original_resolution(&self.original, max_subdivision, self.color_space);
         }
     }
 
@@ -326,7 +857,58 @@ impl Theme {
         I: IntoIterator<Item = V> + Clone,
     {
         for style in self.styles.iter_mut() {
-            style.start_resolution(&self.original, path.clone(), max_subdivision);
+            style.start_resolution(
+                &self.original,
+                path.clone(),
+                max_subdivision,
+                self.color_space,
+            );
+        }
+    }
+
+    /// Resolves strokes and fills of all styles in a `stack` of
+    /// variations, see [`VariationStack`].
+    pub fn use_variation_stack(&mut self, stack: &VariationStack) {
+        for style in self.styles.iter_mut() {
+            style.resolve_by_stack(&self.original, stack);
+        }
+    }
+
+    /// Starts tweening strokes and fills of all styles from their
+    /// current values to those resolved in a `stack` of variations,
+    /// see [`Theme::step_variation()`].
+    pub fn start_variation_stack(&mut self, stack: &VariationStack, max_subdivision: usize) {
+        for style in self.styles.iter_mut() {
+            style.start_resolution_by_stack(
+                &self.original,
+                None,
+                stack,
+                max_subdivision,
+                self.color_space,
+            );
+        }
+    }
+
+    /// Starts tweening strokes and fills of all styles from those
+    /// resolved in one stack of variations to those resolved in
+    /// another, see [`Theme::step_variation()`].
+    ///
+    /// Note: styles take the values resolved in the `from` stack
+    /// immediately.
+    pub fn start_variation_transition(
+        &mut self,
+        from: &VariationStack,
+        to: &VariationStack,
+        max_subdivision: usize,
+    ) {
+        for style in self.styles.iter_mut() {
+            style.start_resolution_by_stack(
+                &self.original,
+                Some(from),
+                to,
+                max_subdivision,
+                self.color_space,
+            );
         }
     }
 
@@ -406,6 +988,11 @@ impl Theme {
         self.named_gradspecs.get(name.as_ref())
     }
 
+    #[inline]
+    pub fn get_hatch<S: AsRef<str>>(&self, name: S) -> Option<&Hatch> {
+        self.named_hatches.get(name.as_ref())
+    }
+
     #[inline]
     pub fn get_marker(&self, marker_id: Option<MarkerId>) -> Option<&Marker> {
         marker_id.and_then(|id| self.markers.get(id.0))
@@ -426,14 +1013,10 @@ impl Theme {
             .map(|style| style.get_markers())
             .map(|markers| {
                 (
-                    markers
-                        .get_start_name()
-                        .and_then(|name| self.get_marker_by_name(name))
+                    self.get_marker(markers.get_start_id())
                         .map(|marker| marker.get_width())
                         .unwrap_or(0.0),
-                    markers
-                        .get_end_name()
-                        .and_then(|name| self.get_marker_by_name(name))
+                    self.get_marker(markers.get_end_id())
                         .map(|marker| marker.get_width())
                         .unwrap_or(0.0),
                 )
@@ -441,23 +1024,156 @@ impl Theme {
             .unwrap_or((0.0, 0.0))
     }
 
+    /// Returns the largest reach of start, mid and end markers of a
+    /// `style`, see [`Marker::get_extent()`].
+    pub fn get_marker_extent(&self, style: &Style) -> f64 {
+        let markers = style.get_markers();
+
+        [markers.get_start_id(), markers.get_mid_id(), markers.get_end_id()]
+            .iter()
+            .filter_map(|marker_id| self.get_marker(*marker_id))
+            .map(|marker| marker.get_extent())
+            .fold(0.0, f64::max)
+    }
+
+    /// Returns the largest distance by which painting with any style
+    /// of this theme may extend outside of a crumb's geometry, i.e.
+    /// the maximum of half stroke widths and marker extents.
+    pub fn get_max_outset(&self) -> f64 {
+        self.styles
+            .iter()
+            .map(|style| {
+                let half_width = style.get_stroke().map(|s| 0.5 * s.get_width()).unwrap_or(0.0);
+
+                half_width.max(self.get_marker_extent(style))
+            })
+            .fold(0.0, f64::max)
+    }
+
+    /// Lists colors of strokes, fills and text of all named styles
+    /// (except the scene's style), which contrast with the background
+    /// color (see [`Theme::get_bg_color()`]) less than `min_ratio`,
+    /// see [`Color::contrast_ratio()`].
+    ///
+    /// Translucent colors are composited over the background.  For
+    /// gradients, the stop of lowest contrast is reported, and for
+    /// hatches, the color of lines.  Styles are listed in the order
+    /// of creation, with their current strokes and fills, i.e. as
+    /// resolved in the variation used, if any.
+    ///
+    /// Note: WCAG 2 requires a contrast ratio of at least 4.5 for
+    /// text, and 3 for other graphics.
+    pub fn audit(&self, min_ratio: f64) -> Vec<ContrastIssue> {
+        let bg_color = self.get_bg_color();
+        let mut styles: Vec<_> = self.named_styles.iter().collect();
+        let mut result = Vec::new();
+
+        styles.sort_by_key(|(_, id)| id.0);
+
+        for (name, id) in styles {
+            if *id == Self::SCENE_STYLE_ID {
+                continue
+            }
+
+            let style = &self.styles[id.0];
+            let mut check = |role, colors: Vec<Color>| {
+                let worst = colors
+                    .into_iter()
+                    .map(|color| (color, color.over(bg_color).contrast_ratio(bg_color)))
+                    .min_by(|(_, a), (_, b)| a.total_cmp(b));
+
+                if let Some((color, ratio)) = worst {
+                    if ratio < min_ratio {
+                        result.push(ContrastIssue {
+                            style_name: name.clone(),
+                            role,
+                            color,
+                            bg_color,
+                            ratio,
+                        });
+                    }
+                }
+            };
+
+            if let Some(stroke) = style.get_stroke() {
+                check(PaintRole::Stroke, self.get_stroke_colors(stroke));
+            }
+
+            if let Some(fill) = style.get_fill() {
+                let role =
+                    if style.get_font().is_some() { PaintRole::Text } else { PaintRole::Fill };
+
+                check(role, self.get_fill_colors(fill));
+            }
+        }
+
+        result
+    }
+
+    fn get_stroke_colors(&self, stroke: &Stroke) -> Vec<Color> {
+        if let Some(name) = stroke.get_gradient_name() {
+            self.get_gradspec(name)
+                .map(|gradient| gradient.get_stops().iter().map(|stop| stop.color).collect())
+                .unwrap_or_default()
+        } else {
+            vec![*stroke.get_brush()]
+        }
+    }
+
+    fn get_fill_colors(&self, fill: &Fill) -> Vec<Color> {
+        match fill {
+            Fill::Color(color) => vec![*color],
+            Fill::Linear(name) | Fill::Radial(name) => self
+                .get_gradspec(name)
+                .map(|gradient| gradient.get_stops().iter().map(|stop| stop.color).collect())
+                .unwrap_or_default(),
+            Fill::Hatch(name) => {
+                self.get_hatch(name).map(|hatch| vec![*hatch.get_brush()]).unwrap_or_default()
+            }
+        }
+    }
+
     #[inline]
     pub fn get_bg_color(&self) -> Color {
         self.get_scene_style().get_fill_color().cloned().unwrap_or(Color::WHITE)
     }
 
+    /// Returns the id of the `n`-th clip path of a rendered scene.
+    ///
+    /// Clip paths share the namespace of element ids with gradients,
+    /// hatches and markers, so the id is prefixed with underscores,
+    /// until it differs from all their names.
+    #[cfg(any(feature = "svg", feature = "tiny"))]
+    pub(crate) fn get_clip_id(&self, n: usize) -> String {
+        let mut id = format!("clip-{}", n);
+
+        while self.named_gradspecs.contains_key(&id)
+            || self.named_hatches.contains_key(&id)
+            || self.named_markers.contains_key(&id)
+        {
+            id.insert(0, '_');
+        }
+
+        id
+    }
+
     #[inline]
-    pub fn get_named_gradspecs(&self) -> hash_map::Iter<String, Gradient> {
+    pub fn get_named_gradspecs(&self) -> hash_map::Iter<'_, String, Gradient> {
         self.named_gradspecs.iter()
     }
 
     #[inline]
-    pub fn get_named_marker_ids(&self) -> hash_map::Iter<String, MarkerId> {
+    pub fn get_named_hatches(&self) -> hash_map::Iter<'_, String, Hatch> {
+        self.named_hatches.iter()
+    }
+
+    #[inline]
+    pub fn get_named_marker_ids(&self) -> hash_map::Iter<'_, String, MarkerId> {
         self.named_markers.iter()
     }
 
     #[inline]
-    pub fn get_named_markers(&self) -> NamedMarkersIter {
+    pub fn get_named_markers(&self) -> NamedMarkersIter<'_> {
         NamedMarkersIter { theme: self, entries: self.named_markers.iter() }
     }
 
@@ -486,6 +1202,29 @@ impl Theme {
         self.default_fonts.get(&GenericFontFamily::Monospace)
     }
 
+    #[inline]
+    pub fn get_embedded_fonts(&self) -> &[EmbeddedFont] {
+        self.embedded_fonts.as_slice()
+    }
+
+    /// Returns the fallback chain of a generic family, see
+    /// [`Theme::with_font_fallbacks()`].
+    #[inline]
+    pub fn get_font_fallbacks(&self, class: GenericFontFamily) -> &[String] {
+        self.font_fallbacks.get(&class).map(|names| names.as_slice()).unwrap_or(&[])
+    }
+
+    /// Loads embedded fonts of this theme into the shared
+    /// [`FontLibrary`](crate::FontLibrary) of the current thread, and
+    /// makes its fallback chains current.  Backends call this before
+    /// fitting, measuring or rendering a scene, and so should callers
+    /// measuring labels on their own, e.g. with
+    /// [`TextLabel::get_bounds()`](crate::TextLabel::get_bounds).
+    #[cfg(feature = "text")]
+    pub fn load_fonts(&self) -> Result<(), VisError> {
+        crate::FontLibrary::with_system_mut(|library| library.load_theme_fonts(self))
+    }
+
     pub fn simple_demo() -> Self {
         let gradient_v_stops = vec![Color::WHITE, Color::BLACK];
         let gradient_h_stops = vec![Color::rgba8(0, 0xff, 0, 64), Color::rgba8(0xff, 0, 0xff, 64)];
@@ -541,6 +1280,274 @@ impl Theme {
             .with_fills(fills)
             .with_variations(variations)
             .with_styles(styles)
+            .build()
+            .expect("Invalid demo theme")
+    }
+}
+
+#[cfg(feature = "serialize")]
+impl Theme {
+    /// Loads a [`Theme`] from its JSON representation.
+    ///
+    /// On a syntax error, the position in the source is given by
+    /// [`VisError::get_position()`].
+    pub fn from_json_str<S: AsRef<str>>(json: S) -> Result<Self, VisError> {
+        Ok(serde_json::from_str(json.as_ref())?)
+    }
+
+    /// Loads a [`Theme`] from a reader providing its JSON
+    /// representation.
+    pub fn from_json_reader<R: std::io::Read>(reader: R) -> Result<Self, VisError> {
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    /// Loads a [`Theme`] from its TOML representation.
+    ///
+    /// On a syntax error, the position in the source is given by
+    /// [`VisError::get_position()`].
+    pub fn from_toml_str<S: AsRef<str>>(toml: S) -> Result<Self, VisError> {
+        let toml = toml.as_ref();
+
+        toml::from_str(toml).map_err(|err| VisError::toml_read(err, toml))
+    }
+
+    /// Saves the [`Theme`] in a pretty-printed JSON format.
+    pub fn to_json_string(&self) -> Result<String, VisError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Writes the [`Theme`] in a pretty-printed JSON format.
+    pub fn write_json<W: std::io::Write>(&self, writer: W) -> Result<(), VisError> {
+        Ok(serde_json::to_writer_pretty(writer, self)?)
+    }
+
+    /// Saves the [`Theme`] in a TOML format.
+    pub fn to_toml_string(&self) -> Result<String, VisError> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+}
+
+/// Removes entries of a `map`, whose names are names of other entries
+/// prefixed with `prefix` and a dash, i.e. entries generated by
+/// [`Theme::with_color_deficiency_variation()`].
+fn remove_renamed<T>(map: &mut HashMap<String, T>, prefix: &str) {
+    let generated: Vec<_> = map
+        .keys()
+        .filter(|name| {
+            name.strip_prefix(prefix)
+                .and_then(|name| name.strip_prefix('-'))
+                .is_some_and(|base_name| map.contains_key(base_name))
+        })
+        .cloned()
+        .collect();
+
+    for name in generated {
+        map.remove(&name);
+    }
+}
+
+/// Serializes with names sorted, for the sake of stable output.
+#[cfg(feature = "serialize")]
+impl serde::Serialize for Variation {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("Variation", 3)?;
+
+        state.serialize_field("strokes", &BTreeMap::from_iter(self.strokes.iter()))?;
+        state.serialize_field("fills", &BTreeMap::from_iter(self.fills.iter()))?;
+        state.serialize_field("variations", &BTreeMap::from_iter(self.variations.iter()))?;
+
+        state.end()
+    }
+}
+
+/// Serializes styles and markers in the order of their ids, and
+/// everything else with names sorted.
+#[cfg(feature = "serialize")]
+impl serde::Serialize for Theme {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut markers: Vec<_> = self
+            .named_markers
+            .iter()
+            .map(|(name, id)| Named { name: name.as_str(), value: &self.markers[id.0] })
+            .collect();
+        let mut fonts: Vec<_> = self.default_fonts.values().collect();
+        let mut styles: Vec<_> = self
+            .named_styles
+            .iter()
+            .map(|(name, id)| {
+                (id.0, Named { name: name.as_str(), value: self.styles[id.0].to_declared() })
+            })
+            .collect();
+
+        markers.sort_by_key(|entry| self.named_markers[entry.name].0);
+        fonts.sort_by_key(|font| font.get_class().as_css().to_string());
+        styles.sort_by_key(|(id, _)| *id);
+
+        let styles: Vec<_> = styles.into_iter().map(|(_, entry)| entry).collect();
+        let mut state = serializer.serialize_struct("Theme", 11)?;
+
+        state.serialize_field("strokes", &BTreeMap::from_iter(self.original.strokes.iter()))?;
+        state.serialize_field("fills", &BTreeMap::from_iter(self.original.fills.iter()))?;
+        state.serialize_field("gradients", &BTreeMap::from_iter(self.named_gradspecs.iter()))?;
+        state.serialize_field("hatches", &BTreeMap::from_iter(self.named_hatches.iter()))?;
+        state.serialize_field("markers", &markers)?;
+        state.serialize_field("fonts", &fonts)?;
+        state.serialize_field("embedded_fonts", &self.embedded_fonts)?;
+        state.serialize_field(
+            "font_fallbacks",
+            &BTreeMap::from_iter(self.font_fallbacks.iter()),
+        )?;
+        state.serialize_field(
+            "variations",
+            &BTreeMap::from_iter(self.original.variations.iter()),
+        )?;
+        state.serialize_field("styles", &styles)?;
+        state.serialize_field("color_space", &self.color_space)?;
+
+        state.end()
+    }
+}
+
+/// A named entry of a list, in which the order of entries matters.
+#[cfg(feature = "serialize")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Named<N, T> {
+    name:  N,
+    #[serde(flatten)]
+    value: T,
+}
+
+/// Deserialized contents of a [`Theme`].
+///
+/// Strokes and fills named `"default"` and `"scene"`, as well as the
+/// styles of these names, are defined in every theme, but may be
+/// overridden.
+#[cfg(feature = "serialize")]
+#[derive(serde::Deserialize)]
+#[serde(rename = "Theme")]
+struct ThemeData {
+    #[serde(default)]
+    strokes:        HashMap<String, Stroke>,
+    #[serde(default)]
+    fills:          HashMap<String, Fill>,
+    #[serde(default)]
+    gradients:      HashMap<String, Gradient>,
+    #[serde(default)]
+    hatches:        HashMap<String, Hatch>,
+    #[serde(default)]
+    markers:        Vec<Named<String, Marker>>,
+    #[serde(default)]
+    fonts:          Vec<Font>,
+    #[serde(default)]
+    embedded_fonts: Vec<EmbeddedFont>,
+    #[serde(default)]
+    font_fallbacks: HashMap<GenericFontFamily, Vec<String>>,
+    #[serde(default)]
+    variations:     HashMap<String, Variation>,
+    #[serde(default)]
+    styles:         Vec<Named<String, Style>>,
+    #[serde(default)]
+    color_space:    ColorSpace,
+}
+
+#[cfg(feature = "serialize")]
+impl TryFrom<ThemeData> for Theme {
+    type Error = VisError;
+
+    fn try_from(data: ThemeData) -> Result<Self, Self::Error> {
+        let ThemeData {
+            strokes,
+            fills,
+            gradients,
+            hatches,
+            markers,
+            fonts,
+            embedded_fonts,
+            font_fallbacks,
+            variations,
+            styles,
+            color_space,
+        } = data;
+        let mut theme = Theme::new().with_color_space(color_space);
+
+        theme.named_gradspecs.extend(gradients);
+        theme.font_fallbacks.extend(font_fallbacks);
+
+        theme
+            .with_strokes(strokes)
+            .with_fills(fills)
+            .with_hatches(hatches)
+            .with_variations(variations)
+            .with_markers(markers.into_iter().map(|entry| (entry.name, entry.value)))
+            .with_default_fonts(fonts)
+            .with_embedded_fonts(embedded_fonts)
+            .with_styles(styles.into_iter().map(|entry| (entry.name, entry.value)))
+            .build()
+    }
+}
+
+/// The part of a style painted with a color, see [`ContrastIssue`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PaintRole {
+    Stroke,
+    Fill,
+    /// The fill of a style with a font.
+    Text,
+}
+
+/// A color of a style, which doesn't contrast enough with the
+/// background, see [`Theme::audit()`].
+#[derive(Clone, Debug)]
+pub struct ContrastIssue {
+    style_name: String,
+    role:       PaintRole,
+    color:      Color,
+    bg_color:   Color,
+    ratio:      f64,
+}
+
+impl ContrastIssue {
+    #[inline]
+    pub fn get_style_name(&self) -> &str {
+        self.style_name.as_str()
+    }
+
+    #[inline]
+    pub fn get_role(&self) -> PaintRole {
+        self.role
+    }
+
+    #[inline]
+    pub fn get_color(&self) -> Color {
+        self.color
+    }
+
+    #[inline]
+    pub fn get_bg_color(&self) -> Color {
+        self.bg_color
+    }
+
+    #[inline]
+    pub fn get_ratio(&self) -> f64 {
+        self.ratio
+    }
+}
+
+impl std::fmt::Display for ContrastIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{:?} of style '{}': contrast ratio {:.2} of {} against {}",
+            self.role,
+            self.style_name,
+            self.ratio,
+            self.color.to_css_string(),
+            self.bg_color.to_css_string()
+        )
     }
 }
 
@@ -564,3 +1571,299 @@ impl<'a> Iterator for NamedMarkersIter<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Crumb;
+    use kurbo::Circle;
+
+    fn fill_style() -> Style {
+        Style::new().with_fill(Fill::Color(Color::WHITE))
+    }
+
+    #[test]
+    fn inherit_without_build() {
+        let theme = Theme::new().with_styles(vec![("derived", Style::new().extends("base"))]);
+
+        assert!(theme.get_style_by_name("derived").unwrap().get_fill().is_none());
+
+        let theme = theme.with_styles(vec![("base", fill_style())]);
+
+        assert_eq!(
+            theme.get_style_by_name("derived").unwrap().get_fill_color(),
+            Some(&Color::WHITE)
+        );
+    }
+
+    #[test]
+    fn style_cycle() {
+        let styles = vec![
+            ("first", fill_style().extends("second")),
+            ("second", Style::new().extends("third")),
+            ("third", Style::new().extends("first")),
+        ];
+        let err = Theme::new().with_styles(styles).build().unwrap_err();
+
+        assert!(err.to_string().starts_with("StyleCycleForName(\"first\"): in style 'first'"));
+
+        let styles = vec![("self", Style::new().extends("self"))];
+        let err = Theme::new().with_styles(styles).build().unwrap_err();
+
+        assert!(err.to_string().starts_with("StyleCycleForName(\"self\")"));
+    }
+
+    #[test]
+    fn style_missing_for_base() {
+        let styles = vec![("derived", Style::new().extends("base")), ("other", fill_style())];
+        let err = Theme::new().with_styles(styles).build().unwrap_err();
+
+        assert_eq!(err.to_string(), "StyleMissingForName(\"base\"): in style 'derived'");
+    }
+
+    #[test]
+    fn gradient_mismatch_for_name() {
+        let stops = vec![Color::WHITE, Color::BLACK];
+        let linear_gradients: Vec<(&str, UnitPoint, UnitPoint, &[Color])> = Vec::new();
+        let radial_gradients = vec![("glow", 1.0, stops.as_slice())];
+        let styles = vec![("area", Style::new().with_fill(Fill::Linear("glow".into())))];
+        let err = Theme::new()
+            .with_gradients(linear_gradients, radial_gradients)
+            .with_styles(styles)
+            .build()
+            .unwrap_err();
+
+        assert_eq!(err.to_string(), "GradientMismatchForName(\"glow\"): in style 'area'");
+    }
+
+    #[test]
+    fn gradient_missing_for_name() {
+        let area = Style::new().with_fill(Fill::Radial("glow".into()));
+        let styles = vec![("other", fill_style()), ("area", area)];
+        let err = Theme::new().with_styles(styles).build().unwrap_err();
+
+        assert_eq!(err.to_string(), "GradientMissingForName(\"glow\"): in style 'area'");
+    }
+
+    #[test]
+    fn hatch_missing_for_name() {
+        let area = Style::new().with_fill(Fill::Hatch("lines".into()));
+        let styles = vec![("other", fill_style()), ("area", area)];
+        let err = Theme::new().with_styles(styles).build().unwrap_err();
+
+        assert_eq!(err.to_string(), "HatchMissingForName(\"lines\"): in style 'area'");
+    }
+
+    #[test]
+    fn marker_missing_for_name() {
+        let markers = vec![("dot", Marker::new(Crumb::Circle(Circle::new((0.0, 0.0), 1.0))))];
+        let styles = vec![
+            ("line", Style::new().with_named_start_marker("dot")),
+            ("arrow", Style::new().with_named_start_marker("dot").with_named_end_marker("head")),
+        ];
+        let err = Theme::new().with_markers(markers).with_styles(styles).build().unwrap_err();
+
+        assert_eq!(err.to_string(), "MarkerMissingForName(\"head\"): in style 'arrow'");
+    }
+
+    #[test]
+    fn style_missing_for_marker() {
+        let dot = Marker::new(Crumb::Circle(Circle::new((0.0, 0.0), 1.0)));
+        let markers = vec![("plain", dot.clone()), ("dot", dot.with_named_style("dotted"))];
+        let err = Theme::new().with_markers(markers).build().unwrap_err();
+
+        assert_eq!(err.to_string(), "StyleMissingForName(\"dotted\"): in marker 'dot'");
+    }
+
+    #[test]
+    fn paint_missing_for_name() {
+        let strokes = vec![("faded", Stroke::new().with_named_gradient("fade"))];
+        let err = Theme::new().with_strokes(strokes).build().unwrap_err();
+
+        assert_eq!(err.to_string(), "GradientMissingForName(\"fade\"): in stroke 'faded'");
+
+        let fills = vec![("area", Fill::Color(Color::WHITE))];
+        let dark_fills = vec![("area", Fill::Hatch("lines".into()))];
+        let err = Theme::new()
+            .with_fills(fills)
+            .with_variations(vec![("dark", Variation::new().with_fills(dark_fills))])
+            .build()
+            .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "HatchMissingForName(\"lines\"): in fill 'area' of variation 'dark'"
+        );
+    }
+
+    #[test]
+    fn variation_stack_priorities() {
+        let red = Color::rgb8(0xff, 0, 0);
+        let edge = |brush| vec![("edge", Stroke::new().with_brush(brush))];
+        let variations = vec![
+            (
+                "dark",
+                Variation::new()
+                    .with_strokes(edge(Color::WHITE))
+                    .with_fills(vec![("edge", Fill::Color(Color::BLACK))]),
+            ),
+            ("contrast", Variation::new().with_strokes(edge(red))),
+        ];
+        let style = Style::new().with_named_stroke("edge").with_named_fill("edge");
+        let mut theme = Theme::new()
+            .with_strokes(edge(Color::BLACK))
+            .with_fills(vec![("edge", Fill::Color(Color::WHITE))])
+            .with_variations(variations)
+            .with_styles(vec![("edge", style)])
+            .build()
+            .unwrap();
+        let edge_id = theme.get("edge");
+        let mut stack =
+            VariationStack::new().with_variation(["contrast"], 2).with_variation(["dark"], 1);
+        let mut paint_with = |stack: &VariationStack| {
+            theme.use_variation_stack(stack);
+
+            let style = theme.get_style(edge_id).unwrap();
+
+            (*style.get_stroke().unwrap().get_brush(), style.get_fill_color().copied())
+        };
+
+        assert_eq!(paint_with(&stack), (red, Some(Color::BLACK)));
+
+        stack.push(["dark"], 2);
+
+        assert_eq!(paint_with(&stack), (Color::WHITE, Some(Color::BLACK)));
+
+        stack.push(["contrast"], 2);
+
+        assert_eq!(paint_with(&stack), (red, Some(Color::BLACK)));
+
+        stack.push(["contrast"], 0);
+
+        assert_eq!(paint_with(&stack), (Color::WHITE, Some(Color::BLACK)));
+        assert!(stack.remove(["dark"]));
+        assert_eq!(paint_with(&stack), (red, Some(Color::WHITE)));
+        assert!(!stack.remove(["dark"]));
+
+        stack.clear();
+
+        assert_eq!(paint_with(&stack), (Color::BLACK, Some(Color::WHITE)));
+    }
+
+    #[test]
+    fn audit_threshold() {
+        let strokes = vec![
+            ("faint", Stroke::new().with_brush(Color::rgb8(0xdd, 0xdd, 0xdd))),
+            ("strong", Stroke::new().with_brush(Color::BLACK)),
+        ];
+        let dark_fills = vec![(SCENE_NAME, Fill::Color(Color::BLACK))];
+        let styles = vec![
+            ("faint", Style::new().with_named_stroke("faint")),
+            ("strong", Style::new().with_named_stroke("strong")),
+            ("translucent", Style::new().with_fill(Fill::Color(Color::rgba8(0, 0, 0, 16)))),
+        ];
+        let mut theme = Theme::new()
+            .with_strokes(strokes)
+            .with_variations(vec![("dark", Variation::new().with_fills(dark_fills))])
+            .with_styles(styles)
+            .build()
+            .unwrap();
+        let names = |issues: Vec<ContrastIssue>| {
+            issues.iter().map(|issue| issue.get_style_name().to_string()).collect::<Vec<_>>()
+        };
+
+        assert!(theme.audit(1.0).is_empty());
+        assert_eq!(names(theme.audit(3.0)), ["default", "faint", "translucent"]);
+        assert_eq!(names(theme.audit(1.2)), ["default", "translucent"]);
+
+        let issue = &theme.audit(3.0)[1];
+
+        assert_eq!(issue.get_role(), PaintRole::Stroke);
+        assert_eq!(issue.get_color(), Color::rgb8(0xdd, 0xdd, 0xdd));
+        assert_eq!(issue.get_bg_color(), Color::WHITE);
+        assert!(issue.get_ratio() > 1.0 && issue.get_ratio() < 1.5);
+
+        theme.use_variation(["dark"]);
+
+        let issues = theme.audit(3.0);
+
+        assert_eq!(names(issues.clone()), ["default", "strong", "translucent"]);
+        assert_eq!(issues[0].get_role(), PaintRole::Stroke);
+        assert_eq!(issues[0].get_bg_color(), Color::BLACK);
+    }
+
+    #[test]
+    fn color_deficiency_idempotent() {
+        let sorted_names = |theme: &Theme| {
+            let mut names: Vec<_> = theme
+                .get_named_gradspecs()
+                .map(|(name, _)| name.clone())
+                .chain(theme.get_named_hatches().map(|(name, _)| name.clone()))
+                .collect();
+
+            names.sort();
+            names
+        };
+        let theme = Theme::simple_demo()
+            .with_hatches(vec![("hatch", Hatch::default())])
+            .with_color_deficiency_variation(ColorDeficiency::Protanopia);
+        let names = sorted_names(&theme);
+
+        assert_eq!(names.len(), 10);
+        assert!(names.contains(&"protanopia-gradient-v".to_string()));
+        assert!(names.contains(&"protanopia-hatch".to_string()));
+
+        let theme = theme.with_color_deficiency_variation(ColorDeficiency::Protanopia);
+
+        assert_eq!(sorted_names(&theme), names);
+        assert_eq!(
+            theme.get_stroke_by_path(["protanopia"], "circ-1").map(Stroke::get_brush),
+            Some(&ColorDeficiency::Protanopia.simulate(Color::rgb8(0xff, 0, 0)))
+        );
+    }
+
+    #[cfg(feature = "svg")]
+    #[test]
+    fn clip_ids_unique() {
+        let hatches = vec![("clip-0", Hatch::default()), ("_clip-0", Hatch::default())];
+        let theme = Theme::new().with_hatches(hatches);
+
+        assert_eq!(theme.get_clip_id(0), "__clip-0");
+        assert_eq!(theme.get_clip_id(1), "clip-1");
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn json_and_toml_round_trips() {
+        let theme = Theme::simple_demo();
+        let json = theme.to_json_string().unwrap();
+        let toml = theme.to_toml_string().unwrap();
+        let from_json = Theme::from_json_str(&json).unwrap();
+        let from_toml = Theme::from_toml_str(&toml).unwrap();
+
+        assert_eq!(from_json.to_json_string().unwrap(), json);
+        assert_eq!(from_toml.to_toml_string().unwrap(), toml);
+
+        for loaded in &[from_json, from_toml] {
+            assert_eq!(loaded.get("circ-1"), theme.get("circ-1"));
+            assert_eq!(loaded.get_stroke(loaded.get("rect-2")).map(Stroke::get_width), Some(1.0));
+            assert_eq!(
+                loaded.get_stroke_by_path(["dark"], "circ-1").map(Stroke::get_brush),
+                theme.get_stroke_by_path(["dark"], "circ-1").map(Stroke::get_brush)
+            );
+        }
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn error_positions() {
+        let err = Theme::from_toml_str("[strokes]\nline = \n").unwrap_err();
+
+        assert_eq!(err.get_position(), Some((2, 8)));
+        assert!(err.to_string().ends_with("at line 2 column 8"));
+
+        let err = Theme::from_json_str("{\n  \"styles\": [,]\n}").unwrap_err();
+
+        assert_eq!(err.get_position(), Some((2, 14)));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{Color, Rgba, Stroke, Fill};
+use crate::{Color, ColorSpace, Rgba, Stroke, Fill};
 
 pub trait Steppable: std::fmt::Debug {
     /// Note: `amount` is interpreted in tween space; the caller is
@@ -26,6 +26,16 @@ pub trait Tweenable: Steppable + Sized {
     fn breakdown(self, other: Self, max_inner: usize) -> Vec<Self> {
         vec![self, other]
     }
+
+    /// Same as [`Tweenable::breakdown()`], but colors are
+    /// interpolated in a given color `space`.
+    ///
+    /// Default implementation ignores `space` and calls
+    /// [`Tweenable::breakdown()`].
+    #[allow(unused_variables)]
+    fn breakdown_in_space(self, other: Self, max_inner: usize, space: ColorSpace) -> Vec<Self> {
+        self.breakdown(other, max_inner)
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -34,15 +44,23 @@ pub struct Tweener<T: Tweenable + Clone> {
     num_segments: f64,
     position:     f64,
     value:        T,
+    color_space:  ColorSpace,
 }
 
 impl<T: Tweenable + Clone> Tweener<T> {
     pub fn new(start: T, stop: T, max_inner: usize) -> Self {
+        Tweener::new_in_space(start, stop, max_inner, ColorSpace::default())
+    }
+
+    /// Creates a tweener, which interpolates colors in a given color
+    /// `space`, see [`Tweenable::breakdown_in_space()`].
+    pub fn new_in_space(start: T, stop: T, max_inner: usize, color_space: ColorSpace) -> Self {
         // eprintln!("Tweener {:?} -> {:?}", start, stop);
 
         let value = start.clone();
-        let breakpoints = start.breakdown(stop, max_inner);
-        let mut tweener = Tweener { breakpoints, num_segments: 0.0, position: 0.0, value };
+        let breakpoints = start.breakdown_in_space(stop, max_inner, color_space);
+        let mut tweener =
+            Tweener { breakpoints, num_segments: 0.0, position: 0.0, value, color_space };
 
         tweener.initialize();
 
@@ -75,7 +93,7 @@ impl<T: Tweenable + Clone> Tweener<T> {
     pub fn restart(&mut self, new_stop: T, max_inner: usize) {
         if let Some(new_start) = self.breakpoints.pop() {
             self.value.clone_from(&new_start);
-            self.breakpoints = new_start.breakdown(new_stop, max_inner);
+            self.breakpoints = new_start.breakdown_in_space(new_stop, max_inner, self.color_space);
         } else {
             self.value.clone_from(&new_stop);
             self.breakpoints = vec![new_stop; 2];
@@ -162,26 +180,6 @@ impl Easing for LinearEasing {
     }
 }
 
-impl Steppable for (f64, f64) {
-    fn step(&mut self, target: &Self, amount: f64) {
-        *self = (
-            (self.0 + (target.0 - self.0) * amount).clamp(0.0, 255.0),
-            (self.1 + (target.1 - self.1) * amount).clamp(0.0, 255.0),
-        );
-    }
-}
-
-impl Steppable for (f64, f64, f64, f64) {
-    fn step(&mut self, target: &Self, amount: f64) {
-        *self = (
-            (self.0 + (target.0 - self.0) * amount).clamp(-6.0, 12.0),
-            (self.1 + (target.1 - self.1) * amount).clamp(0.0, 1.0),
-            (self.2 + (target.2 - self.2) * amount).clamp(0.0, 255.0),
-            (self.3 + (target.3 - self.3) * amount).clamp(0.0, 255.0),
-        );
-    }
-}
-
 impl Steppable for Color {
     fn step(&mut self, target: &Self, amount: f64) {
         let Rgba::<f64>(r0, g0, b0, a0) = self.clone().into();
@@ -212,52 +210,85 @@ impl Steppable for Color {
 
 impl Tweenable for Color {
     fn breakdown(self, other: Self, max_inner: usize) -> Vec<Self> {
-        if max_inner > 0 {
-            let mut breakpoints = vec![self.clone()];
-            let num_segments = (max_inner + 1) as f64;
-
-            let (opt_h0, s0, v0, a0) = self.as_hsva();
-            let (opt_h1, s1, v1, a1) = other.as_hsva();
-
-            let (h0, h1) = if let Some(h0) = opt_h0 {
-                if let Some(h1) = opt_h1 {
-                    (h0, h1)
-                } else {
-                    (h0, h0)
-                }
-            } else if let Some(h1) = opt_h1 {
-                (h1, h1)
-            } else {
-                let mut inner = (v0, a0);
-                let target = (v1, a1);
+        self.breakdown_in_space(other, max_inner, ColorSpace::default())
+    }
 
-                for i in 0..max_inner {
-                    inner.step(&target, (i + 1) as f64 / num_segments);
-                    breakpoints.push(Color::from_hsva(0.0, 0.0, inner.0, inner.1));
-                }
+    /// Inner breakpoints are mixed in a given color `space`, see
+    /// [`Color::mix()`].  Colors are stepped linearly in sRGB between
+    /// breakpoints.
+    fn breakdown_in_space(self, other: Self, max_inner: usize, space: ColorSpace) -> Vec<Self> {
+        let num_segments = (max_inner + 1) as f64;
+        let mut breakpoints = vec![self];
 
-                breakpoints.push(other);
+        for i in 0..max_inner {
+            breakpoints.push(self.mix(other, (i + 1) as f64 / num_segments, space));
+        }
 
-                return breakpoints
-            };
+        breakpoints.push(other);
 
-            let mut inner = (h0, s0, v0, a0);
-            let target = (h1, s1, v1, a1);
+        breakpoints
+    }
+}
 
-            for i in 0..max_inner {
-                inner.step(&target, (i + 1) as f64 / num_segments);
-                breakpoints.push(Color::from_hsva(inner.0, inner.1, inner.2, inner.3));
-            }
+/// Maximal length of a dash array interpolated elementwise.
+const MAX_DASH_ARRAY_LEN: usize = 64;
+
+/// Brings two dash arrays to a common length, so that they may be
+/// interpolated elementwise, or returns `None` if that length would
+/// exceed [`MAX_DASH_ARRAY_LEN`].
+///
+/// Arrays are repeated as in SVG, i.e. odd-length arrays are doubled
+/// first.  An empty array (a solid line) is matched with the dashes
+/// of the other array and zero-length gaps.
+fn align_dash_arrays(a: &[f64], b: &[f64]) -> Option<(Vec<f64>, Vec<f64>)> {
+    fn to_even(dashes: &[f64]) -> Vec<f64> {
+        if dashes.len() % 2 == 1 {
+            dashes.iter().chain(dashes.iter()).copied().collect()
+        } else {
+            dashes.to_vec()
+        }
+    }
 
-            breakpoints.push(other);
+    fn to_solid(dashes: &[f64]) -> Vec<f64> {
+        dashes.iter().enumerate().map(|(i, v)| if i % 2 == 0 { *v } else { 0.0 }).collect()
+    }
 
-            breakpoints
+    fn gcd(a: usize, b: usize) -> usize {
+        if b == 0 {
+            a
         } else {
-            vec![self, other]
+            gcd(b, a % b)
         }
     }
+
+    let a = to_even(a);
+    let b = to_even(b);
+
+    if a.is_empty() {
+        let a = to_solid(&b);
+
+        return Some((a, b))
+    } else if b.is_empty() {
+        let b = to_solid(&a);
+
+        return Some((a, b))
+    }
+
+    let len = a.len() / gcd(a.len(), b.len()) * b.len();
+
+    if len > MAX_DASH_ARRAY_LEN {
+        None
+    } else {
+        let a = a.iter().cycle().take(len).copied().collect();
+        let b = b.iter().cycle().take(len).copied().collect();
+
+        Some((a, b))
+    }
 }
 
+/// Note: dash arrays are interpolated elementwise, if they may be
+/// brought to a common length, and switched halfway otherwise, just
+/// like gradients, line caps and joins.
 impl Steppable for Stroke {
     fn step(&mut self, target: &Self, amount: f64) {
         let brush = self.get_mut_brush();
@@ -265,28 +296,69 @@ impl Steppable for Stroke {
 
         let width = self.get_width();
         self.set_width(width + (target.get_width() - width) * amount);
+
+        let offset = self.get_dash_offset();
+        let offset = offset + (target.get_dash_offset() - offset) * amount;
+
+        let dashes = if self.get_dash_array() == target.get_dash_array() {
+            self.get_dash_array().to_vec()
+        } else if let Some((mut dashes, target_dashes)) =
+            align_dash_arrays(self.get_dash_array(), target.get_dash_array())
+        {
+            for (v0, v1) in dashes.iter_mut().zip(target_dashes) {
+                *v0 += (v1 - *v0) * amount;
+            }
+            dashes
+        } else if amount >= 0.5 {
+            target.get_dash_array().to_vec()
+        } else {
+            self.get_dash_array().to_vec()
+        };
+        self.set_dashes(dashes, offset);
+
+        let miter_limit = self.get_miter_limit();
+        self.set_miter_limit(miter_limit + (target.get_miter_limit() - miter_limit) * amount);
+
+        if amount >= 0.5 {
+            if let Some(name) = target.get_gradient_name() {
+                self.set_named_gradient(name);
+            } else {
+                self.clear_gradient();
+            }
+            self.set_cap(target.get_cap());
+            self.set_join(target.get_join());
+        }
     }
 }
 
 impl Tweenable for Stroke {
     fn breakdown(self, other: Self, max_inner: usize) -> Vec<Self> {
-        let brush = self.get_brush().clone().breakdown(other.get_brush().clone(), max_inner);
+        self.breakdown_in_space(other, max_inner, ColorSpace::default())
+    }
+
+    fn breakdown_in_space(self, other: Self, max_inner: usize, space: ColorSpace) -> Vec<Self> {
+        let brush = self.get_brush().clone().breakdown_in_space(
+            other.get_brush().clone(),
+            max_inner,
+            space,
+        );
         let num_breakpoints = brush.len();
 
         if num_breakpoints > 2 {
-            let w0 = self.get_width();
-            let w1 = other.get_width();
             let num_segments = (num_breakpoints - 1) as f64;
+            let mut breakpoints = vec![self.clone()];
+
+            for (i, b) in brush.into_iter().enumerate().take(num_breakpoints - 1).skip(1) {
+                let mut inner = self.clone();
 
-            brush
-                .into_iter()
-                .enumerate()
-                .map(|(i, b)| {
-                    Stroke::new()
-                        .with_brush(b)
-                        .with_width(w0 + (w1 - w0) * (i as f64 / num_segments))
-                })
-                .collect()
+                inner.step(&other, i as f64 / num_segments);
+                inner.set_brush(b);
+                breakpoints.push(inner);
+            }
+
+            breakpoints.push(other);
+
+            breakpoints
         } else {
             vec![self, other]
         }
@@ -300,23 +372,107 @@ impl Steppable for Fill {
                 Fill::Color(c1) => c0.step(c1, amount),
                 Fill::Linear(_name) => {} // FIXME
                 Fill::Radial(_name) => {} // FIXME
+                Fill::Hatch(_name) => {} // FIXME
             },
             Fill::Linear(_name) => {} // FIXME
             Fill::Radial(_name) => {} // FIXME
+            Fill::Hatch(_name) => {} // FIXME
         }
     }
 }
 
 impl Tweenable for Fill {
     fn breakdown(self, other: Self, max_inner: usize) -> Vec<Self> {
+        self.breakdown_in_space(other, max_inner, ColorSpace::default())
+    }
+
+    fn breakdown_in_space(self, other: Self, max_inner: usize, space: ColorSpace) -> Vec<Self> {
         match self {
             Fill::Color(ref c0) => match other {
-                Fill::Color(c1) => {
-                    c0.clone().breakdown(c1, max_inner).into_iter().map(Fill::Color).collect()
-                }
+                Fill::Color(c1) => c0
+                    .clone()
+                    .breakdown_in_space(c1, max_inner, space)
+                    .into_iter()
+                    .map(Fill::Color)
+                    .collect(),
                 _ => vec![self, other], // FIXME
             },
             _ => vec![self, other], // FIXME
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LineCap;
+
+    #[test]
+    fn dash_arrays_aligned() {
+        let (a, b) = align_dash_arrays(&[4.0, 2.0], &[1.0, 1.0]).unwrap();
+
+        assert_eq!((a, b), (vec![4.0, 2.0], vec![1.0, 1.0]));
+
+        // Odd-length arrays are doubled, then both cycle to the least
+        // common multiple of their lengths.
+        let (a, b) = align_dash_arrays(&[3.0], &[4.0, 2.0, 1.0, 1.0]).unwrap();
+
+        assert_eq!((a, b), (vec![3.0, 3.0, 3.0, 3.0], vec![4.0, 2.0, 1.0, 1.0]));
+
+        let (a, b) = align_dash_arrays(&[1.0, 2.0], &[5.0, 6.0, 7.0]).unwrap();
+
+        assert_eq!(a, vec![1.0, 2.0, 1.0, 2.0, 1.0, 2.0]);
+        assert_eq!(b, vec![5.0, 6.0, 7.0, 5.0, 6.0, 7.0]);
+    }
+
+    #[test]
+    fn dash_arrays_against_solid() {
+        let (a, b) = align_dash_arrays(&[], &[4.0, 2.0]).unwrap();
+
+        assert_eq!((a, b), (vec![4.0, 0.0], vec![4.0, 2.0]));
+
+        let (a, b) = align_dash_arrays(&[1.0, 2.0, 3.0], &[]).unwrap();
+
+        assert_eq!(a, vec![1.0, 2.0, 3.0, 1.0, 2.0, 3.0]);
+        assert_eq!(b, vec![1.0, 0.0, 3.0, 0.0, 2.0, 0.0]);
+        assert_eq!(align_dash_arrays(&[], &[]), Some((vec![], vec![])));
+    }
+
+    #[test]
+    fn dash_arrays_too_long() {
+        let a = vec![1.0; 10];
+        let b = vec![1.0; 14];
+
+        assert_eq!(align_dash_arrays(&a, &b).map(|(a, _)| a.len()), None);
+        assert_eq!(align_dash_arrays(&a, &b[..4]).map(|(a, _)| a.len()), Some(20));
+    }
+
+    #[test]
+    fn stroke_steps() {
+        let dashed = Stroke::new().with_width(1.0).with_dashes(vec![4.0, 2.0], 0.0);
+        let dotted =
+            Stroke::new().with_width(3.0).with_dashes(vec![1.0], 2.0).with_cap(LineCap::Round);
+        let mut stroke = dashed.clone();
+
+        stroke.step(&dotted, 0.25);
+
+        assert_eq!(stroke.get_width(), 1.5);
+        assert_eq!(stroke.get_dash_array(), &[3.25, 1.75]);
+        assert_eq!(stroke.get_dash_offset(), 0.5);
+        assert_eq!(stroke.get_cap(), LineCap::Butt);
+
+        stroke.step(&dotted, 1.0);
+
+        assert_eq!(stroke.get_dash_array(), &[1.0, 1.0]);
+        assert_eq!(stroke.get_cap(), LineCap::Round);
+
+        // Arrays which can't be aligned are switched halfway.
+        let odd = Stroke::new().with_dashes((1..=33).map(f64::from), 0.0);
+        let mut stroke = dashed.clone();
+
+        stroke.step(&odd, 0.4);
+        assert_eq!(stroke.get_dash_array(), &[4.0, 2.0]);
+        stroke.step(&odd, 0.5);
+        assert_eq!(stroke.get_dash_array().len(), 33);
+    }
+}
@@ -0,0 +1,801 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, hash_map::DefaultHasher},
+    hash::{Hash, Hasher},
+    sync::OnceLock,
+};
+use kurbo::{Point, Vec2, Rect, BezPath, Affine};
+use crate::{
+    TextLabel, TextMetrics, BaselineShift, Font, EmbeddedFont, StyleId, Theme, VisError, AsCss,
+    text::{Anchor, Item},
+    font::{GenericFontFamily, FontStyle},
+};
+
+/// A database of font faces, used for laying out, measuring and
+/// outlining the text of [`TextLabel`]s.
+///
+/// Each thread has a shared library, initially holding all fonts
+/// installed on the host, which is used by rendering backends and for
+/// computing bounding boxes of labels.  Fonts may be added to the
+/// shared library from a local directory or from embedded bytes, see
+/// [`FontLibrary::with_system_mut()`], or from a [`Theme`], see
+/// [`FontLibrary::load_theme_fonts()`].
+///
+/// Since bounds of labels depend on the fonts of a library, caches
+/// of measured bounds are keyed by [`FontLibrary::get_state_key()`].
+pub struct FontLibrary {
+    db:        fontdb::Database,
+    embedded:  Vec<EmbeddedFace>,
+    fallbacks: HashMap<GenericFontFamily, Vec<String>>,
+    loads_key: u64,
+    state_key: u64,
+}
+
+/// A face loaded from an [`EmbeddedFont`], found by the declared
+/// family name, weight and style, regardless of the names recorded
+/// in its data.  The face is told apart from faces of other fonts
+/// declared alike by the id of its [`EmbeddedFont`].
+struct EmbeddedFace {
+    family:  String,
+    weight:  u16,
+    style:   FontStyle,
+    font_id: u64,
+    face_id: fontdb::ID,
+}
+
+thread_local! {
+    static SYSTEM_LIBRARY: RefCell<FontLibrary> = RefCell::new(FontLibrary::with_system_fonts());
+}
+
+/// Faces of all fonts installed on the host, which are looked up once
+/// per process and copied into the shared library of each thread.
+static HOST_FONTS: OnceLock<fontdb::Database> = OnceLock::new();
+
+const GENERIC_FAMILIES: [GenericFontFamily; 5] = [
+    GenericFontFamily::Serif,
+    GenericFontFamily::SansSerif,
+    GenericFontFamily::Cursive,
+    GenericFontFamily::Monospace,
+    GenericFontFamily::Unspecified,
+];
+
+impl FontLibrary {
+    /// Creates an empty library.
+    pub fn new() -> Self {
+        FontLibrary {
+            db:        fontdb::Database::new(),
+            embedded:  Vec::new(),
+            fallbacks: HashMap::new(),
+            loads_key: 0,
+            state_key: 0,
+        }
+    }
+
+    /// Creates a library of all fonts installed on the host.  The
+    /// host is searched for fonts only once per process.
+    pub fn with_system_fonts() -> Self {
+        let mut library = FontLibrary::new();
+
+        library.db = HOST_FONTS
+            .get_or_init(|| {
+                let mut db = fontdb::Database::new();
+
+                db.load_system_fonts();
+
+                db
+            })
+            .clone();
+        library.mix_loads_key("system");
+
+        library
+    }
+
+    /// Calls `f` with the shared library of the current thread,
+    /// which is loaded with all fonts installed on the host on first
+    /// use.
+    pub fn with_system<T, F: FnOnce(&FontLibrary) -> T>(f: F) -> T {
+        SYSTEM_LIBRARY.with(|library| f(&library.borrow()))
+    }
+
+    /// Calls `f` with the shared library of the current thread, which
+    /// may be modified, e.g.
+    ///
+    /// ```ignore
+    /// FontLibrary::with_system_mut(|library| library.load_fonts_dir("assets/fonts"));
+    /// ```
+    ///
+    /// Panics, if called from within [`FontLibrary::with_system()`].
+    pub fn with_system_mut<T, F: FnOnce(&mut FontLibrary) -> T>(f: F) -> T {
+        SYSTEM_LIBRARY.with(|library| f(&mut library.borrow_mut()))
+    }
+
+    pub fn load_system_fonts(&mut self) {
+        self.db.load_system_fonts();
+        self.mix_loads_key("system");
+    }
+
+    /// Loads all fonts found in a directory and its subdirectories.
+    /// Malformed fonts are skipped.
+    pub fn load_fonts_dir<P: AsRef<std::path::Path>>(&mut self, dir: P) {
+        self.mix_loads_key(dir.as_ref());
+        self.db.load_fonts_dir(dir);
+    }
+
+    /// Loads a font, or all faces of a font collection, from TTF or
+    /// OTF bytes.
+    pub fn load_font_data(&mut self, data: Vec<u8>) {
+        self.mix_loads_key(data.as_slice());
+        self.db.load_font_data(data);
+    }
+
+    /// Returns a key of the fonts and fallback chains of this library,
+    /// which changes whenever they change.  Libraries loaded alike,
+    /// e.g. shared libraries of two threads which loaded the same
+    /// [`Theme`], have equal keys.
+    #[inline]
+    pub fn get_state_key(&self) -> u64 {
+        self.state_key
+    }
+
+    /// Records fonts loaded other than from an [`EmbeddedFont`].
+    fn mix_loads_key<H: Hash + ?Sized>(&mut self, load: &H) {
+        let mut hasher = DefaultHasher::new();
+
+        self.loads_key.hash(&mut hasher);
+        load.hash(&mut hasher);
+        self.loads_key = hasher.finish();
+        self.update_state_key();
+    }
+
+    fn update_state_key(&mut self) {
+        let mut hasher = DefaultHasher::new();
+
+        self.loads_key.hash(&mut hasher);
+
+        for face in self.embedded.iter() {
+            (&face.family, face.weight, face.style.as_css(), face.font_id).hash(&mut hasher);
+        }
+
+        for class in GENERIC_FAMILIES.iter() {
+            self.get_fallbacks(*class).hash(&mut hasher);
+        }
+
+        self.state_key = hasher.finish();
+    }
+
+    /// Loads the embedded fonts of a `theme`, unless already loaded,
+    /// and replaces fallback chains of generic families with those of
+    /// the `theme`.
+    pub fn load_theme_fonts(&mut self, theme: &Theme) -> Result<(), VisError> {
+        for font in theme.get_embedded_fonts() {
+            self.load_embedded_font(font)?;
+        }
+
+        self.fallbacks.clear();
+
+        for class in GENERIC_FAMILIES {
+            let names = theme.get_font_fallbacks(class);
+
+            if !names.is_empty() {
+                self.fallbacks.insert(class, names.to_vec());
+            }
+        }
+
+        self.update_state_key();
+
+        Ok(())
+    }
+
+    /// Loads an embedded `font`, unless a face of the same family,
+    /// weight and style is already loaded from the same font, or from
+    /// its clone.  A face loaded from another font is replaced and
+    /// removed from the library, so that themes embedding different
+    /// data under the same family name don't share faces.  Of a font
+    /// collection, only the first face is used.
+    pub fn load_embedded_font(&mut self, font: &EmbeddedFont) -> Result<(), VisError> {
+        let family = font.get_family_name();
+        let weight = font.get_weight().as_number();
+        let style = font.get_style();
+        let font_id = font.get_id();
+        let slot = self.embedded.iter().position(|face| {
+            face.family == family && face.weight == weight && face.style == *style
+        });
+
+        if matches!(slot, Some(ndx) if self.embedded[ndx].font_id == font_id) {
+            return Ok(())
+        }
+
+        let num_faces = self.db.len();
+
+        self.db.load_font_data(font.load_data()?.into_owned());
+
+        if let Some(info) = self.db.faces().get(num_faces) {
+            let face = EmbeddedFace {
+                family: family.to_string(),
+                weight,
+                style: style.clone(),
+                font_id,
+                face_id: info.id,
+            };
+
+            if let Some(ndx) = slot {
+                let old_face = std::mem::replace(&mut self.embedded[ndx], face);
+
+                self.db.remove_face(old_face.face_id);
+            } else {
+                self.embedded.push(face);
+            }
+
+            self.update_state_key();
+
+            Ok(())
+        } else {
+            Err(VisError::malformed_font_for_name(family))
+        }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.db.is_empty()
+    }
+
+    /// Returns the number of font faces in this library.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.db.len()
+    }
+
+    /// Finds the face best matching a `font`: its family names are
+    /// tried in order, then the fallback chain of its generic family,
+    /// then the generic family, then the sans-serif fallback chain,
+    /// and finally any sans-serif face.  Embedded faces take
+    /// precedence over host faces of the same family name.
+    pub(crate) fn query(&self, font: &Font) -> Option<fontdb::ID> {
+        let class = font.get_class();
+        let mut families: Vec<_> = font
+            .get_names()
+            .iter()
+            .chain(self.get_fallbacks(class))
+            .map(|name| fontdb::Family::Name(name.as_str()))
+            .collect();
+
+        match class {
+            GenericFontFamily::Serif => families.push(fontdb::Family::Serif),
+            GenericFontFamily::Cursive => families.push(fontdb::Family::Cursive),
+            GenericFontFamily::Monospace => families.push(fontdb::Family::Monospace),
+            GenericFontFamily::SansSerif | GenericFontFamily::Unspecified => {}
+        }
+
+        if class != GenericFontFamily::SansSerif {
+            families.extend(
+                self.get_fallbacks(GenericFontFamily::SansSerif)
+                    .iter()
+                    .map(|name| fontdb::Family::Name(name.as_str())),
+            );
+        }
+        families.push(fontdb::Family::SansSerif);
+
+        let weight = font.get_weight().as_number();
+        let style = font.get_style();
+
+        families.iter().find_map(|family| {
+            if let fontdb::Family::Name(name) = family {
+                if let Some(face_id) = self.query_embedded(name, weight, style) {
+                    return Some(face_id)
+                }
+            }
+
+            self.db.query(&fontdb::Query {
+                families: std::slice::from_ref(family),
+                weight:   fontdb::Weight(weight),
+                stretch:  fontdb::Stretch::Normal,
+                style:    match style {
+                    FontStyle::Normal => fontdb::Style::Normal,
+                    FontStyle::Italic => fontdb::Style::Italic,
+                    FontStyle::Oblique => fontdb::Style::Oblique,
+                },
+            })
+        })
+    }
+
+    /// Finds the embedded face of a family with the requested style,
+    /// if any, and the closest weight.
+    fn query_embedded(&self, family: &str, weight: u16, style: &FontStyle) -> Option<fontdb::ID> {
+        self.embedded
+            .iter()
+            .filter(|face| face.family == family)
+            .min_by_key(|face| (face.style != *style, (face.weight as i32 - weight as i32).abs()))
+            .map(|face| face.face_id)
+    }
+
+    fn get_fallbacks(&self, class: GenericFontFamily) -> &[String] {
+        self.fallbacks.get(&class).map(|names| names.as_slice()).unwrap_or(&[])
+    }
+
+    /// Calls `f` with a parsed face, or returns `None`, if the face
+    /// is missing or malformed.
+    pub(crate) fn with_face<T, F>(&self, face_id: fontdb::ID, f: F) -> Option<T>
+    where
+        F: FnOnce(&ttf_parser::Face) -> T,
+    {
+        self.db
+            .with_face_data(face_id, |data, index| {
+                ttf_parser::Face::from_slice(data, index).ok().map(|face| f(&face))
+            })
+            .flatten()
+    }
+
+    /// Returns a copy of the data of a font file (or collection)
+    /// containing a face.
+    #[cfg(feature = "fvg")]
+    pub(crate) fn get_face_data(&self, face_id: fontdb::ID) -> Option<Vec<u8>> {
+        self.db.with_face_data(face_id, |data, _| data.to_vec())
+    }
+
+    /// Lays out a `label` into runs of glyphs, in label coordinates.
+    ///
+    /// The root of a label is set in its own font, if any, or in
+    /// `default_font`.  Spans without an explicit font inherit the
+    /// font of their parent, with their own size, if any.  Styles and
+    /// letter spacing are inherited likewise, and baseline shifts are
+    /// resolved with the subscript and superscript metrics of the face
+    /// of a parent.
+    ///
+    /// Note: glyphs are mapped one-to-one from characters and placed
+    /// by their advances, without shaping or kerning.  Multi-line
+    /// labels have to be broken into lines first, see
+    /// [`TextLabel::break_lines()`].
+    pub fn layout(&self, label: &TextLabel, default_font: &Font) -> Vec<GlyphRun> {
+        self.lay_out_label(label, default_font).runs
+    }
+
+    /// Measures a `label` laid out by [`FontLibrary::layout()`], or
+    /// returns `None`, if there is no face for any of its characters.
+    pub fn measure(&self, label: &TextLabel, default_font: &Font) -> Option<TextMetrics> {
+        let layout = self.lay_out_label(label, default_font);
+        let mut runs = layout.runs.iter();
+        let first = runs.next()?;
+        let mut ascent = first.ascent;
+        let mut descent = first.descent;
+        let mut bounds = first.get_bounds();
+
+        for run in runs {
+            ascent = ascent.max(run.ascent);
+            descent = descent.max(run.descent);
+            bounds = bounds.union(run.get_bounds());
+        }
+
+        Some(TextMetrics {
+            advance: layout.chunk_widths.first().copied().unwrap_or(0.0),
+            ascent,
+            descent,
+            bounds,
+        })
+    }
+
+    fn lay_out_label<'a>(&'a self, label: &TextLabel, default_font: &Font) -> Layout<'a> {
+        let mut font = label.get_font().unwrap_or(default_font).clone();
+
+        if let Some(size) = label.get_font_size() {
+            font.set_size(size);
+        }
+
+        let mut layout = Layout {
+            library:        self,
+            runs:           Vec::new(),
+            chunk:          None,
+            chunk_widths:   Vec::new(),
+            pen:            Point::ZERO,
+            is_run_open:    false,
+            baseline:       0.0,
+            letter_spacing: label.get_letter_spacing().unwrap_or(0.0),
+            style_id:       label.get_style_id(),
+        };
+
+        layout.lay_out(label, &font, &[], &[]);
+        layout.close_chunk();
+
+        layout
+    }
+
+    /// Returns the outlines of all glyphs of a `run`, in label
+    /// coordinates.
+    pub fn outline(&self, run: &GlyphRun) -> BezPath {
+        let mut outliner = Outliner { path: BezPath::new(), ts: Affine::IDENTITY };
+
+        self.with_face(run.face_id, |face| {
+            let scale = run.font_size / f64::from(face.units_per_em().unwrap_or(1000));
+
+            for (glyph_id, offset) in run.glyphs.iter() {
+                outliner.ts = Affine::translate((run.origin.x + offset, run.origin.y))
+                    * Affine::scale_non_uniform(scale, -scale);
+                face.outline_glyph(ttf_parser::GlyphId(*glyph_id), &mut outliner);
+            }
+        });
+
+        outliner.path
+    }
+
+    /// Returns the outlines of all glyphs of a `label`, in label
+    /// coordinates, see [`FontLibrary::layout()`].
+    pub fn outline_label(&self, label: &TextLabel, default_font: &Font) -> BezPath {
+        let mut path = BezPath::new();
+
+        for run in self.layout(label, default_font) {
+            path.extend(self.outline(&run).elements().iter().copied());
+        }
+
+        path
+    }
+
+    /// Returns the outlines of all glyphs of a `label`, in label
+    /// coordinates, merged into a path per sequence of runs with the
+    /// same style, see [`GlyphRun::get_style_id()`].
+    pub fn outline_label_by_style(
+        &self,
+        label: &TextLabel,
+        default_font: &Font,
+    ) -> Vec<(Option<StyleId>, BezPath)> {
+        let mut paths: Vec<(Option<StyleId>, BezPath)> = Vec::new();
+
+        for run in self.layout(label, default_font) {
+            let path = self.outline(&run);
+
+            match paths.last_mut() {
+                Some((style_id, last_path)) if *style_id == run.style_id => {
+                    last_path.extend(path.elements().iter().copied());
+                }
+                _ => paths.push((run.style_id, path)),
+            }
+        }
+
+        paths
+    }
+}
+
+impl Default for FontLibrary {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for FontLibrary {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("FontLibrary").field("num_faces", &self.db.len()).finish()
+    }
+}
+
+/// A sequence of glyphs of a single face, set along a baseline
+/// without breaks, see [`FontLibrary::layout()`].
+#[derive(Clone, Debug)]
+pub struct GlyphRun {
+    face_id:   fontdb::ID,
+    font_size: f64,
+    origin:    Point,
+    text:      String,
+    glyphs:    Vec<(u16, f64)>,
+    advance:   f64,
+    ascent:    f64,
+    descent:   f64,
+    spacing:   f64,
+    style_id:  Option<StyleId>,
+}
+
+impl GlyphRun {
+    /// Returns the start of the baseline of this run, in label
+    /// coordinates.
+    #[inline]
+    pub fn get_origin(&self) -> Point {
+        self.origin
+    }
+
+    #[inline]
+    pub fn get_text(&self) -> &str {
+        self.text.as_str()
+    }
+
+    #[inline]
+    pub fn get_font_size(&self) -> f64 {
+        self.font_size
+    }
+
+    /// Returns the total advance of glyphs of this run, including
+    /// letter spacing.
+    #[inline]
+    pub fn get_advance(&self) -> f64 {
+        self.advance
+    }
+
+    /// Returns the extra space added after each glyph of this run.
+    #[inline]
+    pub fn get_letter_spacing(&self) -> f64 {
+        self.spacing
+    }
+
+    /// Returns the style of the innermost span containing this run,
+    /// if any has a style.
+    #[inline]
+    pub fn get_style_id(&self) -> Option<StyleId> {
+        self.style_id
+    }
+
+    /// Returns the ascent of the face of this run, scaled to its font
+    /// size.
+    #[inline]
+    pub fn get_ascent(&self) -> f64 {
+        self.ascent
+    }
+
+    /// Returns the descent of the face of this run, scaled to its
+    /// font size, as a positive number.
+    #[inline]
+    pub fn get_descent(&self) -> f64 {
+        self.descent
+    }
+
+    /// Returns the box spanned by the advances, ascent and descent of
+    /// this run, in label coordinates.
+    pub fn get_bounds(&self) -> Rect {
+        Rect::new(
+            self.origin.x,
+            self.origin.y - self.ascent,
+            self.origin.x + self.advance,
+            self.origin.y + self.descent,
+        )
+    }
+
+    #[cfg(feature = "fvg")]
+    #[inline]
+    pub(crate) fn get_face_id(&self) -> fontdb::ID {
+        self.face_id
+    }
+}
+
+/// A text chunk being laid out: its anchor, the index of its first
+/// run and the position at which it starts.
+struct Chunk {
+    anchor:    Anchor,
+    first_run: usize,
+    start_x:   f64,
+}
+
+struct Layout<'a> {
+    library:        &'a FontLibrary,
+    runs:           Vec<GlyphRun>,
+    chunk:          Option<Chunk>,
+    chunk_widths:   Vec<f64>,
+    pen:            Point,
+    is_run_open:    bool,
+    // Shift of the baseline of the current span, upwards.
+    baseline:       f64,
+    letter_spacing: f64,
+    style_id:       Option<StyleId>,
+}
+
+impl Layout<'_> {
+    /// Lays out a `label` (or a span) in a `font`, shifting its
+    /// characters by its own `dx` and `dy`, or by those inherited
+    /// from its parent, if missing.
+    fn lay_out(&mut self, label: &TextLabel, font: &Font, parent_dx: &[f64], parent_dy: &[f64]) {
+        if label.is_root() || label.get_origin().is_some() {
+            self.close_chunk();
+            self.pen = label.get_origin().unwrap_or_default();
+            self.chunk = Some(Chunk {
+                anchor:    label.get_anchor(),
+                first_run: self.runs.len(),
+                start_x:   self.pen.x,
+            });
+        }
+
+        let num_chars = label.get_num_chars();
+        let dx = merge_shifts(label.get_dx(), parent_dx, num_chars);
+        let dy = merge_shifts(label.get_dy(), parent_dy, num_chars);
+        let face_id = self.library.query(font);
+        let library = self.library;
+        let mut ndx = 0;
+
+        self.is_run_open = false;
+
+        for item in label.get_body() {
+            match item {
+                Item::Text(text) => {
+                    let len = text.chars().count();
+                    let dx = &dx[ndx..ndx + len];
+                    let dy = &dy[ndx..ndx + len];
+
+                    if let Some(face_id) = face_id {
+                        library.with_face(face_id, |face| {
+                            self.push_text(face, face_id, font.get_size(), text, dx, dy)
+                        });
+                    }
+                    ndx += len;
+                }
+                Item::Span(span) => {
+                    let span_font = if span.has_explicit_font() {
+                        span.get_font().cloned().unwrap_or_else(|| font.clone())
+                    } else {
+                        let mut span_font = font.clone();
+
+                        if let Some(size) = span.get_own_font_size() {
+                            span_font.set_size(size);
+                        }
+                        span_font
+                    };
+                    let len = span.get_num_chars();
+                    let baseline = self.baseline;
+                    let letter_spacing = self.letter_spacing;
+                    let style_id = self.style_id;
+
+                    if let Some(shift) = span.get_baseline_shift() {
+                        self.baseline += self.resolve_shift(shift, face_id, font.get_size());
+                    }
+                    if let Some(spacing) = span.get_letter_spacing() {
+                        self.letter_spacing = spacing;
+                    }
+                    if let Some(id) = span.get_style_id() {
+                        self.style_id = Some(id);
+                    }
+
+                    self.lay_out(span, &span_font, &dx[ndx..], &dy[ndx..]);
+                    self.is_run_open = false;
+                    self.baseline = baseline;
+                    self.letter_spacing = letter_spacing;
+                    self.style_id = style_id;
+                    ndx += len;
+                }
+            }
+        }
+    }
+
+    /// Returns the distance, by which a baseline is raised by a
+    /// `shift`, in a face of a given `font_size`.
+    fn resolve_shift(
+        &self,
+        shift: BaselineShift,
+        face_id: Option<fontdb::ID>,
+        font_size: f64,
+    ) -> f64 {
+        let metric = face_id.and_then(|face_id| {
+            self.library
+                .with_face(face_id, |face| {
+                    let scale = font_size / f64::from(face.units_per_em().unwrap_or(1000));
+
+                    match shift {
+                        BaselineShift::Sub => face
+                            .subscript_metrics()
+                            .map(|metrics| -f64::from(metrics.y_offset) * scale),
+                        BaselineShift::Super => face
+                            .superscript_metrics()
+                            .map(|metrics| f64::from(metrics.y_offset) * scale),
+                        BaselineShift::Length(_) => None,
+                    }
+                })
+                .flatten()
+        });
+
+        metric.unwrap_or_else(|| shift.estimate(font_size))
+    }
+
+    fn push_text(
+        &mut self,
+        face: &ttf_parser::Face,
+        face_id: fontdb::ID,
+        font_size: f64,
+        text: &str,
+        dx: &[f64],
+        dy: &[f64],
+    ) {
+        let scale = font_size / f64::from(face.units_per_em().unwrap_or(1000));
+        let spacing = self.letter_spacing;
+
+        for ((ch, dx), dy) in text.chars().zip(dx).zip(dy) {
+            let shift = Vec2::new(*dx, *dy);
+
+            if shift != Vec2::ZERO {
+                self.pen += shift;
+                self.is_run_open = false;
+            }
+
+            if !self.is_run_open {
+                self.runs.push(GlyphRun {
+                    face_id,
+                    font_size,
+                    origin: self.pen - Vec2::new(0.0, self.baseline),
+                    text: String::new(),
+                    glyphs: Vec::new(),
+                    advance: 0.0,
+                    ascent: f64::from(face.ascender()) * scale,
+                    descent: -f64::from(face.descender()) * scale,
+                    spacing,
+                    style_id: self.style_id,
+                });
+                self.is_run_open = true;
+            }
+
+            let glyph_id = face.glyph_index(ch).unwrap_or(ttf_parser::GlyphId(0));
+            let advance =
+                face.glyph_hor_advance(glyph_id).map_or(0.0, |v| f64::from(v) * scale) + spacing;
+
+            if let Some(run) = self.runs.last_mut() {
+                run.glyphs.push((glyph_id.0, run.advance));
+                run.text.push(ch);
+                run.advance += advance;
+            }
+
+            self.pen.x += advance;
+        }
+    }
+
+    /// Aligns the runs of the current chunk according to its anchor.
+    fn close_chunk(&mut self) {
+        if let Some(Chunk { anchor, first_run, start_x }) = self.chunk.take() {
+            let width = self.pen.x - start_x;
+            let shift = match anchor {
+                Anchor::Start => 0.0,
+                Anchor::Middle => -0.5 * width,
+                Anchor::End => -width,
+            };
+
+            for run in self.runs[first_run..].iter_mut() {
+                run.origin.x += shift;
+            }
+
+            self.chunk_widths.push(width);
+        }
+    }
+}
+
+/// Returns `num_chars` shifts, taken from `own`, or else from
+/// `inherited`, or else zero.
+fn merge_shifts(own: &[f64], inherited: &[f64], num_chars: usize) -> Vec<f64> {
+    (0..num_chars)
+        .map(|ndx| own.get(ndx).or_else(|| inherited.get(ndx)).copied().unwrap_or(0.0))
+        .collect()
+}
+
+/// Collects glyph outlines, transformed from font units to label
+/// coordinates.
+struct Outliner {
+    path: BezPath,
+    ts:   Affine,
+}
+
+impl Outliner {
+    #[inline]
+    fn point(&self, x: f32, y: f32) -> Point {
+        self.ts * Point::new(f64::from(x), f64::from(y))
+    }
+}
+
+impl ttf_parser::OutlineBuilder for Outliner {
+    fn move_to(&mut self, x: f32, y: f32) {
+        let p = self.point(x, y);
+
+        self.path.move_to(p);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        let p = self.point(x, y);
+
+        self.path.line_to(p);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let p1 = self.point(x1, y1);
+        let p = self.point(x, y);
+
+        self.path.quad_to(p1, p);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let p1 = self.point(x1, y1);
+        let p2 = self.point(x2, y2);
+        let p = self.point(x, y);
+
+        self.path.curve_to(p1, p2, p);
+    }
+
+    fn close(&mut self) {
+        self.path.close_path();
+    }
+}